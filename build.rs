@@ -0,0 +1,214 @@
+//! Generates the `Instruction` enum and its op code tables from `resources/instructions.json`
+//!
+//! The generated code is included by `src/cpu/instructions/instructions.rs`
+
+use std::{collections::BTreeMap, env, fmt::Write as _, fs, path::Path};
+
+const INSTRUCTIONS_JSON: &str = "resources/instructions.json";
+
+/// Single entry of `instructions.json`
+struct OpCode {
+    op: u8,
+    name: String,
+    code_name: String,
+    addr_mode: String,
+    bytes: usize,
+    cycles: usize,
+    desc: String,
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed={INSTRUCTIONS_JSON}");
+    println!("cargo:rerun-if-changed=build.rs");
+
+    let json = fs::read_to_string(INSTRUCTIONS_JSON).expect("Could not read instruction table");
+    let mut ops = parse_opcodes(&json);
+    ops.sort_by_key(|op| op.op);
+
+    for (i, op) in ops.iter().enumerate() {
+        assert_eq!(op.op as usize, i, "{INSTRUCTIONS_JSON} must have exactly one entry for every op code, ${i:02X} is missing or duplicated");
+    }
+    assert_eq!(ops.len(), 256, "{INSTRUCTIONS_JSON} must have exactly one entry for every op code");
+
+    let out = Path::new(&env::var("OUT_DIR").unwrap()).join("instructions.rs");
+    fs::write(out, generate(&ops)).expect("Could not write generated instruction table");
+}
+
+/// Name of the `AddressingMode` variant used for an addressing mode in `instructions.json`
+///
+/// Stack instructions and instructions without arguments in memory are `Implied`,
+/// their implementations take care of their own arguments
+fn addressing_mode(mode: &str) -> &'static str {
+    match mode {
+        "Absolute" => "Absolute",
+        "Absolute Indexed,X" => "AbsoluteX",
+        "Absolute Indexed,Y" => "AbsoluteY",
+        "Absolute Indirect" => "AbsoluteIndirect",
+        "Absolute Indexed Indirect" => "AbsoluteIndirectX",
+        "Absolute Indirect Long" => "AbsoluteIndirectLong",
+        "Absolute Long" => "Long",
+        "Absolute Long Indexed,X" => "LongX",
+        "Direct Page" => "Direct",
+        "DP Indexed,X" => "DirectX",
+        "DP Indexed,Y" => "DirectY",
+        "DP Indirect" => "Indirect",
+        "DP Indexed Indirect,X" => "IndirectX",
+        "DP Indirect Indexed, Y" => "IndirectY",
+        "DP Indirect Long" => "IndirectLong",
+        "DP Indirect Long Indexed, Y" => "IndirectLongY",
+        "Immediate" => "Immediate",
+        "Program Counter Relative" => "Relative",
+        "Program Counter Relative Long" => "RelativeLong",
+        "Stack Relative" => "StackRelative",
+        "SR Indirect Indexed,Y" => "StackRelativeY",
+        "Block Move" => "Move",
+        "" | "Implied" | "Accumulator" | "Stack/Interrupt" | "Stack (Absolute)" | "Stack (DP Indirect)" |
+        "Stack (PC Relative Long)" | "Stack (Push)" | "Stack (Pull)" | "Stack (RTI)" | "Stack (RTL)" | "Stack (RTS)" => "Implied",
+        _ => panic!("Unknown addressing mode \"{mode}\" in {INSTRUCTIONS_JSON}"),
+    }
+}
+
+fn generate(ops: &[OpCode]) -> String {
+    let mut s = String::new();
+    s.push_str("// Generated by build.rs from resources/instructions.json, do not edit\n\n");
+
+    s.push_str("#[derive(Debug, Clone, Copy, PartialEq, Eq)]\npub enum Instruction {\n");
+    for op in ops {
+        writeln!(s, "    /// (${:02X}) {}, {}\n    ///\n    /// * Byte length: {}\n    /// * Cycles: {}\n    {},\n",
+            op.op, op.name, op.desc, op.bytes, op.cycles, op.code_name).unwrap();
+    }
+    s.push_str("}\n\nimpl Instruction {\n");
+
+    write_table(&mut s, ops, "/// Returns `Instruction` enum variant from op code\n    pub fn from_op(op: u8) -> Instruction",
+        "op", |op| format!("0x{:02X} => Instruction::{}", op.op, op.code_name));
+
+    write_match(&mut s, ops, "/// Op code of this instruction\n    pub fn get_opcode(&self) -> u8", |op| format!("0x{:02X}", op.op));
+
+    write_match(&mut s, ops, "/// Mnemonic of this instruction in assembly, ie `LDA`\n    pub fn get_mnemonic(&self) -> &'static str",
+        |op| format!("{:?}", op.name));
+
+    write_match(&mut s, ops, "/// Description of this instruction, ie `Load Accumulator from Memory (Immediate)`\n    pub fn get_description(&self) -> &'static str",
+        |op| format!("{:?}", op.desc));
+
+    write_match(&mut s, ops, "/// Length of this instruction in bytes when all registers are in 8 bit mode\n    pub(super) fn get_base_length(&self) -> usize",
+        |op| op.bytes.to_string());
+
+    write_match(&mut s, ops, "/// Get base cycle duration of this instruction, with all registers in 8 bit mode and without any penalties.\n    \
+        /// `Cpu::get_cycle_count` adds the penalties that depend on the CPU state. `MVN` and `MVP` take this time per byte moved\n    \
+        pub fn get_cycle_time(&self) -> usize",
+        |op| op.cycles.to_string());
+
+    write_match(&mut s, ops, "/// Addressing mode of this instruction when all registers are in 8 bit mode\n    pub(super) fn get_base_addressing_mode(&self) -> super::AddressingMode",
+        |op| format!("super::AddressingMode::{}", addressing_mode(&op.addr_mode)));
+
+    s.push_str("}\n");
+    s
+}
+
+/// Writes a function that matches on `arg` with one arm per op code
+fn write_table(s: &mut String, ops: &[OpCode], signature: &str, arg: &str, arm: impl Fn(&OpCode) -> String) {
+    writeln!(s, "    {signature} {{\n        match {arg} {{").unwrap();
+    for op in ops {
+        writeln!(s, "            {},", arm(op)).unwrap();
+    }
+    s.push_str("        }\n    }\n\n");
+}
+
+/// Writes a function on `&self` that groups instructions with the same result into a single arm
+fn write_match(s: &mut String, ops: &[OpCode], signature: &str, value: impl Fn(&OpCode) -> String) {
+    let mut arms: BTreeMap<String, Vec<&str>> = BTreeMap::new();
+    for op in ops {
+        arms.entry(value(op)).or_default().push(&op.code_name);
+    }
+
+    writeln!(s, "    {signature} {{\n        use Instruction::*;\n        match self {{").unwrap();
+    for (value, names) in arms {
+        writeln!(s, "            {} => {value},", names.join(" | ")).unwrap();
+    }
+    s.push_str("        }\n    }\n\n");
+}
+
+fn parse_opcodes(json: &str) -> Vec<OpCode> {
+    let mut parser = JsonParser { chars: json.chars().peekable() };
+    parser.parse_array().into_iter().map(|entry| {
+        let field = |key: &str| entry.get(key).unwrap_or_else(|| panic!("Missing \"{key}\" in {INSTRUCTIONS_JSON}")).clone();
+        let number = |key: &str| field(key).parse().unwrap_or_else(|_| panic!("\"{key}\" is not a number in {INSTRUCTIONS_JSON}"));
+        OpCode {
+            op: u8::from_str_radix(&field("op"), 16).expect("Op code is not a hexadecimal byte"),
+            name: field("name"),
+            code_name: field("code_name"),
+            addr_mode: field("addr_mode"),
+            bytes: number("bytes"),
+            cycles: number("cycles"),
+            desc: field("desc"),
+        }
+    }).collect()
+}
+
+/// Parser for the subset of JSON used by `instructions.json`: an array of objects with string values
+struct JsonParser<I: Iterator<Item = char>> {
+    chars: std::iter::Peekable<I>,
+}
+
+impl<I: Iterator<Item = char>> JsonParser<I> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+
+    fn expect(&mut self, expected: char) {
+        self.skip_whitespace();
+        match self.chars.next() {
+            Some(c) if c == expected => {},
+            c => panic!("Expected '{expected}' in {INSTRUCTIONS_JSON}, found {c:?}"),
+        }
+    }
+
+    /// Parses the items of a list between `open` and `close` separated by commas
+    fn parse_list<T>(&mut self, open: char, close: char, mut item: impl FnMut(&mut Self) -> T) -> Vec<T> {
+        self.expect(open);
+        let mut items = Vec::new();
+        self.skip_whitespace();
+        if self.chars.next_if_eq(&close).is_some() {
+            return items;
+        }
+        loop {
+            items.push(item(self));
+            self.skip_whitespace();
+            match self.chars.next() {
+                Some(',') => {},
+                Some(c) if c == close => return items,
+                c => panic!("Expected ',' or '{close}' in {INSTRUCTIONS_JSON}, found {c:?}"),
+            }
+        }
+    }
+
+    fn parse_array(&mut self) -> Vec<BTreeMap<String, String>> {
+        self.parse_list('[', ']', |p| p.parse_object())
+    }
+
+    fn parse_object(&mut self) -> BTreeMap<String, String> {
+        self.parse_list('{', '}', |p| {
+            let key = p.parse_string();
+            p.expect(':');
+            (key, p.parse_string())
+        }).into_iter().collect()
+    }
+
+    fn parse_string(&mut self) -> String {
+        self.expect('"');
+        let mut s = String::new();
+        loop {
+            match self.chars.next() {
+                Some('"') => return s,
+                Some('\\') => match self.chars.next() {
+                    Some('n') => s.push('\n'),
+                    Some('t') => s.push('\t'),
+                    Some(c @ ('"' | '\\' | '/')) => s.push(c),
+                    c => panic!("Unsupported escape {c:?} in {INSTRUCTIONS_JSON}"),
+                },
+                Some(c) => s.push(c),
+                None => panic!("Unterminated string in {INSTRUCTIONS_JSON}"),
+            }
+        }
+    }
+}
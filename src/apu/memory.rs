@@ -1,3 +1,5 @@
+use crate::savestate::{Snapshot, StateWriter, StateReader, SaveStateError};
+
 pub struct ApuMemory {
     
 }
@@ -8,4 +10,13 @@ impl ApuMemory {
             
         }
     }
-}
\ No newline at end of file
+}
+
+/// The APU is not emulated yet, so there is no state to save. This keeps its place in the save state format
+impl Snapshot for ApuMemory {
+    fn save_state(&self, _w: &mut StateWriter) {}
+
+    fn load_state(&mut self, _r: &mut StateReader) -> Result<(), SaveStateError> {
+        Ok(())
+    }
+}
@@ -1,6 +1,6 @@
 use std::sync::{Arc, Mutex};
 
-use crate::arc_mut;
+use crate::{arc_mut, savestate::{Snapshot, StateWriter, StateReader, SaveStateError}};
 
 use self::memory::ApuMemory;
 
@@ -24,4 +24,14 @@ impl Apu {
         self.memory = memref;
     }
 
-}
\ No newline at end of file
+}
+
+impl Snapshot for Apu {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.memory.lock().unwrap().save_state(w);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), SaveStateError> {
+        self.memory.lock().unwrap().load_state(r)
+    }
+}
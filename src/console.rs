@@ -0,0 +1,325 @@
+use std::{sync::{Arc, mpsc, atomic::{AtomicBool, Ordering}}, thread, time::Instant};
+
+use pix_engine::prelude::{Engine, PixResult};
+
+use crate::{
+    arc_mut,
+    cpu::{Cpu, CpuError, debug::Step, instructions::interrupt::Interrupt, memory::cartridge::CartridgeParseError},
+    ppu::{Ppu, memory::PpuMemory},
+    apu::{Apu, memory::ApuMemory},
+    savestate::{Snapshot, StateWriter, StateReader, SaveStateError, SaveStateHeader, slots::{SaveSlots, SlotRequest}, rewind::RewindBuffer},
+    region::MASTER_CLOCKS_PER_DOT,
+};
+
+/// Window is this many times the size of a frame when it opens
+const DEFAULT_WINDOW_SCALE: u32 = 3;
+
+/// The whole console: CPU, PPU and APU connected through their shared memory
+/// 
+/// The CPU drives the console: after every instruction the PPU runs for the master clock cycles the instruction took.
+/// Interrupts are only raised by `Console::raise_nmi` and `Console::raise_irq`, since nothing generates them yet
+pub struct Console {
+    pub cpu: Cpu,
+    pub ppu: Ppu,
+    pub apu: Apu,
+    /// Snapshots for rewinding, `None` when rewinding is disabled
+    rewind: Option<RewindBuffer>,
+    nmi_pending: bool,
+    irq_pending: bool,
+    /// Master clock cycles the CPU ran ahead of the PPU
+    ppu_clocks: usize,
+    /// Number of frames that were started since power on
+    frames: u64,
+}
+
+impl Console {
+    pub fn new() -> Console {
+        let mut cpu = Cpu::new();
+        let mut ppu = Ppu::new();
+        let mut apu = Apu::new();
+
+        let ppumem = arc_mut!(PpuMemory::new());
+        cpu.memory.set_ppumemory_ref(ppumem.clone());
+        ppu.set_ppumemory_ref(ppumem);
+
+        let apumem = arc_mut!(ApuMemory::new());
+        cpu.memory.set_apumemory_ref(apumem.clone());
+        apu.set_apumemory_ref(apumem);
+
+        Console { cpu, ppu, apu, rewind: None, nmi_pending: false, irq_pending: false, ppu_clocks: 0, frames: 0 }
+    }
+
+    /// Inserts a cartridge and resets the CPU, so execution starts at the reset vector of the ROM
+    pub fn insert_cartridge(&mut self, rom: &[u8]) -> Result<(), CartridgeParseError> {
+        self.cpu.memory.insert_cartridge(rom)?;
+        self.cpu.reset();
+        Ok(())
+    }
+
+    /// Checksum from the header of the inserted ROM, save states are only loaded into the ROM they were made with
+    pub fn rom_checksum(&self) -> u16 {
+        self.cpu.memory.cartridge_metadata.get_checksum()
+    }
+
+    /// Runs the console without a window until `frames` frames have been drawn
+    pub fn run_headless(&mut self, frames: usize) -> Result<(), CpuError> {
+        for _ in 0..frames {
+            self.run_frame()?;
+        }
+        Ok(())
+    }
+
+    /// Runs the console until the next frame starts, capturing a rewind snapshot when one is due
+    pub fn run_frame(&mut self) -> Result<(), CpuError> {
+        let frame = self.frames;
+        while self.frames == frame {
+            self.step()?;
+        }
+
+        if self.rewind.as_mut().is_some_and(|rewind| rewind.advance_frame()) {
+            let mut w = StateWriter::with_capacity(self.rewind.as_ref().map_or(0, |r| r.snapshot_size_hint()));
+            self.save_machine(&mut w);
+            if let Some(rewind) = &mut self.rewind {
+                rewind.push(w.into_bytes());
+            }
+        }
+        Ok(())
+    }
+
+    /// Executes one instruction, or enters a pending interrupt, and runs the PPU for the time it took
+    pub fn step(&mut self) -> Result<Step, CpuError> {
+        let nmi = std::mem::take(&mut self.nmi_pending);
+        let step = self.cpu.step(nmi, self.irq_pending)?;
+        if step.interrupt == Some(Interrupt::Irq) {
+            self.irq_pending = false;
+        }
+        self.run_ppu(step.cycles);
+        Ok(step)
+    }
+
+    /// Enters the NMI handler before the next instruction
+    pub fn raise_nmi(&mut self) {
+        self.nmi_pending = true;
+    }
+
+    /// Holds the IRQ line until the IRQ handler is entered, which waits until IRQs are enabled
+    pub fn raise_irq(&mut self) {
+        self.irq_pending = true;
+    }
+
+    /// Runs the PPU for `cycles` master clock cycles, keeping the cycles that don't make up a whole dot for the next time
+    fn run_ppu(&mut self, cycles: usize) {
+        self.ppu_clocks += cycles;
+        while self.ppu_clocks >= MASTER_CLOCKS_PER_DOT {
+            self.ppu_clocks -= MASTER_CLOCKS_PER_DOT;
+            self.ppu.tick();
+            if self.ppu.at_frame_start() {
+                self.frames += 1;
+                if let Some(tracer) = self.cpu.tracer_mut() {
+                    tracer.next_frame();
+                }
+            }
+        }
+
+        let (h, v) = self.ppu.beam_position();
+        if let Some(tracer) = self.cpu.tracer_mut() {
+            tracer.set_beam_position(v as u16, h as u16);
+        }
+    }
+
+    /// Enables rewinding with a snapshot every `interval` frames, using at most `budget` bytes. Replaces any earlier snapshots
+    pub fn enable_rewind(&mut self, interval: usize, budget: usize) {
+        self.rewind = Some(RewindBuffer::new(interval, budget));
+    }
+
+    pub fn disable_rewind(&mut self) {
+        self.rewind = None;
+    }
+
+    /// Restores the newest rewind snapshot and removes it from the buffer, so calling this repeatedly steps further back
+    /// 
+    /// Returns false if there is nothing to rewind to
+    pub fn step_back(&mut self) -> bool {
+        let Some(snapshot) = self.rewind.as_mut().and_then(|rewind| rewind.pop()) else {
+            return false;
+        };
+        match self.restore_machine(&snapshot) {
+            Ok(()) => true,
+            Err(e) => {
+                // Snapshots are made by this console, so this only happens if the ROM was swapped since
+                eprintln!("Failed to rewind: {e}");
+                if let Some(rewind) = &mut self.rewind {
+                    rewind.clear();
+                }
+                false
+            },
+        }
+    }
+
+    /// Serializes the entire machine, including a header with the ROM checksum and a thumbnail of the last frame
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut w = StateWriter::new();
+        SaveStateHeader::new(self.rom_checksum(), self.ppu.thumbnail()).write(&mut w);
+        self.save_machine(&mut w);
+        w.into_bytes()
+    }
+
+    /// Restores a state made by `save_state`, returns its header
+    /// 
+    /// The state is rejected if it was made with another ROM or another version of the format.
+    /// If the state turns out to be invalid halfway, the machine is put back the way it was before loading
+    pub fn load_state(&mut self, state: &[u8]) -> Result<SaveStateHeader, SaveStateError> {
+        let mut r = StateReader::new(state);
+        let header = SaveStateHeader::read(&mut r)?;
+        if header.rom_checksum != self.rom_checksum() {
+            return Err(SaveStateError::RomMismatch { expected: self.rom_checksum(), found: header.rom_checksum });
+        }
+
+        self.restore_machine(r.remaining())?;
+        if let Some(rewind) = &mut self.rewind {
+            rewind.clear();
+        }
+        Ok(header)
+    }
+
+    /// Saves the entire machine to quick save slot `slot`
+    pub fn save_slot(&self, slot: usize) -> Result<(), SaveStateError> {
+        SaveSlots::new(self.rom_checksum()).write(slot, &self.save_state())
+    }
+
+    /// Loads quick save slot `slot`, see `Console::load_state`
+    pub fn load_slot(&mut self, slot: usize) -> Result<SaveStateHeader, SaveStateError> {
+        let state = SaveSlots::new(self.rom_checksum()).read(slot)?;
+        self.load_state(&state)
+    }
+
+    fn save_machine(&self, w: &mut StateWriter) {
+        self.cpu.save_state(w);
+        self.ppu.save_state(w);
+        self.apu.save_state(w);
+    }
+
+    fn load_machine(&mut self, r: &mut StateReader) -> Result<(), SaveStateError> {
+        self.cpu.load_state(r)?;
+        self.ppu.load_state(r)?;
+        self.apu.load_state(r)
+    }
+
+    /// Loads a snapshot without header, if the snapshot turns out to be invalid halfway the machine is put back the way it was
+    fn restore_machine(&mut self, snapshot: &[u8]) -> Result<(), SaveStateError> {
+        let mut backup = StateWriter::new();
+        self.save_machine(&mut backup);
+
+        if let Err(e) = self.load_machine(&mut StateReader::new(snapshot)) {
+            self.load_machine(&mut StateReader::new(&backup.into_bytes()))
+                .expect("restoring the state from before loading failed");
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    fn handle_slot_request(&mut self, request: SlotRequest) {
+        match request {
+            SlotRequest::Save(slot) => match self.save_slot(slot) {
+                Ok(()) => println!("Saved state to slot {slot}"),
+                Err(e) => eprintln!("Failed to save state to slot {slot}: {e}"),
+            },
+            SlotRequest::Load(slot) => match self.load_slot(slot) {
+                Ok(_) => println!("Loaded state from slot {slot}"),
+                Err(e) => eprintln!("Failed to load state from slot {slot}: {e}"),
+            },
+        }
+    }
+
+    /// Opens the emulator window and runs the console on a separate thread until the window is closed
+    /// 
+    /// The console is paced to the frame rate of its region, the window presents every completed frame with vsync.
+    /// Quick save and quick load requests from the window are handled between frames.
+    /// While the rewind key is held the console steps back one snapshot per frame instead of running
+    pub fn run(&mut self) -> PixResult<()> {
+        let (sender, requests) = mpsc::channel();
+        let rewinding = Arc::new(AtomicBool::new(false));
+        let mut presenter = self.ppu.create_presenter();
+        presenter.set_slot_requests(sender);
+        presenter.set_rewind_flag(rewinding.clone());
+        let (width, height) = presenter.window_size(DEFAULT_WINDOW_SCALE, self.ppu.visible_lines());
+
+        let mut engine = Engine::builder()
+            .dimensions(width, height)
+            .title("snesemu")
+            .show_frame_rate()
+            .resizable()
+            .vsync_enabled()
+            .build()?;
+
+        let running = AtomicBool::new(true);
+        thread::scope(|scope| {
+            let running = &running;
+            scope.spawn(move || {
+                while running.load(Ordering::Relaxed) {
+                    let t = Instant::now();
+                    if rewinding.load(Ordering::Relaxed) {
+                        self.step_back();
+                    } else if let Err(e) = self.run_frame() {
+                        eprintln!("CPU error: {e:?}");
+                        break;
+                    }
+                    for request in requests.try_iter() {
+                        self.handle_slot_request(request);
+                    }
+                    if let Some(remaining) = self.ppu.get_region().frame_time().checked_sub(t.elapsed()) {
+                        thread::sleep(remaining);
+                    }
+                }
+            });
+
+            let result = engine.run(&mut presenter);
+            running.store(false, Ordering::Relaxed);
+            result
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cpu::debug::Register;
+
+    /// Machine part of a save state, without the header which has a timestamp
+    fn machine_state(console: &Console) -> Vec<u8> {
+        let state = console.save_state();
+        let mut r = StateReader::new(&state);
+        SaveStateHeader::read(&mut r).unwrap();
+        r.remaining().to_vec()
+    }
+
+    #[test]
+    fn test_save_load_round_trip() {
+        let mut console = Console::new();
+        console.cpu.memory.poke(0x7E2000, 0x42);
+        console.cpu.memory.poke(0x7FFFFF, 0x24);
+        console.cpu.set_register(Register::X, 0x1234);
+        console.run_headless(1).unwrap();
+        let state = console.save_state();
+
+        let mut loaded = Console::new();
+        loaded.load_state(&state).unwrap();
+        assert_eq!(loaded.cpu.memory.peek(0x7E2000), Some(0x42));
+        assert_eq!(loaded.cpu.memory.peek(0x7FFFFF), Some(0x24));
+        assert_eq!(loaded.cpu.registers().x, console.cpu.registers().x);
+        assert_eq!(machine_state(&loaded), machine_state(&console));
+    }
+
+    #[test]
+    fn test_invalid_state_leaves_machine_unchanged() {
+        let mut console = Console::new();
+        console.cpu.memory.poke(0x7E0100, 0x42);
+        let state = console.save_state();
+
+        let mut other = Console::new();
+        other.cpu.memory.poke(0x7E0100, 0x99);
+        let before = machine_state(&other);
+        assert!(other.load_state(&state[..state.len() - 1]).is_err());
+        assert_eq!(machine_state(&other), before);
+    }
+}
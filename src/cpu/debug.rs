@@ -0,0 +1,306 @@
+use std::{fmt, ops::RangeInclusive};
+
+use super::{Cpu, CpuError, processorstatusflag::ProcessorStatusFlags, instructions::{decode::DecodedInstruction, instructions::Instruction, interrupt::Interrupt}};
+
+/// Writing a set bit to MDMAEN starts the general purpose DMA of that channel
+const MDMAEN: u16 = 0x420B;
+
+/// Whether the CPU read or wrote a byte
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessKind {
+    Read,
+    Write,
+}
+
+/// Accesses a watchpoint triggers on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchKind {
+    Read,
+    Write,
+    /// Both reads and writes
+    Access,
+}
+
+impl WatchKind {
+    /// Parses `r`, `w` or `rw`
+    pub fn from_name(name: &str) -> Option<WatchKind> {
+        match name {
+            "r" => Some(WatchKind::Read),
+            "w" => Some(WatchKind::Write),
+            "rw" => Some(WatchKind::Access),
+            _ => None,
+        }
+    }
+
+    fn matches(&self, kind: AccessKind) -> bool {
+        match self {
+            WatchKind::Read => kind == AccessKind::Read,
+            WatchKind::Write => kind == AccessKind::Write,
+            WatchKind::Access => true,
+        }
+    }
+}
+
+/// Condition on the byte that is read or written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueCondition {
+    Equal(u8),
+    NotEqual(u8),
+    Less(u8),
+    Greater(u8),
+}
+
+impl ValueCondition {
+    pub fn matches(&self, value: u8) -> bool {
+        match *self {
+            ValueCondition::Equal(v) => value == v,
+            ValueCondition::NotEqual(v) => value != v,
+            ValueCondition::Less(v) => value < v,
+            ValueCondition::Greater(v) => value > v,
+        }
+    }
+}
+
+/// Stops execution when the CPU accesses a long address in `range`
+///
+/// Addresses are matched as the CPU puts them on the bus, so a watchpoint on `$7E0010` does not see accesses through the
+/// mirror at `$000010`. Read watchpoints also see the op code and operand fetches of instructions,
+/// and the data the CPU reads up front for every memory operand, including those of stores and jumps
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Watchpoint {
+    pub range: RangeInclusive<u32>,
+    pub kind: WatchKind,
+    /// Only trigger when the byte matches, `None` triggers on every access
+    pub condition: Option<ValueCondition>,
+}
+
+impl Watchpoint {
+    fn matches(&self, access: &MemoryAccess) -> bool {
+        self.range.contains(&access.addr)
+            && self.kind.matches(access.kind)
+            && self.condition.is_none_or(|condition| condition.matches(access.value))
+    }
+}
+
+/// A single byte the CPU read or wrote
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryAccess {
+    pub addr: u32,
+    pub value: u8,
+    pub kind: AccessKind,
+}
+
+/// Access that triggered the watchpoint with id `id`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WatchHit {
+    pub id: usize,
+    pub access: MemoryAccess,
+}
+
+/// Checks the memory accesses of the CPU against watchpoints and notices DMA starts, not part of the machine state
+///
+/// Hits are collected until they are taken with `MemoryWatch::take_hits`, usually after every instruction
+pub struct MemoryWatch {
+    watchpoints: Vec<(usize, Watchpoint)>,
+    next_id: usize,
+    hits: Vec<WatchHit>,
+    /// Channels started by writes to MDMAEN since the last `MemoryWatch::take_dma_start`
+    dma_start: u8,
+}
+
+impl MemoryWatch {
+    pub fn new() -> MemoryWatch {
+        MemoryWatch { watchpoints: Vec::new(), next_id: 1, hits: Vec::new(), dma_start: 0 }
+    }
+
+    /// Adds a watchpoint, returns the id to remove it with
+    pub fn add(&mut self, watchpoint: Watchpoint) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.watchpoints.push((id, watchpoint));
+        id
+    }
+
+    /// Removes the watchpoint with id `id`, returns false if there is none
+    pub fn remove(&mut self, id: usize) -> bool {
+        let len = self.watchpoints.len();
+        self.watchpoints.retain(|(i, _)| *i != id);
+        self.watchpoints.len() != len
+    }
+
+    /// Watchpoints with their ids, in the order they were added
+    pub fn watchpoints(&self) -> impl Iterator<Item = (usize, &Watchpoint)> {
+        self.watchpoints.iter().map(|(id, watchpoint)| (*id, watchpoint))
+    }
+
+    /// Returns the watchpoint hits since the last call, in the order of the accesses
+    pub fn take_hits(&mut self) -> Vec<WatchHit> {
+        std::mem::take(&mut self.hits)
+    }
+
+    /// Returns the DMA channels that were started since the last call as a bit mask, like they are written to MDMAEN
+    pub fn take_dma_start(&mut self) -> u8 {
+        std::mem::take(&mut self.dma_start)
+    }
+
+    fn check(&mut self, access: MemoryAccess) {
+        let (bank, hhll) = ((access.addr >> 16) as u8, access.addr as u16);
+        if access.kind == AccessKind::Write && hhll == MDMAEN && (bank & 0x7F) < 0x40 {
+            self.dma_start |= access.value;
+        }
+
+        for (id, watchpoint) in &self.watchpoints {
+            if watchpoint.matches(&access) {
+                self.hits.push(WatchHit { id: *id, access });
+            }
+        }
+    }
+}
+
+/// Register of the CPU, used to set registers from a debugger
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Register {
+    A,
+    X,
+    Y,
+    S,
+    D,
+    DB,
+    PB,
+    PC,
+    P,
+}
+
+impl Register {
+    /// Parses a register name as used in traces, ie `A`, `DB` or `PC`. Case insensitive
+    pub fn from_name(name: &str) -> Option<Register> {
+        use Register::*;
+        match name.to_ascii_uppercase().as_str() {
+            "A" => Some(A),
+            "X" => Some(X),
+            "Y" => Some(Y),
+            "S" | "SP" => Some(S),
+            "D" | "DP" => Some(D),
+            "DB" | "DBR" => Some(DB),
+            "PB" | "PBR" | "K" => Some(PB),
+            "PC" => Some(PC),
+            "P" => Some(P),
+            _ => None,
+        }
+    }
+}
+
+/// Copy of the CPU registers
+#[derive(Debug, Clone, Copy)]
+pub struct Registers {
+    pub acc: u16,
+    pub x: u16,
+    pub y: u16,
+    pub sp: u16,
+    pub dp: u16,
+    pub dbr: u8,
+    pub pbr: u8,
+    pub pc: u16,
+    pub status: ProcessorStatusFlags,
+}
+
+impl fmt::Display for Registers {
+    /// Formats like `A:0000 X:0000 Y:0000 S:01FF D:0000 DB:00 PB:00 PC:8000 P:34 nvMXdIzc E`,
+    /// flags are upper case when set and `E` is shown in emulation mode
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use ProcessorStatusFlags as P;
+        let flags: String = [
+            (P::Negative, 'n'), (P::Overflow, 'v'), (P::Accumulator8bit, 'm'), (P::XYreg8bit, 'x'),
+            (P::Decimal, 'd'), (P::IRQdisable, 'i'), (P::Zero, 'z'), (P::Carry, 'c'),
+        ].iter()
+            .map(|(flag, c)| if self.status.contains(*flag) { c.to_ascii_uppercase() } else { *c })
+            .collect();
+        let mode = if self.status.contains(P::Emulation) { " E" } else { "" };
+
+        write!(f, "A:{:04X} X:{:04X} Y:{:04X} S:{:04X} D:{:04X} DB:{:02X} PB:{:02X} PC:{:04X} P:{:02X} {flags}{mode}",
+            self.acc, self.x, self.y, self.sp, self.dp, self.dbr, self.pbr, self.pc, self.status.get_bits())
+    }
+}
+
+/// What a single call to `Cpu::step` did
+#[derive(Debug, Clone, Copy)]
+pub struct Step {
+    /// Instruction that was executed, `None` if a hardware interrupt was entered instead
+    pub instruction: Option<DecodedInstruction>,
+    /// Interrupt that was entered, by hardware or by `BRK` or `COP`
+    pub interrupt: Option<Interrupt>,
+    /// Master clock cycles the step took
+    pub cycles: usize,
+}
+
+impl Cpu {
+    /// Executes the next instruction or enters a pending interrupt right away, ignoring the time the previous one still takes
+    ///
+    /// `Cpu::tick` calls this once the previous instruction has taken its time, debuggers call it directly
+    pub fn step(&mut self, nmi_pending: bool, irq_pending: bool) -> Result<Step, CpuError> {
+        // NMI pending -> execute NMI
+        if nmi_pending {
+            self.execute_nmi()?;
+            return Ok(Step { instruction: None, interrupt: Some(Interrupt::Nmi), cycles: self.wait_cycles });
+        }
+        // IRQ pending and IRQ not disabled -> execute IRQ
+        else if irq_pending && !self.status.contains(ProcessorStatusFlags::IRQdisable) {
+            self.execute_irq()?;
+            return Ok(Step { instruction: None, interrupt: Some(Interrupt::Irq), cycles: self.wait_cycles });
+        }
+        // IRQ pending and irq disabled AND WAI flag on -> execute instruction as normal
+        else if irq_pending && self.status.contains(ProcessorStatusFlags::IRQdisable | ProcessorStatusFlags::WaitForInterrupt) {
+            self.status.clear_flag(ProcessorStatusFlags::WaitForInterrupt);
+        }
+
+        // read & execute instruction
+        self.reset_bus_timing();
+        let instr = self.decode_next_instruction();
+        if self.tracer.is_some() {
+            self.trace_instruction(&instr);
+        }
+        self.execute_instruction(instr)?;
+
+        let interrupt = match instr.instruction {
+            Instruction::Brk => Some(Interrupt::Brk),
+            Instruction::Cop => Some(Interrupt::Cop),
+            _ => None,
+        };
+        Ok(Step { instruction: Some(instr), interrupt, cycles: self.wait_cycles })
+    }
+
+    pub fn registers(&self) -> Registers {
+        Registers { acc: self.acc, x: self.x, y: self.y, sp: self.sp, dp: self.dp, dbr: self.dbr, pbr: self.pbr, pc: self.pc, status: self.status }
+    }
+
+    /// Sets `reg` to `val`, 8 bit registers use the low byte
+    ///
+    /// The restrictions of the current mode still apply, ie setting `P` in emulation mode keeps 8 bit registers
+    pub fn set_register(&mut self, reg: Register, val: u16) {
+        match reg {
+            Register::A => self.acc = val,
+            Register::X => self.x = val,
+            Register::Y => self.y = val,
+            Register::S => self.set_sp(val),
+            Register::D => self.dp = val,
+            Register::DB => self.dbr = val as u8,
+            Register::PB => self.pbr = val as u8,
+            Register::PC => self.pc = val,
+            Register::P => self.status.set_stack_bits(val as u8),
+        }
+        self.update_register_modes();
+    }
+
+    pub fn watch(&self) -> &MemoryWatch {
+        &self.watch
+    }
+
+    pub fn watch_mut(&mut self) -> &mut MemoryWatch {
+        &mut self.watch
+    }
+
+    /// Passes a bus access to the watchpoints
+    pub(super) fn watch_access(&mut self, addr: u32, value: u8, kind: AccessKind) {
+        self.watch.check(MemoryAccess { addr, value, kind });
+    }
+}
@@ -0,0 +1,57 @@
+use super::{Cpu, processorstatusflag::ProcessorStatusFlags};
+
+/// Page the stack is confined to in emulation mode
+const EMULATION_STACK_PAGE: u16 = 0x0100;
+
+impl Cpu {
+    /// Returns true if the CPU is in 6502 emulation mode
+    pub fn is_emulation(&self) -> bool {
+        self.status.contains(ProcessorStatusFlags::Emulation)
+    }
+
+    /// Applies the restrictions of the current mode to the registers, call this after anything that changes the status register
+    ///
+    /// * In emulation mode the accumulator and index registers are always 8 bit, the break flag is set
+    ///   and the stack pointer is confined to page 1
+    /// * When the index registers are 8 bit the high bytes of X and Y are cleared
+    pub fn update_register_modes(&mut self) {
+        if self.is_emulation() {
+            self.status.set_flag(ProcessorStatusFlags::Accumulator8bit | ProcessorStatusFlags::XYreg8bit | ProcessorStatusFlags::Break);
+            self.sp = EMULATION_STACK_PAGE | (self.sp & 0xFF);
+        }
+
+        if self.status.contains(ProcessorStatusFlags::XYreg8bit) {
+            self.x &= 0xFF;
+            self.y &= 0xFF;
+        }
+    }
+
+    /// Sets the stack pointer, in emulation mode only the low byte is set
+    pub fn set_sp(&mut self, val: u16) {
+        self.sp = match self.is_emulation() {
+            true => EMULATION_STACK_PAGE | (val & 0xFF),
+            false => val,
+        };
+    }
+
+    /// Stack pointer after moving it by `offset` bytes, in emulation mode it wraps around in page 1
+    pub(super) fn stack_offset(&self, offset: i16) -> u16 {
+        let sp = self.sp.wrapping_add_signed(offset);
+        match self.is_emulation() {
+            true => EMULATION_STACK_PAGE | (sp & 0xFF),
+            false => sp,
+        }
+    }
+
+    /// Address of direct page offset `offset`
+    ///
+    /// In emulation mode, when the low byte of the direct page register is zero,
+    /// direct page addresses wrap around within the direct page like zero page addresses do on the 6502.
+    /// Otherwise they wrap around at the end of bank 0
+    pub(super) fn direct_addr(&self, offset: u16) -> u16 {
+        match self.is_emulation() && self.dp & 0xFF == 0 {
+            true => self.dp | (offset & 0xFF),
+            false => self.dp.wrapping_add(offset),
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{cpu::{instructions::{AddressingMode, instructions::Instruction}, Cpu}, to_long};
+use crate::{cpu::{instructions::{AddressingMode, decode::DecodedInstruction}, Cpu}, to_long};
 
 
 /// Container for holding data that an instruction can take as input
@@ -23,30 +23,18 @@ impl Cpu {
     /// Returns `InstrData` struct based on instruction addressing mode
     /// 
     /// Source: [6502.org](http://www.6502.org/tutorials/65c816opcodes.html)
-    pub fn get_instruction_data(&mut self, instr: &Instruction) -> InstrData {
+    pub fn get_instruction_data(&mut self, instr: &DecodedInstruction) -> InstrData {
         use AddressingMode::*;
         
-        let (arg0, arg1, arg2) = match instr.get_length() {
-            1 => (0, 0, 0), // Implied
-            2 => {
-                let arg0 = self.mem_read(self.get_pc_addr());
-                (arg0, 0, 0)
-            },
-            3 => {
-                let arg0 = self.mem_read(self.get_pc_addr());
-                let arg1 = self.mem_read(self.get_pc_addr());
-                (arg0, arg1, 0)
-            },
-            4 => {
-                let arg0 = self.mem_read(self.get_pc_addr());
-                let arg1 = self.mem_read(self.get_pc_addr());
-                let arg2 = self.mem_read(self.get_pc_addr());
-                (arg0, arg1, arg2)
-            }
+        let (arg0, arg1, arg2) = match *instr.operand_bytes() {
+            [] => (0, 0, 0), // Implied
+            [arg0] => (arg0, 0, 0),
+            [arg0, arg1] => (arg0, arg1, 0),
+            [arg0, arg1, arg2] => (arg0, arg1, arg2),
             _ => unreachable!("No instruction has more than 3 arguments")
         };
         
-        match instr.get_addressing_mode() {
+        match instr.mode {
             Absolute => self.get_absolute_data(arg0, arg1),
             AbsoluteX => self.get_absolutex_data(arg0, arg1),
             AbsoluteY => self.get_absolutey_data(arg0, arg1),
@@ -57,8 +45,8 @@ impl Cpu {
             DirectX => self.get_directx_data(arg0),
             DirectY => self.get_directy_data(arg0),
             Implied => InstrData::new(0, 0, 0, 0),
-            Immediate => self.get_immediate(arg0),
-            ImmediateLong => self.get_immediate_long(arg0, arg1),
+            Immediate => self.get_immediate(instr, arg0),
+            ImmediateLong => self.get_immediate_long(instr, arg0, arg1),
             Indirect => self.get_indirect_data(arg0),
             IndirectX => self.get_indirectx_data(arg0),
             IndirectY => self.get_indirecty_data(arg0),
@@ -178,15 +166,16 @@ impl Cpu {
     /// 
     /// reads `$dl = D + $LL` and `$dh = D + $LL + 1`
     /// 
-    /// `$dl` and `$dh` wrap around 16 bytes, meaning that if `D = $FF00` and `$LL = $FF` then low_addr = `$00FFFF` and high_addr = `$000000`
+    /// `$dl` and `$dh` wrap around 16 bytes, meaning that if `D = $FF00` and `$LL = $FF` then low_addr = `$00FFFF` and high_addr = `$000000`.
+    /// In emulation mode they wrap around within the direct page if its low byte is zero, see `Cpu::direct_addr`
     /// 
     /// # Returns
     /// * data: value in `$dh` as high byte, value in `$dl` as low byte
     /// * low_addr: `$dl`
     /// * high_adr: `$dh`
     fn get_direct_data(&mut self, arg0: u8) -> InstrData {
-        let low_addr = self.dp.wrapping_add(arg0 as u16);
-        let high_addr = low_addr.wrapping_add(1);
+        let low_addr = self.direct_addr(arg0 as u16);
+        let high_addr = self.direct_addr(arg0 as u16 + 1);
         
         let data = self.mem_read_long(low_addr as u32, high_addr as u32);
         InstrData::new(data, low_addr as u32, high_addr as u32, 0)
@@ -196,15 +185,17 @@ impl Cpu {
     /// 
     /// reads `$dl = D + $LL + X` and `$dh = D + $LL + 1 + X`
     /// 
-    /// `$dl` and `$dh` wrap around 16 bytes, meaning that if `D = $FF00` and `$LL = $FF` then low_addr = `$00FFFF` and high_addr = `$000000`
+    /// `$dl` and `$dh` wrap around 16 bytes, meaning that if `D = $FF00` and `$LL = $FF` then low_addr = `$00FFFF` and high_addr = `$000000`.
+    /// In emulation mode they wrap around within the direct page if its low byte is zero, see `Cpu::direct_addr`
     /// 
     /// # Returns
     /// * data: value in `$dh` as high byte, value in `$dl` as low byte
     /// * low_addr: `$dl`
     /// * high_adr: `$dh`
     fn get_directx_data(&mut self, arg0: u8) -> InstrData {
-        let low_addr = self.dp.wrapping_add(arg0 as u16).wrapping_add(self.x);
-        let high_addr = low_addr.wrapping_add(1);
+        let offset = (arg0 as u16).wrapping_add(self.x);
+        let low_addr = self.direct_addr(offset);
+        let high_addr = self.direct_addr(offset.wrapping_add(1));
         
         let data = self.mem_read_long(low_addr as u32, high_addr as u32);
         InstrData::new(data, low_addr as u32, high_addr as u32, 0)
@@ -215,23 +206,25 @@ impl Cpu {
     /// reads `$dl = D + $LL + Y` and `$dh = D + $LL + 1 + Y`
     /// 
     /// `$dl` and `$dh` wrap around 16 bytes, meaning that if `D = $FF00` and `$LL = $FF`
-    ///  then low_addr = `$00FFFF` and high_addr = `$000000`
+    ///  then low_addr = `$00FFFF` and high_addr = `$000000`.
+    /// In emulation mode they wrap around within the direct page if its low byte is zero, see `Cpu::direct_addr`
     /// 
     /// # Returns
     /// * data: value in `$dh` as high byte, value in `$dl` as low byte
     /// * low_addr: `$dl`
     /// * high_adr: `$dh`
     fn get_directy_data(&mut self, arg0: u8) -> InstrData {
-        let low_addr = self.dp.wrapping_add(arg0 as u16).wrapping_add(self.y);
-        let high_addr = low_addr.wrapping_add(1);
+        let offset = (arg0 as u16).wrapping_add(self.y);
+        let low_addr = self.direct_addr(offset);
+        let high_addr = self.direct_addr(offset.wrapping_add(1));
         
         let data = self.mem_read_long(low_addr as u32, high_addr as u32);
         InstrData::new(data, low_addr as u32, high_addr as u32, 0)
     }
 
     fn get_indirect_data(&mut self, arg0: u8) -> InstrData {
-        let pointer_lo = self.dp.wrapping_add(arg0 as u16);
-        let pointer_hi = pointer_lo.wrapping_add(1);
+        let pointer_lo = self.direct_addr(arg0 as u16);
+        let pointer_hi = self.direct_addr(arg0 as u16 + 1);
 
         let ll = self.mem_read(pointer_lo as u32);
         let hh = self.mem_read(pointer_hi as u32);
@@ -244,8 +237,9 @@ impl Cpu {
     }
 
     fn get_indirectx_data(&mut self, arg0: u8) -> InstrData {
-        let pointer_lo = self.dp.wrapping_add(arg0 as u16).wrapping_add(self.x);
-        let pointer_hi = pointer_lo.wrapping_add(1);
+        let offset = (arg0 as u16).wrapping_add(self.x);
+        let pointer_lo = self.direct_addr(offset);
+        let pointer_hi = self.direct_addr(offset.wrapping_add(1));
 
         let ll = self.mem_read(pointer_lo as u32);
         let hh = self.mem_read(pointer_hi as u32);
@@ -258,8 +252,8 @@ impl Cpu {
     }
 
     fn get_indirecty_data(&mut self, arg0: u8) -> InstrData {
-        let pointer_lo = self.dp.wrapping_add(arg0 as u16);
-        let pointer_hi = pointer_lo.wrapping_add(1);
+        let pointer_lo = self.direct_addr(arg0 as u16);
+        let pointer_hi = self.direct_addr(arg0 as u16 + 1);
 
         let ll = self.mem_read(pointer_lo as u32);
         let hh = self.mem_read(pointer_hi as u32);
@@ -303,13 +297,25 @@ impl Cpu {
         InstrData::new(data, low_addr, high_addr, 0)
     }
 
-    fn get_immediate(&mut self, arg0: u8) -> InstrData {
-        InstrData::new(arg0 as u16, 0, 0, 0)
+    /// $OP #$LL
+    /// 
+    /// # Returns
+    /// * data: `$LL`
+    /// * low_addr: address of `$LL` in the instruction, so instructions that read their argument from memory read the constant
+    fn get_immediate(&mut self, instr: &DecodedInstruction, arg0: u8) -> InstrData {
+        let low_addr = instr.operand_addr(0);
+        InstrData::new(arg0 as u16, low_addr, low_addr, 0)
     }
 
-    fn get_immediate_long(&mut self, arg0: u8, arg1: u8) -> InstrData {
+    /// $OP #$LL #$HH
+    /// 
+    /// # Returns
+    /// * data: `$HHLL`
+    /// * low_addr: address of `$LL` in the instruction
+    /// * high_addr: address of `$HH` in the instruction
+    fn get_immediate_long(&mut self, instr: &DecodedInstruction, arg0: u8, arg1: u8) -> InstrData {
         let data = (arg1 as u16) << 8 | (arg0 as u16);
-        InstrData::new(data, 0, 0, 0)
+        InstrData::new(data, instr.operand_addr(0), instr.operand_addr(1), 0)
     }
 
     fn get_long_data(&mut self, arg0: u8, arg1: u8, arg2: u8) -> InstrData {
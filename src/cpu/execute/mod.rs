@@ -1,34 +1,53 @@
 pub mod instrdata;
+mod timing;
 
 
-use super::{instructions::instructions::Instruction, Cpu, CpuError};
+use super::{instructions::{instructions::Instruction, decode::DecodedInstruction, interrupt::Interrupt}, processorstatusflag::ProcessorStatusFlags, Cpu, CpuError};
+
+use self::instrdata::InstrData;
 
 impl Cpu {
 
     pub fn execute_nmi(&mut self) -> Result<(), CpuError> {
-        self.exe_interrupt(0x00FFEA);
-        Ok(())
+        self.execute_hardware_interrupt(Interrupt::Nmi)
     }
 
     pub fn execute_irq(&mut self) -> Result<(), CpuError> {
-        self.exe_interrupt(0x00FFEE);
+        self.execute_hardware_interrupt(Interrupt::Irq)
+    }
+
+    /// Enters the handler of `interrupt` between instructions and sets the wait time to the master clock cycles it took
+    pub fn execute_hardware_interrupt(&mut self, interrupt: Interrupt) -> Result<(), CpuError> {
+        self.reset_bus_timing();
+        let cycles = self.get_interrupt_cycles();
+        self.status.clear_flag(ProcessorStatusFlags::WaitForInterrupt);
+        self.exe_interrupt(interrupt);
+        self.wait_cycles = self.get_master_cycles(cycles);
         Ok(())
     }
 
-    /// Executes `instr`, increments PC and adds to cycle wait time
-    pub fn execute_instruction(&mut self, instr: Instruction) -> Result<(), CpuError> {
-        self.execute_op(&instr);
+    /// Increments PC, executes `instr` and sets the wait time to the master clock cycles the instruction took
+    /// 
+    /// The length is taken from the decoded instruction, since `REP` and `SEP` change the register sizes it depends on.
+    /// PC points to the next instruction while `instr` executes, so jumps, branches and interrupts can simply overwrite it.
+    /// Bus accesses are counted from the start of decoding, so `Cpu::reset_bus_timing` must be called before decoding `instr`
+    pub fn execute_instruction(&mut self, instr: DecodedInstruction) -> Result<(), CpuError> {
+        // Penalties depend on the state before executing, SEP/REP/XCE/PLP change it
+        let branch_taken = self.branch_taken(&instr.instruction);
+        let instr_data = self.get_instruction_data(&instr);
+        let cycles = self.get_cycle_count(&instr, &instr_data, branch_taken);
+
+        self.pc = self.pc.wrapping_add(instr.length() as u16);
 
-        self.wait_cycles = instr.get_cycle_time();
+        self.execute_op(&instr.instruction, instr_data);
 
-        self.pc = self.pc.wrapping_add(instr.get_length() as u16);
+        self.wait_cycles = self.get_master_cycles(cycles);
 
         Ok(())
     }
 
-    fn execute_op(&mut self, instr: &Instruction) {
+    fn execute_op(&mut self, instr: &Instruction, instr_data: InstrData) {
         use Instruction::*;
-        let instr_data = self.get_instruction_data(&instr);
         let data = instr_data.data;
         let low_addr = instr_data.low_addr;
         let high_addr = instr_data.high_addr;
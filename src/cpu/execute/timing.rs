@@ -0,0 +1,186 @@
+use crate::cpu::{Cpu, processorstatusflag::ProcessorStatusFlags, memory::FAST_ACCESS, instructions::{AddressingMode, instructions::Instruction, decode::DecodedInstruction}};
+
+use super::instrdata::InstrData;
+
+/// CPU cycles of an interrupt in native mode, emulation mode takes one cycle less
+const INTERRUPT_CYCLES: usize = 8;
+
+impl Cpu {
+    /// Number of CPU cycles `instr` takes, the base cycle time of the instruction plus all penalties that apply to it
+    /// 
+    /// `data` must be the data the instruction was executed with and `branch_taken` must be decided before executing it
+    /// 
+    /// Source: [6502.org](http://www.6502.org/tutorials/65c816opcodes.html#6.2)
+    pub(super) fn get_cycle_count(&self, instr: &DecodedInstruction, data: &InstrData, branch_taken: bool) -> usize {
+        use Instruction::*;
+        use AddressingMode::*;
+
+        let m16 = !self.status.contains(ProcessorStatusFlags::Accumulator8bit);
+        let x16 = !self.status.contains(ProcessorStatusFlags::XYreg8bit);
+        let emulation = self.status.contains(ProcessorStatusFlags::Emulation);
+        let mut cycles = instr.instruction.get_cycle_time();
+
+        // 16 bit accumulator/memory and index registers need an extra cycle to read or write the second byte
+        match instr.instruction {
+            AslAbs | AslAbsX | AslDP | AslDPX | DecAbs | DecAbsX | DecDP | DecDPX |
+            IncAbs | IncAbsX | IncDP | IncDPX | LsrAbs | LsrAbsX | LsrDP | LsrDPX |
+            RolAbs | RolAbsX | RolDP | RolDPX | RorAbs | RorAbsX | RorDP | RorDPX |
+            TrbAbs | TrbDP | TsbAbs | TsbDP => cycles += 2 * m16 as usize,
+
+            Pha | Pla => cycles += m16 as usize,
+            Phx | Phy | Plx | Ply => cycles += x16 as usize,
+
+            _ => match instr.instruction.get_register_size_flag() {
+                Some(flag) if flag.contains(ProcessorStatusFlags::Accumulator8bit) => cycles += m16 as usize,
+                Some(_) => cycles += x16 as usize,
+                None => {},
+            },
+        }
+
+        // Direct page accesses take an extra cycle when the direct page is not aligned to a page
+        let direct_page = matches!(instr.mode, Direct | DirectX | DirectY | Indirect | IndirectX | IndirectY | IndirectLong | IndirectLongY)
+            || instr.instruction == Pei;
+        if direct_page && self.dp & 0xFF != 0 {
+            cycles += 1;
+        }
+
+        // Indexed reads take an extra cycle when the index crosses a page or is 16 bits. Writes always take it
+        let index = match instr.mode {
+            AbsoluteX => Some(self.x),
+            AbsoluteY | IndirectY => Some(self.y),
+            _ => None,
+        };
+        if let Some(index) = index {
+            let base = data.low_addr.wrapping_sub(index as u32);
+            let page_crossed = (base ^ data.low_addr) & 0xFFFF00 != 0;
+            let is_read = !instr.instruction.is_store() && !instr.instruction.writes_back();
+            if is_read && (page_crossed || x16) {
+                cycles += 1;
+            }
+        }
+
+        // Taken branches take an extra cycle, in emulation mode another one when they cross a page
+        if instr.mode == Relative && branch_taken {
+            if instr.instruction != Bra {
+                cycles += 1;
+            }
+            if emulation && (data.low_addr ^ instr.next_addr()) & 0xFF00 != 0 {
+                cycles += 1;
+            }
+        }
+
+        // Native mode interrupts also push and pull the program bank
+        if matches!(instr.instruction, Brk | Cop | Rti) && !emulation {
+            cycles += 1;
+        }
+
+        cycles
+    }
+
+    /// Number of master clock cycles an instruction or interrupt of `cycles` CPU cycles takes
+    /// 
+    /// Cycles that accessed the bus take as long as the memory they accessed, see `CpuMemory::access_time`,
+    /// the remaining cycles are internal and take 6 master clock cycles
+    pub(super) fn get_master_cycles(&self, cycles: usize) -> usize {
+        let internal_cycles = cycles.saturating_sub(self.bus_accesses);
+        self.bus_cycles + internal_cycles * FAST_ACCESS
+    }
+
+    /// Starts counting bus accesses for the next instruction or interrupt
+    pub(crate) fn reset_bus_timing(&mut self) {
+        self.bus_accesses = 0;
+        self.bus_cycles = 0;
+    }
+
+    /// Number of CPU cycles an interrupt takes
+    pub(super) fn get_interrupt_cycles(&self) -> usize {
+        match self.status.contains(ProcessorStatusFlags::Emulation) {
+            true => INTERRUPT_CYCLES - 1,
+            false => INTERRUPT_CYCLES,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Cpu in native mode with 8 bit registers and the direct page at `$0000`
+    fn native_cpu() -> Cpu {
+        let mut cpu = Cpu::new();
+        cpu.status = ProcessorStatusFlags::Accumulator8bit | ProcessorStatusFlags::XYreg8bit;
+        cpu.dp = 0;
+        cpu.x = 0;
+        cpu.y = 0;
+        cpu
+    }
+
+    fn decode_at(addr: u32, bytes: &[u8], status: ProcessorStatusFlags) -> DecodedInstruction {
+        DecodedInstruction::decode(addr, status, |a| bytes.get((a - addr) as usize).copied().unwrap_or(0))
+    }
+
+    fn cycles(cpu: &Cpu, bytes: &[u8], low_addr: u32, branch_taken: bool) -> usize {
+        let instr = decode_at(0x008000, bytes, cpu.status);
+        cpu.get_cycle_count(&instr, &InstrData::new(0, low_addr, low_addr + 1, low_addr + 2), branch_taken)
+    }
+
+    #[test]
+    fn test_16bit_registers_penalty() {
+        let mut cpu = native_cpu();
+        assert_eq!(cycles(&cpu, &[0xA9, 0x00], 0x008001, false), 2);
+        assert_eq!(cycles(&cpu, &[0xFE, 0x00, 0x10], 0x001000, false), 7);
+
+        cpu.status.clear_flag(ProcessorStatusFlags::Accumulator8bit);
+        assert_eq!(cycles(&cpu, &[0xA9, 0x00, 0x00], 0x008001, false), 3);
+        // Read-modify-write instructions read and write the extra byte
+        assert_eq!(cycles(&cpu, &[0xFE, 0x00, 0x10], 0x001000, false), 9);
+        assert_eq!(cycles(&cpu, &[0x48], 0, false), 4);
+    }
+
+    #[test]
+    fn test_direct_page_penalty() {
+        let mut cpu = native_cpu();
+        assert_eq!(cycles(&cpu, &[0xA5, 0x10], 0x000010, false), 3);
+        cpu.dp = 0x0001;
+        assert_eq!(cycles(&cpu, &[0xA5, 0x10], 0x000011, false), 4);
+        cpu.dp = 0x0100;
+        assert_eq!(cycles(&cpu, &[0xA5, 0x10], 0x000110, false), 3);
+    }
+
+    #[test]
+    fn test_index_penalty() {
+        let mut cpu = native_cpu();
+        cpu.x = 0x10;
+        // LDA $10F0,X without and with crossing a page
+        assert_eq!(cycles(&cpu, &[0xBD, 0xE0, 0x10], 0x0010F0, false), 4);
+        assert_eq!(cycles(&cpu, &[0xBD, 0xF8, 0x10], 0x001108, false), 5);
+        // Stores always take the extra cycle, so it is part of their base time
+        assert_eq!(cycles(&cpu, &[0x9D, 0xE0, 0x10], 0x0010F0, false), 5);
+
+        cpu.status.clear_flag(ProcessorStatusFlags::XYreg8bit);
+        assert_eq!(cycles(&cpu, &[0xBD, 0xE0, 0x10], 0x0010F0, false), 5);
+    }
+
+    #[test]
+    fn test_branch_penalty() {
+        let mut cpu = native_cpu();
+        assert_eq!(cycles(&cpu, &[0xD0, 0x10], 0x008012, false), 2);
+        assert_eq!(cycles(&cpu, &[0xD0, 0x10], 0x008012, true), 3);
+        // BRA is always taken, its base time includes that
+        assert_eq!(cycles(&cpu, &[0x80, 0x10], 0x008012, true), 3);
+
+        // Crossing a page only costs a cycle in emulation mode
+        assert_eq!(cycles(&cpu, &[0xD0, 0x80], 0x007F82, true), 3);
+        cpu.status.set_flag(ProcessorStatusFlags::Emulation);
+        assert_eq!(cycles(&cpu, &[0xD0, 0x80], 0x007F82, true), 4);
+    }
+
+    #[test]
+    fn test_interrupt_bank_penalty() {
+        let mut cpu = native_cpu();
+        assert_eq!(cycles(&cpu, &[0x00, 0x00], 0, false), 8);
+        cpu.status.set_flag(ProcessorStatusFlags::Emulation);
+        assert_eq!(cycles(&cpu, &[0x00, 0x00], 0, false), 7);
+        assert_eq!(cpu.get_interrupt_cycles(), 7);
+    }
+}
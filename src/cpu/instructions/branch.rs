@@ -1,10 +1,28 @@
-use crate::cpu::{Cpu, processorstatusflag::ProcessorStatusFlags};
+use crate::cpu::{Cpu, processorstatusflag::ProcessorStatusFlags, instructions::instructions::Instruction};
 
 impl Cpu {
 
 	fn branch(&mut self, target_addr: u32) {
 		self.pc = target_addr as u16;
 	}
+
+	/// Returns true if `instr` is a branch that will be taken in the current state, used for timing
+	pub fn branch_taken(&self, instr: &Instruction) -> bool {
+		use Instruction::*;
+		let flag = |f| self.status.contains(f);
+		match instr {
+			Blt => !flag(ProcessorStatusFlags::Carry),
+			Bge => flag(ProcessorStatusFlags::Carry),
+			Beq => flag(ProcessorStatusFlags::Zero),
+			Bne => !flag(ProcessorStatusFlags::Zero),
+			Bmi => flag(ProcessorStatusFlags::Negative),
+			Bpl => !flag(ProcessorStatusFlags::Negative),
+			Bvs => flag(ProcessorStatusFlags::Overflow),
+			Bvc => !flag(ProcessorStatusFlags::Overflow),
+			Bra | Brl => true,
+			_ => false,
+		}
+	}
 	
 	/// Branch if Carry Clear (Program Counter Relative)
 	pub fn exe_bcc(&mut self, target_addr: u32) {
@@ -0,0 +1,164 @@
+use crate::cpu::{Cpu, processorstatusflag::ProcessorStatusFlags};
+
+use super::{AddressingMode, instructions::Instruction};
+
+/// Instruction as it is found in memory, decoded for a specific CPU state
+///
+/// The same bytes can decode to instructions of different lengths, since immediate arguments
+/// are 1 or 2 bytes depending on the register size flags in the status register
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodedInstruction {
+    /// Long address of the op code
+    pub addr: u32,
+    pub opcode: u8,
+    pub instruction: Instruction,
+    pub mode: AddressingMode,
+    /// Argument bytes in the order they follow the op code, only the first `operand_count` are used
+    operands: [u8; 3],
+    operand_count: usize,
+}
+
+impl DecodedInstruction {
+    /// Decodes the instruction at `addr`, using `read` to get the bytes at a long address
+    ///
+    /// Arguments are read from the same bank as the op code, wrapping around at the end of the bank like the program counter does
+    pub fn decode(addr: u32, status: ProcessorStatusFlags, mut read: impl FnMut(u32) -> u8) -> DecodedInstruction {
+        let opcode = read(addr);
+        let instruction = Instruction::from_op(opcode);
+        let operand_count = instruction.get_length(status) - 1;
+
+        let mut operands = [0; 3];
+        for (i, operand) in operands.iter_mut().enumerate().take(operand_count) {
+            *operand = read(Self::addr_in_bank(addr, 1 + i));
+        }
+
+        DecodedInstruction {
+            addr,
+            opcode,
+            instruction,
+            mode: instruction.get_addressing_mode(status),
+            operands,
+            operand_count,
+        }
+    }
+
+    /// Long address `offset` bytes after `addr`, staying in the bank of `addr`
+    fn addr_in_bank(addr: u32, offset: usize) -> u32 {
+        (addr & 0xFF0000) | ((addr as u16).wrapping_add(offset as u16) as u32)
+    }
+
+    /// Length of the instruction in bytes, including the op code
+    pub fn length(&self) -> usize {
+        1 + self.operand_count
+    }
+
+    /// Argument bytes in the order they follow the op code
+    pub fn operand_bytes(&self) -> &[u8] {
+        &self.operands[..self.operand_count]
+    }
+
+    /// Arguments as a single little endian value, `0` for implied instructions
+    pub fn operand(&self) -> u32 {
+        self.operand_bytes().iter().rev().fold(0, |acc, byte| (acc << 8) | *byte as u32)
+    }
+
+    /// Long address of argument byte `i`, immediate instructions read their data from here
+    pub fn operand_addr(&self, i: usize) -> u32 {
+        Self::addr_in_bank(self.addr, 1 + i)
+    }
+
+    /// Long address of the instruction after this one
+    pub fn next_addr(&self) -> u32 {
+        Self::addr_in_bank(self.addr, self.length())
+    }
+
+    /// Target of a relative branch or `PER`, relative to the next instruction and in the same bank. `None` for other instructions
+    pub fn branch_target(&self) -> Option<u32> {
+        let offset = match (self.mode, self.instruction) {
+            (AddressingMode::Relative, _) => self.operands[0] as i8 as i16 as u16,
+            (AddressingMode::RelativeLong, _) | (_, Instruction::Per) => self.operand() as u16,
+            _ => return None,
+        };
+        Some((self.addr & 0xFF0000) | (self.next_addr() as u16).wrapping_add(offset) as u32)
+    }
+}
+
+impl Cpu {
+    /// Decodes the instruction at `addr` for the current state of the status register
+    pub fn decode_instruction(&mut self, addr: u32) -> DecodedInstruction {
+        let status = self.status;
+        DecodedInstruction::decode(addr, status, |a| self.mem_read(a))
+    }
+
+    /// Decodes the instruction at the program counter, which is the next instruction to execute
+    pub fn decode_next_instruction(&mut self) -> DecodedInstruction {
+        self.decode_instruction(self.get_pc_addr())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Decodes `bytes` placed at `$00:8000`
+    fn decode_bytes(bytes: &[u8], status: ProcessorStatusFlags) -> DecodedInstruction {
+        DecodedInstruction::decode(0x008000, status, |addr| bytes.get(addr as usize - 0x8000).copied().unwrap_or(0))
+    }
+
+    #[test]
+    fn test_immediate_length_follows_m() {
+        let lda = [0xA9, 0x34, 0x12];
+        let native16 = decode_bytes(&lda, ProcessorStatusFlags::empty());
+        assert_eq!(native16.length(), 3);
+        assert_eq!(native16.operand(), 0x1234);
+
+        let native8 = decode_bytes(&lda, ProcessorStatusFlags::Accumulator8bit);
+        assert_eq!(native8.length(), 2);
+        assert_eq!(native8.operand(), 0x34);
+
+        // LDX only depends on X
+        assert_eq!(decode_bytes(&[0xA2, 0, 0], ProcessorStatusFlags::Accumulator8bit).length(), 3);
+        assert_eq!(decode_bytes(&[0xA2, 0, 0], ProcessorStatusFlags::XYreg8bit).length(), 2);
+    }
+
+    #[test]
+    fn test_immediate_length_in_emulation_mode() {
+        let status = ProcessorStatusFlags::Emulation | ProcessorStatusFlags::Accumulator8bit | ProcessorStatusFlags::XYreg8bit;
+        assert_eq!(decode_bytes(&[0xA9, 0, 0], status).length(), 2);
+        assert_eq!(decode_bytes(&[0xA0, 0, 0], status).length(), 2);
+        // REP and SEP always have a single byte argument
+        assert_eq!(decode_bytes(&[0xC2, 0x30], ProcessorStatusFlags::empty()).length(), 2);
+    }
+
+    #[test]
+    fn test_fixed_lengths() {
+        let status = ProcessorStatusFlags::empty();
+        assert_eq!(decode_bytes(&[0xEA], status).length(), 1);
+        assert_eq!(decode_bytes(&[0xAD, 0x00, 0x21], status).length(), 3);
+
+        let jsl = decode_bytes(&[0x22, 0x56, 0x34, 0x12], status);
+        assert_eq!(jsl.length(), 4);
+        assert_eq!(jsl.operand(), 0x123456);
+    }
+
+    #[test]
+    fn test_operands_wrap_in_bank() {
+        let bytes = |addr: u32| match addr {
+            0x01FFFF => 0xAD,
+            0x010000 => 0x34,
+            0x010001 => 0x12,
+            _ => 0xFF,
+        };
+        let instr = DecodedInstruction::decode(0x01FFFF, ProcessorStatusFlags::empty(), bytes);
+        assert_eq!(instr.operand(), 0x1234);
+        assert_eq!(instr.next_addr(), 0x010002);
+    }
+
+    #[test]
+    fn test_branch_target() {
+        let status = ProcessorStatusFlags::empty();
+        assert_eq!(decode_bytes(&[0x80, 0xFE], status).branch_target(), Some(0x008000));
+        assert_eq!(decode_bytes(&[0x82, 0x00, 0x80], status).branch_target(), Some(0x000003));
+        assert_eq!(decode_bytes(&[0xEA], status).branch_target(), None);
+    }
+}
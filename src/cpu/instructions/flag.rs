@@ -25,6 +25,7 @@ impl Cpu {
 	/// Reset Processor Status Bits (Immediate)
 	pub fn exe_rep(&mut self, data: u8) {
 		self.status.clear_bits(data as u8);
+		self.update_register_modes();
 	}
 	
 	/// Set Carry Flag (Implied)
@@ -44,15 +45,21 @@ impl Cpu {
 	
 	/// Set Processor Status Bits (Immediate)
 	pub fn exe_sep(&mut self, data: u8) {
-		self.status.set_bits(data)
+		self.status.set_bits(data);
+		self.update_register_modes();
 	}
 	
 	/// Exchange Carry and Emulation Flags (Implied)
+	/// 
+	/// Entering emulation mode makes the registers 8 bit and moves the stack to page 1.
+	/// When leaving emulation mode the registers stay 8 bit until they are changed with `REP`
 	pub fn exe_xce(&mut self, data: u16) {
 		let carry = self.status.contains(ProcessorStatusFlags::Carry);
 		let emulation = self.status.contains(ProcessorStatusFlags::Emulation);
 		self.status.set(ProcessorStatusFlags::Carry, emulation);
 		self.status.set(ProcessorStatusFlags::Emulation, carry);
+		self.status.set(ProcessorStatusFlags::Break, false);
+		self.update_register_modes();
 	}
 	
 	
@@ -1,31 +1,89 @@
 use crate::cpu::{Cpu, processorstatusflag::ProcessorStatusFlags};
 
+/// Sources of interrupts, each has its own vector in bank 0
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interrupt {
+	Cop,
+	Brk,
+	Abort,
+	Nmi,
+	Irq,
+}
+
+impl Interrupt {
+	/// Address of the vector holding the handler address, emulation mode has its own vectors.
+	/// In emulation mode `BRK` shares its vector with IRQ, the break flag in the pushed status tells them apart
+	pub fn vector(&self, emulation: bool) -> u32 {
+		use Interrupt::*;
+		match (self, emulation) {
+			(Cop, false) => 0x00FFE4,
+			(Brk, false) => 0x00FFE6,
+			(Abort, false) => 0x00FFE8,
+			(Nmi, false) => 0x00FFEA,
+			(Irq, false) => 0x00FFEE,
+			(Cop, true) => 0x00FFF4,
+			(Abort, true) => 0x00FFF8,
+			(Nmi, true) => 0x00FFFA,
+			(Brk | Irq, true) => 0x00FFFE,
+		}
+	}
+
+	/// Returns true for interrupts caused by hardware instead of an instruction
+	pub fn is_hardware(&self) -> bool {
+		matches!(self, Interrupt::Abort | Interrupt::Nmi | Interrupt::Irq)
+	}
+}
+
 impl Cpu {
 	
-	pub fn exe_interrupt(&mut self, interrupt_vector: u32) {
-		self.push_byte_stack(self.pbr);
-		self.push_long_stack(self.pc.wrapping_add(2));
-		self.push_byte_stack(self.status.get_bits());
-		self.pc = self.mem_read_long(interrupt_vector, interrupt_vector.wrapping_add(1));
+	/// Enters the handler of `interrupt`, the program counter has to point to the instruction to return to
+	/// 
+	/// Native mode pushes the program bank, program counter and status register. Emulation mode does not push the program bank
+	/// and pushes the status with the break flag telling `BRK` apart from IRQ. Decimal mode is cleared,
+	/// further IRQs are disabled and the handler runs in bank 0
+	pub fn exe_interrupt(&mut self, interrupt: Interrupt) {
+		let emulation = self.is_emulation();
+		let vector = interrupt.vector(emulation);
+
+		// Hardware interrupts push the status with the break flag cleared
+		self.status.set(ProcessorStatusFlags::Break, emulation && !interrupt.is_hardware());
+
+		if !emulation {
+			self.push_byte_stack(self.pbr);
+		}
+		self.push_long_stack(self.pc);
+		self.push_byte_stack(self.status.get_stack_bits());
+		self.update_register_modes();
+
+		self.status.clear_flag(ProcessorStatusFlags::Decimal);
+		self.status.set_flag(ProcessorStatusFlags::IRQdisable);
+		self.pbr = 0;
+		self.pc = self.mem_read_long(vector, vector.wrapping_add(1));
 	}
 
 	/// Break (Stack/Interrupt)
+	/// 
+	/// The byte after the op code is a signature that is skipped, so the handler returns to the instruction after it
 	pub fn exe_brk(&mut self, data: u16) {
-		self.exe_interrupt(0x00FFE6);
+		self.exe_interrupt(Interrupt::Brk);
 	}
 	
 	/// Co-Processor (Stack/Interrupt)
 	pub fn exe_cop(&mut self, data: u16) {
-		self.exe_interrupt(0x00FFE4);
+		self.exe_interrupt(Interrupt::Cop);
 	}
 	
 	/// Return from Interrupt (Stack (RTI))
+	/// 
+	/// Counterpart of `Cpu::exe_interrupt`, the program bank is only pulled in native mode
 	pub fn exe_rti(&mut self, data: u16) {
 		let status_bits = self.pull_byte_stack();
-		self.status.set_bits(status_bits);
+		self.status.set_stack_bits(status_bits);
+		self.update_register_modes();
 		self.pc = self.pull_long_stack();
-		self.pbr = self.pull_byte_stack();
-
+		if !self.is_emulation() {
+			self.pbr = self.pull_byte_stack();
+		}
 	}
 	
 	/// Wait for Interrupt (Implied)
@@ -34,4 +92,134 @@ impl Cpu {
 	}
 	
 	
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::cpu::RESET_VECTOR;
+
+	/// Every interrupt handler is at `$8000` plus the low byte of its vector and starts with `RTI`
+	fn handler(vector: u32) -> u16 {
+		0x8000 | (vector & 0xFF) as u16
+	}
+
+	/// CPU with a 32 KiB LoROM cartridge holding the vectors and handlers, reset and stopped at `$00:0000` in WRAM
+	fn cpu() -> Cpu {
+		let mut rom = vec![0; 0x8000];
+		rom[0x7FC0..0x7FD5].fill(b' ');
+		rom[0x7FD5] = 0x20;
+		rom[0x7FDE..0x7FE0].copy_from_slice(&[0xFF, 0xFF]);
+		for vector in (0x00FFE4..=0x00FFEE).step_by(2).chain((0x00FFF4..=0x00FFFE).step_by(2)) {
+			let i = (vector & 0x7FFF) as usize;
+			rom[i..i + 2].copy_from_slice(&handler(vector).to_le_bytes());
+			rom[(handler(vector) & 0x7FFF) as usize] = 0x40;
+		}
+
+		let mut cpu = Cpu::new();
+		cpu.memory.insert_cartridge(&rom).unwrap();
+		cpu.reset();
+		cpu.pc = 0x0000;
+		cpu
+	}
+
+	/// CPU in native mode with 16 bit registers, `BRK` and `COP` at `$7E:0000`
+	fn native_cpu() -> Cpu {
+		let mut cpu = cpu();
+		cpu.memory.poke(0x7E0000, 0x00);
+		cpu.memory.poke(0x7E0002, 0x02);
+		cpu.status = ProcessorStatusFlags::from_bits_retain(0x08);
+		cpu.update_register_modes();
+		cpu.pbr = 0x7E;
+		cpu.sp = 0x1FF0;
+		cpu
+	}
+
+	fn stack(cpu: &Cpu, len: u16) -> Vec<u8> {
+		(1..=len).map(|i| cpu.memory.peek(cpu.sp.wrapping_add(i) as u32).unwrap()).collect()
+	}
+
+	#[test]
+	fn test_reset_loads_vector() {
+		let mut cpu = cpu();
+		cpu.sp = 0x1234;
+		cpu.reset();
+		assert_eq!(cpu.get_pc_addr(), handler(RESET_VECTOR) as u32);
+		assert_eq!(cpu.sp, 0x01FF);
+		assert!(cpu.is_emulation());
+		assert!(cpu.status.contains(ProcessorStatusFlags::IRQdisable));
+	}
+
+	#[test]
+	fn test_native_nmi() {
+		let mut cpu = native_cpu();
+		cpu.step(true, false).unwrap();
+		assert_eq!(cpu.get_pc_addr(), handler(0x00FFEA) as u32);
+		// Status, PC and PBR are pushed, in that order from the top of the stack
+		assert_eq!(stack(&cpu, 4), [0x08, 0x00, 0x00, 0x7E]);
+		assert!(!cpu.status.contains(ProcessorStatusFlags::Decimal));
+		assert!(cpu.status.contains(ProcessorStatusFlags::IRQdisable));
+
+		cpu.step(false, false).unwrap();
+		assert_eq!(cpu.get_pc_addr(), 0x7E0000);
+		assert_eq!(cpu.sp, 0x1FF0);
+		assert!(cpu.status.contains(ProcessorStatusFlags::Decimal));
+	}
+
+	#[test]
+	fn test_native_vectors() {
+		let mut cpu = native_cpu();
+		cpu.step(false, false).unwrap();
+		assert_eq!(cpu.get_pc_addr(), handler(0x00FFE6) as u32);
+		// BRK skips its signature byte
+		assert_eq!(stack(&cpu, 4), [0x08, 0x02, 0x00, 0x7E]);
+		cpu.step(false, false).unwrap();
+
+		cpu.step(false, false).unwrap();
+		assert_eq!(cpu.get_pc_addr(), handler(0x00FFE4) as u32);
+		cpu.step(false, false).unwrap();
+
+		// IRQs are disabled by interrupt entry and enabled again by RTI
+		cpu.step(false, true).unwrap();
+		assert_eq!(cpu.get_pc_addr(), handler(0x00FFEE) as u32);
+	}
+
+	#[test]
+	fn test_emulation_nmi() {
+		let mut cpu = cpu();
+		cpu.status.set_flag(ProcessorStatusFlags::Decimal);
+		cpu.step(true, false).unwrap();
+		assert_eq!(cpu.get_pc_addr(), handler(0x00FFFA) as u32);
+		// No PBR, the pushed status has the break flag cleared
+		assert_eq!(cpu.sp, 0x01FC);
+		assert_eq!(stack(&cpu, 3), [0x2C, 0x00, 0x00]);
+		assert!(!cpu.status.contains(ProcessorStatusFlags::Decimal));
+
+		cpu.step(false, false).unwrap();
+		assert_eq!(cpu.get_pc_addr(), 0x000000);
+		assert_eq!(cpu.sp, 0x01FF);
+	}
+
+	#[test]
+	fn test_emulation_brk_and_irq_share_vector() {
+		let mut cpu = cpu();
+		cpu.memory.poke(0x000000, 0x00);
+		cpu.step(false, false).unwrap();
+		assert_eq!(cpu.get_pc_addr(), handler(0x00FFFE) as u32);
+		assert_eq!(stack(&cpu, 3), [0x34, 0x02, 0x00]);
+		cpu.step(false, false).unwrap();
+		assert_eq!(cpu.get_pc_addr(), 0x000002);
+
+		cpu.status.clear_flag(ProcessorStatusFlags::IRQdisable);
+		cpu.step(false, true).unwrap();
+		assert_eq!(cpu.get_pc_addr(), handler(0x00FFFE) as u32);
+		assert_eq!(stack(&cpu, 3), [0x20, 0x02, 0x00]);
+	}
+
+	#[test]
+	fn test_emulation_cop_vector() {
+		let mut cpu = cpu();
+		cpu.memory.poke(0x000000, 0x02);
+		cpu.step(false, false).unwrap();
+		assert_eq!(cpu.get_pc_addr(), handler(0x00FFF4) as u32);
+	}
+}
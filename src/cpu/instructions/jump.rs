@@ -15,8 +15,8 @@ impl Cpu {
 	
 	/// Jump to Subroutine (Absolute)
 	pub fn exe_jsr(&mut self, long_addr: u32) {
-		// push program counter plus 3 to get $OP of next instruction
-		self.push_long_stack(self.pc.wrapping_add(2));
+		// push address of the last byte of this instruction, the program counter already points to the next instruction
+		self.push_long_stack(self.pc.wrapping_sub(1));
 		self.pc = long_addr as u16;
 	}
 	
@@ -24,8 +24,8 @@ impl Cpu {
 	pub fn exe_jsl(&mut self, long_addr: u32) {
 		// push program bank register
 		self.push_byte_stack(self.pbr);
-		// push program counter plus 3 to get $OP of next instruction
-		self.push_long_stack(self.pc.wrapping_add(3));
+		// push address of the last byte of this instruction, the program counter already points to the next instruction
+		self.push_long_stack(self.pc.wrapping_sub(1));
 		self.pc = long_addr as u16;
 		self.pbr = (long_addr >> 16) as u8;
 	}
@@ -1,5 +1,7 @@
 use self::instructions::Instruction;
 
+use super::processorstatusflag::ProcessorStatusFlags;
+
 pub mod arithmetic;
 pub mod r#move;
 pub mod branch;
@@ -13,7 +15,9 @@ pub mod stack;
 pub mod transfer;
 
 pub mod instructions;
+pub mod decode;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AddressingMode {
     /// Argument is absolute 2 bytes
     Absolute,
@@ -34,9 +38,9 @@ pub enum AddressingMode {
     DirectY,
     /// Argument is implied
     Implied,
-    /// Argument is 1 byte constant
+    /// Argument is 1 byte constant, only used when the register the instruction works on is in 8 bit mode
     Immediate,
-    /// Argument is 2 byte constant
+    /// Argument is 2 byte constant, used instead of `Immediate` when the register the instruction works on is in 16 bit mode
     ImmediateLong,
     /// Argument is memory address that is inside memory address specified by argument (ie `LDA $XX` would read memory address in `$0000XX`)
     Indirect,
@@ -65,119 +69,49 @@ pub enum AddressingMode {
 impl Instruction {
     /// Length of this instruction in bytes, includes op code and all arguments
     /// 
-    /// ADC #$21 #$33 would have a length of 3
-    pub fn get_length(&self) -> usize {
-        use Instruction::*;
-        match self {
-            Asl | Clc | Cld | Cli | Clv | Dea | Dex | Dey | Ina | Inx | Iny | Lsr | Nop | Pha | Phb | Phd | Phk | Php | Phx | Phy | Pla | Plb | Pld | Plp | Plx | Ply | Rol | Ror | Rti | Rtl | Rts | Sec | Sed | Sei | Stp | Tax | Tay | Tcd | Tcs | Tdc | Tsc | Tsx | Txa | Txs | Txy | Tya | Tyx | Wai | Xba | Xce  => 1,
-            AdcDPIX | AdcSR | AdcDP | AdcDPLong | AdcImm | AdcDPIY | AdcDPI | AdcSRY | AdcDPX | AdcDPIYLong | AndDPIX | AndSR | AndDP | AndDPLong | AndImm | AndDPIY | AndDPI | AndSRY | AndDPX | AndDPIYLong | AslDP | AslDPX | Blt | Bge | Beq | BitDP | BitDPX | BitImm | Bmi | Bne | Bpl | Bra | Brk | Bvc | Bvs | CmpDPIX | CmpSR | CmpDP | CmpDPLong | CmpImm | CmpDPIY | CmpDPI | CmpSRY | CmpDPX | CmpDPIYLong | Cop | CpxImm | CpxDP | CpyImm | CpyDP | DecDP | DecDPX | EorDPIX | EorSR | EorDP | EorDPLong | EorImm | EorDPIY | EorDPI | EorSRY | EorDPX | EorDPIYLong | IncDP | IncDPX | LdaDPIX | LdaSR | LdaDP | LdaDPLong | LdaImm | LdaDPIY | LdaDPI | LdaSRY | LdaDPX | LdaDPIYLong | LdxImm | LdxDP | LdxDPY | LdyImm | LdyDP | LdyDPX | LsrDP | LsrDPX | OraDPIX | OraSR | OraDP | OraDPLong | OraImm | OraDPIY | OraDPI | OraSRY | OraDPX | OraDPIYLong | Pei | RepImm | RolDP | RolDPX | RorDP | RorDPX | SbcDPIX | SbcSR | SbcDP | SbcDPLong | SbcImm | SbcDPIY | SbcDPI | SbcSRY | SbcDPX | SbcDPIYLong | SepImm | StaDPIX | StaSR | StaDP | StaDPLong | StaDPIY | StaDPI | StaSRY | StaDPX | StaDPIYLong | StxDP | StxDPY | StyDP | StyDPX | StzDP | StzDPX | TrbDP | TsbDP | Wdm  => 2,
-            AdcAbs | AdcAbsY | AdcAbsX | AndAbs | AndAbsY | AndAbsX | AslAbs | AslAbsX | BitAbs | BitAbsX | Brl | CmpAbs | CmpAbsY | CmpAbsX | CpxAbs | CpyAbs | DecAbs | DecAbsX | EorAbs | EorAbsY | EorAbsX | IncAbs | IncAbsX | JmpAbs | JmpIndirect | JmpAbsIX | Jml | JsrAbs | JsrAbsIX | LdaAbs | LdaAbsY | LdaAbsX | LdxAbs | LdxAbsY | LdyAbs | LdyAbsX | LsrAbs | LsrAbsX | Mvn | Mvp | OraAbs | OraAbsY | OraAbsX | Pea | Per | RolAbs | RolAbsX | RorAbs | RorAbsX | SbcAbs | SbcAbsY | SbcAbsX | StaAbs | StaAbsY | StaAbsX | StxAbs | StyAbs | StzAbs | StzAbsX | TrbAbs | TsbAbs  => 3,
-            AdcAbsLong | AdcAbsXLong | AndAbsLong | AndAbsXLong | CmpAbsLong | CmpAbsXLong | EorAbsLong | EorAbsXLong | JmlAbsLong | JslAbsLong | LdaAbsLong | LdaAbsXLong | OraAbsLong | OraAbsXLong | SbcAbsLong | SbcAbsXLong | StaAbsLong | StaAbsXLong  => 4,
+    /// ADC #$21 #$33 would have a length of 3. Immediate instructions on the accumulator or the index registers
+    /// have a 2 byte argument when that register is in 16 bit mode in `status`
+    pub fn get_length(&self, status: ProcessorStatusFlags) -> usize {
+        match self.get_addressing_mode(status) {
+            AddressingMode::ImmediateLong => 3,
+            _ => self.get_base_length(),
         }
     }
-        
-    /// Get cycle duration of this instruction. This cycle time is then added to the cpu after instruction completes to sync with ie PPU
-    pub fn get_cycle_time(&self) -> usize {
-        use Instruction::*;
-        match self {
-            Wdm  => 0,
-            Mvn | Mvp  => 1,
-            AdcImm | AndImm | Asl | Blt | Bge | Beq | BitImm | Bmi | Bne | Bpl | Bvc | Bvs | Clc | Cld | Cli | Clv | CmpImm | CpxImm | CpyImm | Dea | Dex | Dey | EorImm | Ina | Inx | Iny | LdaImm | LdxImm | LdyImm | Lsr | Nop | OraImm | Rol | Ror | SbcImm | Sec | Sed | Sei | Tax | Tay | Tcd | Tcs | Tdc | Tsc | Tsx | Txa | Txs | Txy | Tya | Tyx | Xce  => 2,
-            AdcDP | AndDP | BitDP | Bra | CmpDP | CpxDP | CpyDP | EorDP | JmpAbs | LdaDP | LdxDP | LdyDP | OraDP | Pha | Phb | Phk | Php | Phx | Phy | RepImm | SbcDP | SepImm | StaDP | Stp | StxDP | StyDP | StzDP | Wai | Xba  => 3,
-            AdcSR | AdcAbs | AdcDPX | AdcAbsY | AdcAbsX | AndSR | AndAbs | AndDPX | AndAbsY | AndAbsX | BitAbs | BitDPX | BitAbsX | Brl | CmpSR | CmpAbs | CmpDPX | CmpAbsY | CmpAbsX | CpxAbs | CpyAbs | EorSR | EorAbs | EorDPX | EorAbsY | EorAbsX | JmlAbsLong | LdaSR | LdaAbs | LdaDPX | LdaAbsY | LdaAbsX | LdxAbs | LdxDPY | LdxAbsY | LdyAbs | LdyDPX | LdyAbsX | OraSR | OraAbs | OraDPX | OraAbsY | OraAbsX | Phd | Pla | Plb | Plp | Plx | Ply | SbcSR | SbcAbs | SbcDPX | SbcAbsY | SbcAbsX | StaSR | StaAbs | StaDPX | StxAbs | StxDPY | StyAbs | StyDPX | StzDPX | StzAbs  => 4,
-            AdcAbsLong | AdcDPIY | AdcDPI | AdcAbsXLong | AndAbsLong | AndDPIY | AndDPI | AndAbsXLong | AslDP | CmpAbsLong | CmpDPIY | CmpDPI | CmpAbsXLong | DecDP | EorAbsLong | EorDPIY | EorDPI | EorAbsXLong | IncDP | JmpIndirect | LdaAbsLong | LdaDPIY | LdaDPI | LdaAbsXLong | LsrDP | OraAbsLong | OraDPIY | OraDPI | OraAbsXLong | Pea | Pld | RolDP | RorDP | SbcAbsLong | SbcDPIY | SbcDPI | SbcAbsXLong | StaAbsLong | StaDPI | StaAbsY | StaAbsX | StaAbsXLong | StzAbsX | TrbDP | TsbDP  => 5,
-            AdcDPIX | AdcDPLong | AdcDPIYLong | AndDPIX | AndDPLong | AndDPIYLong | AslAbs | AslDPX | CmpDPIX | CmpDPLong | CmpDPIYLong | DecAbs | DecDPX | EorDPIX | EorDPLong | EorDPIYLong | IncAbs | IncDPX | JmpAbsIX | Jml | JsrAbs | LdaDPIX | LdaDPLong | LdaDPIYLong | LsrAbs | LsrDPX | OraDPIX | OraDPLong | OraDPIYLong | Pei | Per | RolAbs | RolDPX | RorAbs | RorDPX | Rti | Rtl | Rts | SbcDPIX | SbcDPLong | SbcDPIYLong | StaDPIX | StaDPLong | StaDPIY | StaDPIYLong | TrbAbs | TsbAbs  => 6,
-            AdcSRY | AndSRY | AslAbsX | Brk | CmpSRY | Cop | DecAbsX | EorSRY | IncAbsX | LdaSRY | LsrAbsX | OraSRY | RolAbsX | RorAbsX | SbcSRY | StaSRY  => 7,
-            JslAbsLong | JsrAbsIX  => 8,
+
+    /// Returns the addressing mode of this instruction, immediate instructions are `ImmediateLong` when
+    /// the register they work on is in 16 bit mode in `status`
+    pub fn get_addressing_mode(&self, status: ProcessorStatusFlags) -> AddressingMode {
+        match (self.get_base_addressing_mode(), self.get_register_size_flag()) {
+            (AddressingMode::Immediate, Some(flag)) if !status.contains(flag) => AddressingMode::ImmediateLong,
+            (mode, _) => mode,
         }
     }
-    
-    pub fn get_addressing_mode(&self) -> AddressingMode {
-        use AddressingMode::*;
+
+    /// Returns the flag that decides whether the register this instruction loads, stores or compares is 8 or 16 bits,
+    /// this also decides the size of the immediate argument. `None` for instructions that don't work on a register this way
+    pub fn get_register_size_flag(&self) -> Option<ProcessorStatusFlags> {
         use Instruction::*;
         match self {
-            AdcDPIX | AndDPIX | CmpDPIX | EorDPIX | LdaDPIX | OraDPIX | SbcDPIX | StaDPIX  
-            => IndirectX,
-            
-            AdcSR | AndSR | CmpSR | EorSR | LdaSR | OraSR | SbcSR | StaSR  
-            => StackRelative,
-            
-            AdcDP | AndDP | AslDP | BitDP | CmpDP | CpxDP | CpyDP | DecDP | EorDP | IncDP | 
-            LdaDP | LdxDP | LdyDP | LsrDP | OraDP | RolDP | RorDP | SbcDP | StaDP | StxDP |
-            StyDP | StzDP | TrbDP | TsbDP  
-            => Direct,
-            
-            AdcDPLong | AndDPLong | CmpDPLong | EorDPLong | LdaDPLong | 
-            OraDPLong | SbcDPLong | StaDPLong  
-            => IndirectLong,
-            
-            AdcImm | AndImm | BitImm | CmpImm | CpxImm | CpyImm | EorImm | LdaImm | LdxImm |
-            LdyImm | OraImm | RepImm | SbcImm | SepImm  
-            => Immediate,
-            
-            AdcAbs | AndAbs | AslAbs | BitAbs | CmpAbs | CpxAbs | CpyAbs | DecAbs | EorAbs |
-            IncAbs | JmpAbs | JsrAbs | LdaAbs | LdxAbs | LdyAbs | LsrAbs | OraAbs | RolAbs |
-            RorAbs | SbcAbs | StaAbs | StxAbs | StyAbs | StzAbs | TrbAbs | TsbAbs  
-            => Absolute,
-            
-            AdcAbsLong | AndAbsLong | CmpAbsLong | EorAbsLong | JmlAbsLong | JslAbsLong |
-            LdaAbsLong | OraAbsLong | SbcAbsLong | StaAbsLong  
-            => Long,
-            
-            AdcDPIY | AndDPIY | CmpDPIY | EorDPIY | LdaDPIY | OraDPIY |
-            SbcDPIY | StaDPIY  
-            => IndirectY,
-            
-            AdcDPI | AndDPI | CmpDPI | EorDPI | LdaDPI | OraDPI | SbcDPI | StaDPI  
-            => Indirect,
-            
-            AdcSRY | AndSRY | CmpSRY | EorSRY | LdaSRY | OraSRY | SbcSRY | StaSRY  
-            => StackRelativeY,
-            
-            AdcDPX | AndDPX | AslDPX | BitDPX | CmpDPX | DecDPX | EorDPX | IncDPX |
-            LdaDPX | LdyDPX | LsrDPX | OraDPX | RolDPX | RorDPX | SbcDPX | StaDPX |
-            StyDPX | StzDPX 
-            => DirectX,
-            
-            AdcDPIYLong | AndDPIYLong | CmpDPIYLong | EorDPIYLong | LdaDPIYLong |
-            OraDPIYLong | SbcDPIYLong | StaDPIYLong
-            => IndirectLongY,
-            
-            AdcAbsY | AndAbsY | CmpAbsY | EorAbsY | LdaAbsY | LdxAbsY | OraAbsY |
-            SbcAbsY | StaAbsY  
-            => AbsoluteY,
-            
-            AdcAbsX | AndAbsX | AslAbsX | BitAbsX | CmpAbsX | DecAbsX | EorAbsX |
-            IncAbsX | LdaAbsX | LdyAbsX | LsrAbsX | OraAbsX | RolAbsX | RorAbsX |
-            SbcAbsX | StaAbsX | StzAbsX 
-            => AbsoluteX,
-            AdcAbsXLong | AndAbsXLong | CmpAbsXLong | EorAbsXLong | LdaAbsXLong |
-            OraAbsXLong | SbcAbsXLong | StaAbsXLong 
-            => LongX,
-            
-            Asl | Brk | Clc | Cld | Cli | Clv | Cop | Dea | Dex | Dey | Ina | Inx
-            | Iny | Lsr | Nop | Pea | Pei | Per | Pha | Phb | Phd | Phk | Php | Phx
-            | Phy | Pla | Plb | Pld | Plp | Plx | Ply | Rol | Ror | Rti | Rtl | Rts
-            | Sec | Sed | Sei | Stp | Tax | Tay | Tcd | Tcs | Tdc | Tsc | Tsx | Txa
-            | Txs | Txy | Tya | Tyx | Wai | Wdm | Xba | Xce  
-            => Implied,
-            
-            Blt | Bge | Beq | Bmi | Bne | Bpl | Bra | Bvc | Bvs => Relative,
-            
-            Brl  => RelativeLong,
-            
-            JmpIndirect  => AbsoluteIndirect,
-            
-            JmpAbsIX | JsrAbsIX  => AbsoluteIndirectX,
-            
-            Jml  => AbsoluteIndirectLong,
-            
-            LdxDPY | StxDPY  => DirectY,
-            
-            Mvn | Mvp  => Move,
+            AdcAbs | AdcAbsLong | AdcAbsX | AdcAbsXLong | AdcAbsY | AdcDP | AdcDPI | AdcDPIX | AdcDPIY | AdcDPIYLong | AdcDPLong | AdcDPX | AdcImm | AdcSR | AdcSRY |
+            AndAbs | AndAbsLong | AndAbsX | AndAbsXLong | AndAbsY | AndDP | AndDPI | AndDPIX | AndDPIY | AndDPIYLong | AndDPLong | AndDPX | AndImm | AndSR | AndSRY |
+            CmpAbs | CmpAbsLong | CmpAbsX | CmpAbsXLong | CmpAbsY | CmpDP | CmpDPI | CmpDPIX | CmpDPIY | CmpDPIYLong | CmpDPLong | CmpDPX | CmpImm | CmpSR | CmpSRY |
+            EorAbs | EorAbsLong | EorAbsX | EorAbsXLong | EorAbsY | EorDP | EorDPI | EorDPIX | EorDPIY | EorDPIYLong | EorDPLong | EorDPX | EorImm | EorSR | EorSRY |
+            LdaAbs | LdaAbsLong | LdaAbsX | LdaAbsXLong | LdaAbsY | LdaDP | LdaDPI | LdaDPIX | LdaDPIY | LdaDPIYLong | LdaDPLong | LdaDPX | LdaImm | LdaSR | LdaSRY |
+            OraAbs | OraAbsLong | OraAbsX | OraAbsXLong | OraAbsY | OraDP | OraDPI | OraDPIX | OraDPIY | OraDPIYLong | OraDPLong | OraDPX | OraImm | OraSR | OraSRY |
+            SbcAbs | SbcAbsLong | SbcAbsX | SbcAbsXLong | SbcAbsY | SbcDP | SbcDPI | SbcDPIX | SbcDPIY | SbcDPIYLong | SbcDPLong | SbcDPX | SbcImm | SbcSR | SbcSRY |
+            StaAbs | StaAbsLong | StaAbsX | StaAbsXLong | StaAbsY | StaDP | StaDPI | StaDPIX | StaDPIY | StaDPIYLong | StaDPLong | StaDPX | StaSR | StaSRY |
+            BitAbs | BitAbsX | BitDP | BitDPX | BitImm | StzAbs | StzAbsX | StzDP | StzDPX
+            => Some(ProcessorStatusFlags::Accumulator8bit),
+
+            CpxAbs | CpxDP | CpxImm | CpyAbs | CpyDP | CpyImm |
+            LdxAbs | LdxAbsY | LdxDP | LdxDPY | LdxImm | LdyAbs | LdyAbsX | LdyDP | LdyDPX | LdyImm |
+            StxAbs | StxDP | StxDPY | StyAbs | StyDP | StyDPX
+            => Some(ProcessorStatusFlags::XYreg8bit),
+
+            _ => None,
         }
     }
-    
+
     /// Returns true if this instruction writes back to memory during its execution
     /// 
     /// In that case, the instruction should get passed an address instead of the data
@@ -194,4 +128,13 @@ impl Instruction {
             _ => false,
         }
     }
+
+    /// Returns true if this instruction only writes to memory
+    pub fn is_store(&self) -> bool {
+        use Instruction::*;
+        matches!(self,
+            StaAbs | StaAbsLong | StaAbsX | StaAbsXLong | StaAbsY | StaDP | StaDPI | StaDPIX | StaDPIY | StaDPIYLong | StaDPLong | StaDPX | StaSR | StaSRY |
+            StxAbs | StxDP | StxDPY | StyAbs | StyDP | StyDPX | StzAbs | StzAbsX | StzDP | StzDPX
+        )
+    }
 }
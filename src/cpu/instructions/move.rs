@@ -25,7 +25,7 @@ impl Cpu {
 		self.y = self.y.wrapping_add(1);
 		self.acc = self.acc.wrapping_sub(1);
 		if self.get_acc() != 0xFFFF {
-			//decrement by 3 to counteract the increment by 3 that happens after execution (thus keeping pc at this instruction)
+			//decrement by 3 to counteract the increment by 3 that happens before execution (thus keeping pc at this instruction)
 			self.pc = self.pc.wrapping_sub(3); 
 		}
 	}
@@ -53,7 +53,7 @@ impl Cpu {
 		self.y = self.y.wrapping_sub(1);
 		self.acc = self.acc.wrapping_sub(1);
 		if self.get_acc() != 0xFFFF {
-			//decrement by 3 to counteract the increment by 3 that happens after execution (thus keeping pc at this instruction)
+			//decrement by 3 to counteract the increment by 3 that happens before execution (thus keeping pc at this instruction)
 			self.pc = self.pc.wrapping_sub(3); 
 		}
 	}
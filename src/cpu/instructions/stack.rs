@@ -49,7 +49,7 @@ impl Cpu {
 	
 	/// Push Processor Status Register (Stack (Push))
 	pub fn exe_php(&mut self, data: u16) {
-		self.push_byte_stack(self.status.bits() as u8)
+		self.push_byte_stack(self.status.get_stack_bits())
 	}
 	
 	/// Push Index Register X (Stack (Push))
@@ -118,15 +118,8 @@ impl Cpu {
 	/// Pull Processor Status Register (Stack (Pull))
 	pub fn exe_plp(&mut self, data: u16) {
 		let flag_bits = self.pull_byte_stack();
-		self.status.set_bits(flag_bits);
-		
-		if flag_bits == 0 {
-			self.status.set_flag(ProcessorStatusFlags::Zero);
-		}
-
-		if (flag_bits as i8) < 0 {
-			self.status.set_flag(ProcessorStatusFlags::Negative);
-		}
+		self.status.set_stack_bits(flag_bits);
+		self.update_register_modes();
 	}
 	
 	/// Pull Index Register X (Stack (Pull))
@@ -19,7 +19,7 @@ impl Cpu {
 	
 	/// Transfer 16-bit Accumulator to Stack Pointer (Implied)
 	pub fn exe_tcs(&mut self) {
-		self.sp = self.get_acc();
+		self.set_sp(self.acc);
 	}
 	
 	/// Transfer Direct Page Register to 16-bit Accumulator (Implied)
@@ -44,7 +44,7 @@ impl Cpu {
 	
 	/// Transfer Index Register X to Stack Pointer (Implied)
 	pub fn exe_txs(&mut self) {
-		self.sp = self.get_x();
+		self.set_sp(self.get_x());
 	}
 	
 	/// Transfer Index Register X to Index Register Y (Implied)
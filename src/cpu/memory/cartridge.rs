@@ -1,3 +1,5 @@
+use crate::region::Region;
+
 #[derive(Debug)]
 pub enum CartridgeRegion {
     Japan,
@@ -21,6 +23,20 @@ pub enum CartridgeRegion {
     Unidentified,
 }
 
+impl CartridgeRegion {
+    /// Video standard of the consoles this cartridge was sold for, unknown regions are assumed to be NTSC
+    /// 
+    /// Brazil used PAL-M, which has the same timing as NTSC
+    pub fn video_region(&self) -> Region {
+        use CartridgeRegion::*;
+        match self {
+            Japan | NorthAmerica | SouthKorea | Canada | Brazil | CommonInternational | Unidentified => Region::Ntsc,
+            Europe | Sweden | Finland | Denmark | France | Netherlands | Spain | Germany | Italy
+            | China | Indonesia | Australia => Region::Pal,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum CartridgeParseError {
     HeaderNotFound,
@@ -40,6 +56,8 @@ pub struct CartridgeMetadata {
     game_code: String,
     rom_size: usize,
     ram_size: usize,
+    /// Checksum from the header at `$FFDC`, identifies the ROM in save states
+    checksum: u16,
     pub has_copier_bytes: bool,
 }
 
@@ -54,6 +72,7 @@ impl CartridgeMetadata {
             game_code: String::from(""),
             rom_size: 0,
             ram_size: 0,
+            checksum: 0,
             has_copier_bytes: false,
         }
     }
@@ -64,6 +83,20 @@ impl CartridgeMetadata {
         }
     }
     
+    pub fn get_region(&self) -> &CartridgeRegion {
+        &self.region
+    }
+
+    pub fn get_checksum(&self) -> u16 {
+        self.checksum
+    }
+
+    pub fn set_checksum(&mut self, checksum: u16) {
+        if self.checksum == 0 {
+            self.checksum = checksum;
+        }
+    }
+
     pub fn set_region(&mut self, region_id: u8) {
         use CartridgeRegion::*;
         if matches!(self.region, Unidentified) {       
@@ -32,6 +32,13 @@ impl Mappermode for ExHiROM {
         todo!()
     }
 
+    /// ExHiROM mapping is not implemented yet, so nothing can be read
+    fn peek(&self, _long_addr: u32) -> Option<u8> {
+        None
+    }
+
+    fn poke(&mut self, _long_addr: u32, _value: u8) {}
+
     fn copy_bytes_to_rom(&mut self, bytes: &[u8]) {
         for byte in bytes {
             self.rom.push(*byte);
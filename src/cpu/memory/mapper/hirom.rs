@@ -62,11 +62,14 @@ impl HiROM {
     /// Returns index for internal sram vector based on `long_addr`
     fn sram_index_from_long_addr(&self, long_addr: u32) -> Option<usize> {
         let (bank, hhll) = separate_bank_hhll_addr!(long_addr);
+        if self.sram.is_empty() {
+            return None;
+        }
         
         let bank_i = match bank {
             0x30..=0x3F => bank - 0x30,
             0xB0..=0xBF => bank - 0xB0,
-            _ => unreachable!()
+            _ => return None,
         } as usize;
         
         // Mirror bank index if needed
@@ -82,8 +85,23 @@ impl HiROM {
     }
 
     pub fn read_rom(&self, long_addr: u32) -> Option<u8> {
+        self.rom_index_from_long_addr(long_addr).map(|i| self.rom[i])
+    }
+
+    /// Overwrites a byte of ROM, only for tools patching the ROM since the CPU can't write to it
+    pub fn write_rom(&mut self, long_addr: u32, value: u8) {
+        if let Some(i) = self.rom_index_from_long_addr(long_addr) {
+            self.rom[i] = value;
+        }
+    }
+
+    /// Returns index for internal rom vector based on `long_addr`, `None` if it is outside of the ROM
+    fn rom_index_from_long_addr(&self, long_addr: u32) -> Option<usize> {
         // get $BB and $HHLL as separate numbers, to make range checking a bit easier
         let (bank, hi_lo_byte) = separate_bank_hhll_addr!(long_addr);
+        if self.rom.is_empty() {
+            return None;
+        }
 
         // get indices for bank and address
         let bank_i = match bank {
@@ -100,8 +118,7 @@ impl HiROM {
 
         // calculate final index using bank index and hhll index
         let i = bank_i * ROM_BANK_SIZE + hi_lo_byte_i;
-
-        Some(self.rom[i as usize])
+        (i < self.rom.len()).then_some(i)
     }
 }
 
@@ -139,6 +156,22 @@ impl Mappermode for HiROM {
         }
     }
 
+    fn peek(&self, long_addr: u32) -> Option<u8> {
+        self.read(long_addr)
+    }
+
+    fn poke(&mut self, long_addr: u32, value: u8) {
+        let (bank, hhll) = separate_bank_hhll_addr!(long_addr);
+        match (bank, hhll) {
+            (0x30..=0x3F | 0xB0..=0xBF, 0x6000..=0x7FFF) => self.write_sram(long_addr, value),
+
+            (0xC0..=0xFF, 0x0000..=0xFFFF) |
+            (0x00..=0x3F | 0x80..=0xBF, 0x8000..=0xFFFF) => self.write_rom(long_addr, value),
+
+            _ => {},
+        }
+    }
+
     fn copy_bytes_to_rom(&mut self, bytes: &[u8]) {
         for byte in bytes {
             self.rom.push(*byte);
@@ -5,7 +5,8 @@ pub mod exhirom;
 use super::cartridge::{CartridgeMetadata, CartridgeParseError};
 
 
-pub trait Mappermode {
+/// Mappers are `Send` so the console can run on its own thread
+pub trait Mappermode: Send {
 
     /// Handles reading memory in the following regions:
     /// * Q1 upper half
@@ -21,6 +22,12 @@ pub trait Mappermode {
     /// * Q4
     fn write(&mut self, long_addr: u32, value: u8);
 
+    /// Reads the same regions as `Mappermode::read` for tools, never has side effects or panics
+    fn peek(&self, long_addr: u32) -> Option<u8>;
+
+    /// Writes the same regions as `Mappermode::write` for tools, without side effects. Unlike the CPU this also writes to ROM
+    fn poke(&mut self, long_addr: u32, value: u8);
+
     /// Copy raw bytes to internal rom vector
     fn copy_bytes_to_rom(&mut self, bytes: &[u8]);
 
@@ -73,6 +80,7 @@ pub trait Mappermode {
         }
 
         let mut metadata = CartridgeMetadata::new();
+        metadata.set_checksum(checksum);
 
         // header version 3 
         if readf!(0xFFDA) == 0x33 {
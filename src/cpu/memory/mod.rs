@@ -1,14 +1,21 @@
 
 use std::sync::{Mutex, Arc};
 
-use crate::{ppu::memory::PpuMemory, apu::memory::ApuMemory, separate_bank_hhll_addr};
+use crate::{ppu::memory::PpuMemory, apu::memory::ApuMemory, separate_bank_hhll_addr, savestate::{Snapshot, StateWriter, StateReader, SaveStateError}};
 
 use self::{mapper::{Mappermode, lorom::LoROM, hirom::HiROM, exhirom::ExHiROM}, cartridge::{CartridgeParseError, CartridgeMetadata}, ram::Ram};
 
 
 mod mapper;
 mod ram;
-mod cartridge;
+pub mod cartridge;
+
+/// Master clock cycles of a fast bus access, also the length of an internal CPU cycle
+pub const FAST_ACCESS: usize = 6;
+/// Master clock cycles of a slow bus access
+pub const SLOW_ACCESS: usize = 8;
+/// Master clock cycles of an extra slow bus access
+pub const XSLOW_ACCESS: usize = 12;
 
 /// Struct that represents all memory in the SNES, this is shared between CPU, PPU and APU as they all need to read/write to it
 /// 
@@ -23,6 +30,8 @@ pub struct CpuMemory {
     mapper: Box<dyn Mappermode>,
     pub cartridge_metadata: CartridgeMetadata,
     ram: Ram,
+    /// FastROM enabled through MEMSEL (`$420D`), makes ROM in banks `$80` to `$FF` faster to access
+    fastrom: bool,
     ppu_memory: Arc<Mutex<PpuMemory>>,
     apu_memory: Arc<Mutex<ApuMemory>>,
 }
@@ -34,6 +43,7 @@ impl CpuMemory {
             mapper: Box::new(LoROM::new()),
             cartridge_metadata: CartridgeMetadata::new(),
             ram: Ram::new(),
+            fastrom: false,
             ppu_memory: Arc::new(Mutex::new(PpuMemory::new())),
             apu_memory: Arc::new(Mutex::new(ApuMemory::new())),
         }
@@ -122,13 +132,9 @@ impl CpuMemory {
             (0x80..=0xBF, 0x2000..=0x213F) => self.ppu_memory.lock().unwrap().read(hhll),
 
 
-            // Controller
-            (0x00..=0x3F, 0x4000..=0x41FF) |
-            (0x80..=0xBF, 0x4000..=0x41FF) => todo!(),
-
-            // CPU, DMA
-            (0x00..=0x3F, 0x4200..=0x5FFF) |
-            (0x80..=0xBF, 0x4200..=0x5FFF) => todo!(),
+            // Controller, CPU, DMA registers are not emulated yet, reading them is open bus
+            (0x00..=0x3F, 0x4000..=0x5FFF) |
+            (0x80..=0xBF, 0x4000..=0x5FFF) => None,
 
 
             // Rest of space is dependant on mapper, so mapper will deal with it
@@ -155,13 +161,17 @@ impl CpuMemory {
             // APU
             // (0x00..=0x3F, 0x2140..=0x2143) => self.apu_memory.lock().unwrap().write(hhll, value),
 
-            // Controller
-            (0x00..=0x3F, 0x4000..=0x41FF) |
-            (0x80..=0xBF, 0x4000..=0x41FF) => todo!(),
+            // MEMSEL
+            (0x00..=0x3F, 0x420D) |
+            (0x80..=0xBF, 0x420D) => self.fastrom = byte & 1 == 1,
+
+            // MDMAEN, DMA transfers are not emulated yet so starting one does nothing
+            (0x00..=0x3F, 0x420B) |
+            (0x80..=0xBF, 0x420B) => {},
 
-            // CPU, DMA
-            (0x00..=0x3F, 0x4200..=0x5FFF) |
-            (0x80..=0xBF, 0x4200..=0x5FFF) => todo!(),
+            // Controller, CPU, DMA registers are not emulated yet, writing them does nothing
+            (0x00..=0x3F, 0x4000..=0x5FFF) |
+            (0x80..=0xBF, 0x4000..=0x5FFF) => {},
 
 
             // Rest of space is dependant on mapper, so mapper will deal with it
@@ -170,6 +180,76 @@ impl CpuMemory {
         }
     }
 
+    /// Read a byte for tools like debuggers and cheat engines, without any side effects
+    /// 
+    /// Reads the same memory as `CpuMemory::read`, except that registers with read side effects return what they would read
+    /// without the side effect, or `None` if that isn't possible. Open bus is `None` as well, so `Cpu::mdr` is not involved
+    pub fn peek(&self, long_addr: u32) -> Option<u8> {
+        let (bank, hhll) = separate_bank_hhll_addr!(long_addr);
+
+        match (bank, hhll) {
+            // RAM
+            (0x00..=0x3F | 0x80..=0xBF, 0x0000..=0x1FFF) |
+            (0x7E..=0x7F, 0x0000..=0xFFFF) |
+            (0x00..=0x3F | 0x80..=0xBF, 0x2180..=0x2183) => self.ram.peek(long_addr),
+
+            // PPU, APU registers
+            (0x00..=0x3F | 0x80..=0xBF, 0x2000..=0x213F) => self.ppu_memory.lock().unwrap().peek(hhll),
+
+            // Controller, CPU, DMA
+            (0x00..=0x3F | 0x80..=0xBF, 0x4000..=0x5FFF) => None,
+
+            _ => self.mapper.peek(long_addr),
+        }
+    }
+
+    /// Write a byte for tools like debuggers and cheat engines, without any side effects
+    /// 
+    /// Writes the same memory as `CpuMemory::write`, but data ports don't move their address and ROM can be patched
+    pub fn poke(&mut self, long_addr: u32, byte: u8) {
+        let (bank, hhll) = separate_bank_hhll_addr!(long_addr);
+
+        match (bank, hhll) {
+            // RAM
+            (0x00..=0x3F | 0x80..=0xBF, 0x0000..=0x1FFF) |
+            (0x7E..=0x7F, 0x0000..=0xFFFF) |
+            (0x00..=0x3F | 0x80..=0xBF, 0x2180..=0x2183) => self.ram.poke(long_addr, byte),
+
+            // PPU
+            (0x00..=0x3F | 0x80..=0xBF, 0x2000..=0x213F) => self.ppu_memory.lock().unwrap().poke(hhll, byte),
+
+            // MEMSEL
+            (0x00..=0x3F | 0x80..=0xBF, 0x420D) => self.fastrom = byte & 1 == 1,
+
+            // Controller, CPU, DMA
+            (0x00..=0x3F | 0x80..=0xBF, 0x4000..=0x5FFF) => {},
+
+            _ => self.mapper.poke(long_addr, byte),
+        }
+    }
+
+    /// Number of master clock cycles a CPU bus access to `long_addr` takes
+    /// 
+    /// WRAM, expansion and slow ROM take 8 cycles, most I/O and FastROM take 6 and the old style joypad registers take 12
+    /// 
+    /// Source: [snes wiki](https://snes.nesdev.org/wiki/Timing)
+    pub fn access_time(&self, long_addr: u32) -> usize {
+        let (bank, hhll) = separate_bank_hhll_addr!(long_addr);
+        let rom_speed = if self.fastrom { FAST_ACCESS } else { SLOW_ACCESS };
+
+        match (bank, hhll) {
+            (0x00..=0x3F | 0x80..=0xBF, 0x0000..=0x1FFF) => SLOW_ACCESS,
+            (0x00..=0x3F | 0x80..=0xBF, 0x2000..=0x3FFF) => FAST_ACCESS,
+            (0x00..=0x3F | 0x80..=0xBF, 0x4000..=0x41FF) => XSLOW_ACCESS,
+            (0x00..=0x3F | 0x80..=0xBF, 0x4200..=0x5FFF) => FAST_ACCESS,
+            (0x00..=0x3F | 0x80..=0xBF, 0x6000..=0x7FFF) => SLOW_ACCESS,
+            (0x00..=0x3F, 0x8000..=0xFFFF) => SLOW_ACCESS,
+            (0x80..=0xBF, 0x8000..=0xFFFF) => rom_speed,
+            (0x40..=0x7F, _) => SLOW_ACCESS,
+            (0xC0..=0xFF, _) => rom_speed,
+        }
+    }
+
     /// Get all bytes from sram
     /// 
     /// Can be used to perform save games
@@ -177,3 +257,44 @@ impl CpuMemory {
         self.mapper.get_sram_bytes()
     }
 }
+
+/// Only state owned by the CPU side is saved: WRAM and SRAM. ROM comes from the cartridge, 
+/// PPU and APU memory are saved by their own units
+impl Snapshot for CpuMemory {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.ram.save_state(w);
+        w.write_bool(self.fastrom);
+        w.write_vec(&self.mapper.get_sram_bytes());
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), SaveStateError> {
+        self.ram.load_state(r)?;
+        self.fastrom = r.read_bool()?;
+        let sram = r.read_vec()?;
+        if sram.len() != self.mapper.get_sram_size() {
+            return Err(SaveStateError::InvalidValue("SRAM size"));
+        }
+        self.mapper.copy_bytes_to_sram(&sram);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wram_registers_through_bank_mirrors() {
+        for bank in [0x00_u32, 0x80] {
+            let mut memory = CpuMemory::new();
+            memory.poke(bank << 16 | 0x2181, 0x34);
+            memory.poke(bank << 16 | 0x2182, 0x12);
+            memory.poke(bank << 16 | 0x2183, 0x7F);
+            memory.poke(bank << 16 | 0x2180, 0xAB);
+            assert_eq!(memory.peek(0x7F1234), Some(0xAB), "bank {bank:02X}");
+            assert_eq!(memory.peek(bank << 16 | 0x2180), Some(0xAB), "bank {bank:02X}");
+            // The register is not the WRAM byte at $2180
+            assert_eq!(memory.peek(0x7E2180), Some(0x00), "bank {bank:02X}");
+        }
+    }
+}
@@ -1,15 +1,15 @@
-use crate::{separate_bank_hhll_addr, set_ll, set_bb, set_hh};
+use crate::{separate_bank_hhll_addr, set_ll, set_bb, set_hh, savestate::{Snapshot, StateWriter, StateReader, SaveStateError}};
 
 /// Total bytes in RAM
-const RAM_SIZE: usize = 2 * 0xFFFF;
+const RAM_SIZE: usize = 2 * RAM_BANK_SIZE;
 
-const RAM_BANK_SIZE: u16 = 0xFFFF;
+const RAM_BANK_SIZE: usize = 0x10000;
 
 /// CPU Ram
 pub struct Ram {
     /// Raw ram bytes
     bytes: [u8; RAM_SIZE],
-    /// pointer for use with WRAM access registers ($2180 to $2183)
+    /// pointer for use with WRAM access registers ($2180 to $2183), a 17 bit index into `bytes`
     pointer: usize,
 }
 
@@ -29,9 +29,9 @@ impl Ram {
     /// 
     /// Ie, if `hhll > $2000` then `bank == $7E | $7F` and the other way around with the mirrored sections
     pub fn read(&mut self, long_addr: u32) -> Option<u8> {
-        match long_addr {
-            0x2180 => self.read_from_pointer(),
-            0x2181..=0x2183 => None,
+        match separate_bank_hhll_addr!(long_addr) {
+            (0x00..=0x3F | 0x80..=0xBF, 0x2180) => self.read_from_pointer(),
+            (0x00..=0x3F | 0x80..=0xBF, 0x2181..=0x2183) => None,
             _ => self.read_direct(long_addr),
         }
     }
@@ -44,49 +44,68 @@ impl Ram {
     /// 
     /// Ie, if `hhll > $2000` then `bank == $7E | $7F` and the other way around with the mirrored sections
     pub fn write(&mut self, long_addr: u32, byte: u8) {
-        match long_addr {
+        match separate_bank_hhll_addr!(long_addr) {
             //WMDATA
-            0x2180 => self.write_with_pointer(byte),
+            (0x00..=0x3F | 0x80..=0xBF, 0x2180) => self.write_with_pointer(byte),
             //WMADDL
-            0x2181 => self.pointer = set_ll!(self.pointer, byte) as usize,
+            (0x00..=0x3F | 0x80..=0xBF, 0x2181) => self.pointer = set_ll!(self.pointer, byte) as usize,
             //WMADDM
-            0x2182 => self.pointer = set_hh!(self.pointer, byte) as usize,
-            //WMADDL
-            0x2183 => self.pointer = set_bb!(self.pointer, byte) as usize,
+            (0x00..=0x3F | 0x80..=0xBF, 0x2182) => self.pointer = set_hh!(self.pointer, byte) as usize,
+            //WMADDH, only the lowest bit is used since the pointer is 17 bits
+            (0x00..=0x3F | 0x80..=0xBF, 0x2183) => self.pointer = set_bb!(self.pointer, byte & 1) as usize,
 
             _ => self.write_direct(long_addr, byte),
         }
         
     }
 
+    /// Read a byte from RAM without side effects, `WMDATA` returns the byte at the pointer without moving it
+    /// 
+    /// Same addresses as `Ram::read`
+    pub fn peek(&self, long_addr: u32) -> Option<u8> {
+        match separate_bank_hhll_addr!(long_addr) {
+            (0x00..=0x3F | 0x80..=0xBF, 0x2180) => Some(self.bytes[self.pointer]),
+            (0x00..=0x3F | 0x80..=0xBF, 0x2181..=0x2183) => None,
+            _ => self.read_direct(long_addr),
+        }
+    }
+
+    /// Write a byte to RAM without side effects, `WMDATA` writes the byte at the pointer without moving it
+    /// 
+    /// Same addresses as `Ram::write`, the pointer registers are set like `Ram::write` does
+    pub fn poke(&mut self, long_addr: u32, byte: u8) {
+        match separate_bank_hhll_addr!(long_addr) {
+            (0x00..=0x3F | 0x80..=0xBF, 0x2180) => self.bytes[self.pointer] = byte,
+            _ => self.write(long_addr, byte),
+        }
+    }
+
     /// Returns index for internal bytes array from given long_addr
     fn index_from_long_addr(long_addr: u32) -> Option<usize> {
         let (bank, hhll) = separate_bank_hhll_addr!(long_addr);
         let bank_i = match bank {
-            0x00..=0x3F | 0x7E | 0x80..=0xBF => 0_u16,
-            0x7F => 1_u16,
+            0x00..=0x3F | 0x7E | 0x80..=0xBF => 0_usize,
+            0x7F => 1_usize,
             _ => return None,
         };
 
-        let hhll_i = hhll;
-        let i = (bank_i * RAM_BANK_SIZE + hhll_i) as usize;
+        let i = bank_i * RAM_BANK_SIZE + hhll as usize;
         Some(i)
     }
 
-    /// Read from pointer, which is a 17 bit number
+    /// Read from pointer, which is a 17 bit number that wraps around at the end of RAM
     fn read_from_pointer(&mut self) -> Option<u8> {
         let val = self.bytes[self.pointer];
-        self.pointer = self.pointer.wrapping_add(1);
+        self.pointer = (self.pointer + 1) % RAM_SIZE;
         Some(val)
     }
 
     fn write_with_pointer(&mut self, byte: u8) {
         self.bytes[self.pointer] = byte;
-        self.pointer = self.pointer.wrapping_add(1);
+        self.pointer = (self.pointer + 1) % RAM_SIZE;
     }
 
     fn read_direct(&self, long_addr: u32) -> Option<u8> {
-        let i = Self::index_from_long_addr(long_addr);
         match Self::index_from_long_addr(long_addr) {
             Some(i) => Some(self.bytes[i]),
             None => None,
@@ -99,4 +118,70 @@ impl Ram {
             None => {},
         }
     }
-}
\ No newline at end of file
+}
+
+impl Snapshot for Ram {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_bytes(&self.bytes);
+        w.write_usize(self.pointer);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), SaveStateError> {
+        r.read_bytes(&mut self.bytes)?;
+        self.pointer = r.read_usize()? % RAM_SIZE;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_peek_poke_all_ram() {
+        let mut ram = Ram::new();
+        for addr in 0x7E0000..=0x7FFFFF_u32 {
+            ram.poke(addr, (addr ^ (addr >> 8) ^ (addr >> 16)) as u8);
+        }
+        for addr in 0x7E0000..=0x7FFFFF_u32 {
+            assert_eq!(ram.peek(addr), Some((addr ^ (addr >> 8) ^ (addr >> 16)) as u8), "{:06X}", addr);
+        }
+    }
+
+    #[test]
+    fn test_low_ram_mirror() {
+        let mut ram = Ram::new();
+        ram.poke(0x7E1234, 0xAB);
+        assert_eq!(ram.peek(0x001234), Some(0xAB));
+        assert_eq!(ram.peek(0x801234), Some(0xAB));
+        assert_eq!(ram.peek(0x7F1234), Some(0x00));
+    }
+
+    #[test]
+    fn test_pointer_uses_17_bits() {
+        let mut ram = Ram::new();
+        // Games usually write the bank of the address, only its lowest bit selects $7E or $7F
+        ram.write(0x002181, 0xFF);
+        ram.write(0x002182, 0xFF);
+        ram.write(0x002183, 0x7F);
+        ram.write(0x002180, 0x12);
+        ram.write(0x002180, 0x34);
+        assert_eq!(ram.peek(0x7FFFFF), Some(0x12));
+        // The pointer wraps around to the start of RAM
+        assert_eq!(ram.peek(0x7E0000), Some(0x34));
+
+        ram.write(0x002181, 0x00);
+        ram.write(0x002182, 0x00);
+        ram.write(0x002183, 0x7E);
+        assert_eq!(ram.read(0x002180), Some(0x34));
+    }
+
+    #[test]
+    fn test_wram_registers_only_in_system_banks() {
+        let mut ram = Ram::new();
+        ram.poke(0x7E2180, 0x56);
+        ram.write(0x802180, 0x78);
+        assert_eq!(ram.peek(0x7E2180), Some(0x56));
+        assert_eq!(ram.peek(0x7E0000), Some(0x78));
+    }
+}
@@ -1,12 +1,18 @@
 use std::sync::{Arc, Mutex};
 
-use self::{processorstatusflag::ProcessorStatusFlags, instructions::instructions::Instruction, memory::CpuMemory};
+use crate::savestate::{Snapshot, StateWriter, StateReader, SaveStateError};
+
+use self::{processorstatusflag::ProcessorStatusFlags, memory::CpuMemory, trace::Tracer, debug::{MemoryWatch, AccessKind}};
 
 pub mod processorstatusflag;
 pub mod instructions;
 mod execute;
 pub mod memory;
+mod emulation;
+pub mod trace;
+pub mod debug;
 
+#[derive(Debug)]
 pub enum CpuError {
     PlaceHolder,
 }
@@ -59,18 +65,43 @@ pub struct Cpu {
     /// Cpu Memory, holds ram, rom and other cpu registers. Is also used to communicate with controllers, apu and ppu
     pub memory: CpuMemory,
 
-    /// Amount of cycles to wait before next instruction is called (simulates instructions taking x cycles)
+    /// Amount of master clock cycles to wait before next instruction is called (simulates instructions taking x cycles)
     wait_cycles: usize,
 
+    /// Number of bus accesses made by the current instruction, see `Cpu::get_master_cycles`
+    bus_accesses: usize,
+    /// Master clock cycles taken by the bus accesses of the current instruction
+    bus_cycles: usize,
+
+    /// Logs executed instructions when set, not part of the machine state
+    tracer: Option<Tracer>,
+
+    /// Watchpoints checked on every bus access, not part of the machine state
+    watch: MemoryWatch,
+
 }
 
+/// Status register at power on, the CPU starts in emulation mode with interrupts disabled
+const POWER_ON_STATUS: ProcessorStatusFlags = ProcessorStatusFlags::Emulation
+    .union(ProcessorStatusFlags::Break)
+    .union(ProcessorStatusFlags::Accumulator8bit)
+    .union(ProcessorStatusFlags::XYreg8bit)
+    .union(ProcessorStatusFlags::IRQdisable);
+
+/// Stack pointer at power on, the top of page 1
+const POWER_ON_SP: u16 = 0x01FF;
+
+/// Address in bank 0 holding the address execution starts at after a reset, used in both native and emulation mode
+pub const RESET_VECTOR: u32 = 0x00FFFC;
+
 impl Cpu {
+    /// Creates a CPU in its power on state, `Cpu::reset` has to be called once a cartridge is inserted to load the reset vector
     pub fn new() -> Self {
         Cpu {
             memory: CpuMemory::new(),
-            status: ProcessorStatusFlags::from_bits(0).unwrap(),
-            sp: 0,
-            pc: 0xFFFC,
+            status: POWER_ON_STATUS,
+            sp: POWER_ON_SP,
+            pc: 0,
             acc: 0,
             x: 0,
             y: 0,
@@ -79,15 +110,21 @@ impl Cpu {
             pbr: 0,
             mdr: 0,
             wait_cycles: 0,
+            bus_accesses: 0,
+            bus_cycles: 0,
+            tracer: None,
+            watch: MemoryWatch::new(),
         }
     }
 
-    /// Reset CPU, sets all values to initial state
+    /// Reset CPU, sets all values to initial state and jumps to the address in the reset vector
+    /// 
+    /// The CPU enters emulation mode with 8 bit registers, interrupts disabled and the stack at `$01FF`
     pub fn reset(&mut self) {
         // self.memory.lock().unwrap().reset(); // todo -> implement memory reset
-        self.status = ProcessorStatusFlags::from_bits(0).unwrap();
-        self.sp = 0;
-        self.pc = 0xFFFC; // reset vector
+        self.status = POWER_ON_STATUS;
+        self.sp = POWER_ON_SP;
+        self.pc = self.mem_read_long(RESET_VECTOR, RESET_VECTOR + 1);
         self.acc = 0;
         self.x = 0;
         self.y = 0;
@@ -96,31 +133,19 @@ impl Cpu {
         self.pbr = 0;
         self.mdr = 0;
         self.wait_cycles = 0;
+        self.bus_accesses = 0;
+        self.bus_cycles = 0;
     }
 
-    // This function is called every 'clock cycle'
+    /// This function is called every master clock cycle, it waits until the previous instruction has taken its time
+    /// and then executes the next instruction or interrupt
     pub fn tick(&mut self, nmi_pending: bool, irq_pending: bool) -> Result<(), CpuError> {
         if self.wait_cycles > 0 {
             self.wait_cycles -= 1;
+            return Ok(());
         }
 
-        // NMI pending -> execute NMI
-        if nmi_pending {
-            return self.execute_nmi();
-        }
-        // IRQ pending and IRQ not disabled -> execute IRQ
-        else if irq_pending && !self.status.contains(ProcessorStatusFlags::IRQdisable) {
-            return self.execute_irq();
-        }
-        // IRQ pending and irq disabled AND WAI flag on -> execute instruction as normal
-        else if irq_pending && self.status.contains(ProcessorStatusFlags::IRQdisable | ProcessorStatusFlags::WaitForInterrupt) {
-            self.status.clear_flag(ProcessorStatusFlags::WaitForInterrupt);
-        }
-
-        // read & execute instruction
-        let op = self.mem_read(self.get_pc_addr());
-        let instr = Instruction::from_op(op);
-        self.execute_instruction(instr)
+        self.step(nmi_pending, irq_pending).map(|_| ())
     }
 
     /// Returns pc long address
@@ -149,19 +174,29 @@ impl Cpu {
     /// Read from memory using a 24 bit address,
     /// result is stored in `self.mdr` and returned.
     pub fn mem_read(&mut self, addr: u32) -> u8 {
+        self.count_bus_access(addr);
         if let Some(byte) = self.memory.read(addr) {
             self.mdr = byte;
         }
+        self.watch_access(addr, self.mdr, AccessKind::Read);
         self.mdr
     }
 
     /// Write to memory using a 16 bit address, the final address is `$DDHHLL` where `$DD` is equal to `self.dbr` and `$HHLL` is equal to `addr`.
     /// `byte` is passed to `self.mdr` and is written to memory
     pub fn mem_write(&mut self, addr: u32, byte: u8) {
+        self.count_bus_access(addr);
         self.mdr = byte;
+        self.watch_access(addr, byte, AccessKind::Write);
         self.memory.write(addr, self.mdr);
     }
 
+    /// Adds the time of a bus access to `addr` to the time of the current instruction
+    fn count_bus_access(&mut self, addr: u32) {
+        self.bus_accesses += 1;
+        self.bus_cycles += self.memory.access_time(addr);
+    }
+
     /// Returns `0_u16` if carry flag is unset, `1_u16` if carry flag is set
     pub fn carry(&self) -> u16 {
         match self.status.contains(ProcessorStatusFlags::Carry) {
@@ -172,10 +207,10 @@ impl Cpu {
 
     /// Returns accumulator value as either 16 bit or 8 bits depending on accumulator 8 bit flag. 
     /// 
-    /// 8 bit value is actually just 16 bit value ANDed with `0xF`
+    /// 8 bit value is actually just 16 bit value ANDed with `0xFF`
     pub fn get_acc(&self) -> u16 {
         match self.status.contains(ProcessorStatusFlags::Accumulator8bit) {
-            true => self.acc & 0xF,
+            true => self.acc & 0xFF,
             false => self.acc,
         }
     }
@@ -184,14 +219,14 @@ impl Cpu {
     /// else set accumulator to `val`
     pub fn set_acc(&mut self, val: u16) {
         match self.status.contains(ProcessorStatusFlags::Accumulator8bit) {
-            true => self.acc = (self.acc & 0xF0) | (val & 0xF),
+            true => self.acc = (self.acc & 0xFF00) | (val & 0xFF),
             false => self.acc = val,
         }
     }
 
     /// Call this function after setting accumulator to set negative and zero flags. Takes into account 16/8 bit mode
     pub fn set_acc_nz_flag(&mut self) {
-        self.status.set(ProcessorStatusFlags::Zero, self.acc == 0);
+        self.status.set(ProcessorStatusFlags::Zero, self.get_acc() == 0);
         match self.status.contains(ProcessorStatusFlags::Accumulator8bit) {
             true => self.status.set(ProcessorStatusFlags::Negative, (self.acc as i8) < 0),
             false => self.status.set(ProcessorStatusFlags::Negative, (self.acc as i16) < 0),
@@ -200,18 +235,18 @@ impl Cpu {
 
     /// Returns x register value as either 16 bit or 8 bits depending on x register 8 bit flag. 
     /// 
-    /// 8 bit value is actually just 16 bit value ANDed with `0xF`
+    /// 8 bit value is actually just 16 bit value ANDed with `0xFF`
     pub fn get_x(&self) -> u16 {
         match self.status.contains(ProcessorStatusFlags::XYreg8bit) {
-            true => self.x & 0xF,
+            true => self.x & 0xFF,
             false => self.x,
         }
     }
 
-    /// Set x, ANDS `val` with `0xF` if 8 bit mode for x register is enabled
+    /// Set x, ANDS `val` with `0xFF` if 8 bit mode for x register is enabled
     pub fn set_x(&mut self, val: u16) {
         match self.status.contains(ProcessorStatusFlags::XYreg8bit) {
-            true => self.x = val & 0xF,
+            true => self.x = val & 0xFF,
             false => self.x = val,
         }
     }
@@ -227,18 +262,18 @@ impl Cpu {
 
     /// Returns y register value as either 16 bit or 8 bits depending on y register 8 bit flag. 
     /// 
-    /// 8 bit value is actually just 16 bit value ANDed with `0yF`
+    /// 8 bit value is actually just 16 bit value ANDed with `0xFF`
     pub fn get_y(&self) -> u16 {
         match self.status.contains(ProcessorStatusFlags::XYreg8bit) {
-            true => self.y & 0xF,
+            true => self.y & 0xFF,
             false => self.y,
         }
     }
 
-    /// Set y, ANDS `val` with `0yF` if 8 bit mode for y register is enabled
+    /// Set y, ANDS `val` with `0xFF` if 8 bit mode for y register is enabled
     pub fn set_y(&mut self, val: u16) {
         match self.status.contains(ProcessorStatusFlags::XYreg8bit) {
-            true => self.y = val & 0xF,
+            true => self.y = val & 0xFF,
             false => self.y = val,
         }
     }
@@ -254,18 +289,18 @@ impl Cpu {
 
     /// Puts `byte` in `self.mdr` and then pushes it onto the stack, decrement stack pointer after
     /// 
-    /// Locks `self.memory`
+    /// In emulation mode the stack pointer wraps around in page 1
     pub fn push_byte_stack(&mut self, byte: u8) {
         self.mem_write(self.sp as u32, byte);
-        self.sp -= 1;
+        self.sp = self.stack_offset(-1);
     }
 
-    /// Pull single byte from stack and puts it in `self.mdr` and returns `self.mdr`.
-    /// Increments stack pointer.
+    /// Increments stack pointer, then pulls single byte from stack and puts it in `self.mdr` and returns `self.mdr`.
+    /// 
+    /// In emulation mode the stack pointer wraps around in page 1
     pub fn pull_byte_stack(&mut self) -> u8 {
-        self.mem_read(self.sp as u32);
-        self.sp += 1;
-        self.mdr
+        self.sp = self.stack_offset(1);
+        self.mem_read(self.sp as u32)
     }
 
     /// Push a long onto the stack, first pushes high byte, then low byte
@@ -286,3 +321,65 @@ impl Cpu {
         (upper << 8) | lower
     }
 }
+
+impl Snapshot for Cpu {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u16(self.sp);
+        w.write_u8(self.pbr);
+        w.write_u16(self.pc);
+        w.write_u16(self.acc);
+        w.write_u16(self.status.bits());
+        w.write_u16(self.x);
+        w.write_u16(self.y);
+        w.write_u16(self.dp);
+        w.write_u8(self.dbr);
+        w.write_u8(self.mdr);
+        w.write_usize(self.wait_cycles);
+        self.memory.save_state(w);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), SaveStateError> {
+        self.sp = r.read_u16()?;
+        self.pbr = r.read_u8()?;
+        self.pc = r.read_u16()?;
+        self.acc = r.read_u16()?;
+        self.status = ProcessorStatusFlags::from_bits(r.read_u16()?).ok_or(SaveStateError::InvalidValue("processor status"))?;
+        self.x = r.read_u16()?;
+        self.y = r.read_u16()?;
+        self.dp = r.read_u16()?;
+        self.dbr = r.read_u8()?;
+        self.mdr = r.read_u8()?;
+        self.wait_cycles = r.read_usize()?;
+        self.memory.load_state(r)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_8bit_acc_keeps_high_byte() {
+        let mut cpu = Cpu::new();
+        cpu.status.clear_flag(ProcessorStatusFlags::Accumulator8bit);
+        cpu.set_acc(0x12FF);
+        cpu.status.set_flag(ProcessorStatusFlags::Accumulator8bit);
+        assert_eq!(cpu.get_acc(), 0xFF);
+
+        cpu.set_acc(0xAB00);
+        assert_eq!(cpu.get_acc(), 0x00);
+        assert_eq!(cpu.acc, 0x1200);
+        cpu.set_acc_nz_flag();
+        assert!(cpu.status.contains(ProcessorStatusFlags::Zero));
+    }
+
+    #[test]
+    fn test_8bit_index_registers() {
+        let mut cpu = Cpu::new();
+        cpu.status.set_flag(ProcessorStatusFlags::XYreg8bit);
+        cpu.set_x(0x12F0);
+        cpu.set_y(0x340F);
+        assert_eq!((cpu.x, cpu.get_x()), (0xF0, 0xF0));
+        assert_eq!((cpu.y, cpu.get_y()), (0x0F, 0x0F));
+    }
+}
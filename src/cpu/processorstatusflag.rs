@@ -10,7 +10,8 @@ bitflags! {
     pub struct ProcessorStatusFlags: u16 {
         /// Flag to check if WAI was called (not actually in snes!)
         const WaitForInterrupt = 0b100_0000_0000;
-        /// Break (only in Emulation mode)
+        /// Break (only in Emulation mode), takes the place of the X flag in the status register that is pushed on the stack.
+        /// Always set in emulation mode, except in the status register pushed by hardware interrupts
         const Break = 0b10_0000_0000;
         /// Emulation mode
         const Emulation = 0b01_0000_0000;
@@ -65,4 +66,24 @@ impl ProcessorStatusFlags {
     pub fn get_bits(&self) -> u8 {
         self.bits() as u8
     }
+
+    /// Returns the status register as it is pushed on the stack
+    ///
+    /// In emulation mode bit 4 holds the break flag instead of the X flag and bit 5 is always set
+    pub fn get_stack_bits(&self) -> u8 {
+        match self.contains(ProcessorStatusFlags::Emulation) {
+            true => {
+                let brk = if self.contains(ProcessorStatusFlags::Break) { 0x10 } else { 0 };
+                (self.get_bits() & !0x30) | 0x20 | brk
+            },
+            false => self.get_bits(),
+        }
+    }
+
+    /// Replaces the lower 8 bits of the status register with `bits` pulled from the stack, the other flags are kept.
+    ///
+    /// In emulation mode bits 4 and 5 are ignored, see `Cpu::update_register_modes`
+    pub fn set_stack_bits(&mut self, bits: u8) {
+        *self = ProcessorStatusFlags::from_bits_retain((self.bits() & 0xFF00) | bits as u16);
+    }
 }
@@ -0,0 +1,170 @@
+use std::{fs::File, io::{self, BufWriter, Write}, ops::RangeInclusive, path::Path};
+
+use super::{Cpu, instructions::decode::DecodedInstruction};
+
+/// Where trace lines are written to
+pub enum TraceSink {
+    File(BufWriter<File>),
+    /// Called with every trace line, without line ending
+    Callback(Box<dyn FnMut(&str) + Send>),
+}
+
+/// Logs every executed instruction with the CPU registers before it executes
+///
+/// Lines look like `00/8000 LDA #$00 A:0000 X:0000 Y:0000 S:01FF D:0000 DB:00 P:34 V:0 H:0`, like the traces of other emulators,
+/// so traces can be diffed against a reference log. `V` and `H` are the beam position, as set by `Tracer::set_beam_position`
+pub struct Tracer {
+    sink: TraceSink,
+    /// Only instructions at long addresses in this range are logged
+    pc_range: Option<RangeInclusive<u32>>,
+    /// Only instructions in frames in this range are logged
+    frames: Option<RangeInclusive<u64>>,
+    frame: u64,
+    v: u16,
+    h: u16,
+}
+
+impl Tracer {
+    pub fn new(sink: TraceSink) -> Tracer {
+        Tracer { sink, pc_range: None, frames: None, frame: 0, v: 0, h: 0 }
+    }
+
+    /// Creates a tracer that writes to the file at `path`, the file is replaced if it exists
+    pub fn to_file<P: AsRef<Path>>(path: P) -> io::Result<Tracer> {
+        Ok(Tracer::new(TraceSink::File(BufWriter::new(File::create(path)?))))
+    }
+
+    /// Creates a tracer that calls `callback` with every trace line
+    pub fn with_callback(callback: impl FnMut(&str) + Send + 'static) -> Tracer {
+        Tracer::new(TraceSink::Callback(Box::new(callback)))
+    }
+
+    /// Only log instructions at long addresses in `range`, `None` logs all of them
+    pub fn set_pc_range(&mut self, range: Option<RangeInclusive<u32>>) {
+        self.pc_range = range;
+    }
+
+    /// Only log instructions in the frames in `range`, counting from 0. `None` logs all frames
+    pub fn set_frames(&mut self, range: Option<RangeInclusive<u64>>) {
+        self.frames = range;
+    }
+
+    /// Counts a frame, called at the start of every frame
+    pub fn next_frame(&mut self) {
+        self.frame += 1;
+    }
+
+    /// Sets the beam position that is logged with the next instructions
+    pub fn set_beam_position(&mut self, v: u16, h: u16) {
+        self.v = v;
+        self.h = h;
+    }
+
+    /// Returns true if an instruction at `pc` in the current frame should be logged
+    fn is_traced(&self, pc: u32) -> bool {
+        self.pc_range.as_ref().is_none_or(|range| range.contains(&pc))
+            && self.frames.as_ref().is_none_or(|range| range.contains(&self.frame))
+    }
+
+    fn write_line(&mut self, line: &str) {
+        match &mut self.sink {
+            TraceSink::File(file) => {
+                if let Err(e) = writeln!(file, "{line}") {
+                    eprintln!("Failed to write trace: {e}");
+                }
+            },
+            TraceSink::Callback(callback) => callback(line),
+        }
+    }
+
+    /// Writes everything that is buffered to the file, also done when the tracer is dropped
+    pub fn flush(&mut self) -> io::Result<()> {
+        match &mut self.sink {
+            TraceSink::File(file) => file.flush(),
+            TraceSink::Callback(_) => Ok(()),
+        }
+    }
+}
+
+impl Cpu {
+    /// Logs every instruction to `tracer` before it is executed, `None` turns tracing off
+    pub fn set_tracer(&mut self, tracer: Option<Tracer>) {
+        self.tracer = tracer;
+    }
+
+    pub fn tracer_mut(&mut self) -> Option<&mut Tracer> {
+        self.tracer.as_mut()
+    }
+
+    /// Writes a trace line for `instr` with the current registers, called right before `instr` is executed
+    pub(super) fn trace_instruction(&mut self, instr: &DecodedInstruction) {
+        let Some(tracer) = &mut self.tracer else { return };
+        if !tracer.is_traced(instr.addr) {
+            return;
+        }
+
+        let line = format!("{:02X}/{:04X} {} A:{:04X} X:{:04X} Y:{:04X} S:{:04X} D:{:04X} DB:{:02X} P:{:02X} V:{} H:{}",
+            self.pbr, self.pc, instr.format_asm(None), self.acc, self.x, self.y, self.sp, self.dp, self.dbr, self.status.get_bits(),
+            tracer.v, tracer.h);
+        tracer.write_line(&line);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+
+    /// CPU at `$00:0000` in front of `LDA #$00`, `NOP`, `NOP`, with a tracer that collects its lines
+    fn traced_cpu() -> (Cpu, Arc<Mutex<Vec<String>>>) {
+        let mut cpu = Cpu::new();
+        for (i, byte) in [0xA9, 0x00, 0xEA, 0xEA].into_iter().enumerate() {
+            cpu.memory.poke(i as u32, byte);
+        }
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let sink = lines.clone();
+        cpu.set_tracer(Some(Tracer::with_callback(move |line| sink.lock().unwrap().push(line.to_string()))));
+        (cpu, lines)
+    }
+
+    #[test]
+    fn test_trace_line_format() {
+        let (mut cpu, lines) = traced_cpu();
+        cpu.tracer_mut().unwrap().set_beam_position(12, 34);
+        cpu.step(false, false).unwrap();
+        cpu.step(false, false).unwrap();
+        assert_eq!(*lines.lock().unwrap(), [
+            "00/0000 LDA #$00 A:0000 X:0000 Y:0000 S:01FF D:0000 DB:00 P:34 V:12 H:34",
+            "00/0002 NOP A:0000 X:0000 Y:0000 S:01FF D:0000 DB:00 P:36 V:12 H:34",
+        ]);
+    }
+
+    #[test]
+    fn test_pc_range_filter() {
+        let (mut cpu, lines) = traced_cpu();
+        cpu.tracer_mut().unwrap().set_pc_range(Some(0x000002..=0x000002));
+        for _ in 0..3 {
+            cpu.step(false, false).unwrap();
+        }
+        let lines = lines.lock().unwrap();
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].starts_with("00/0002 NOP "));
+    }
+
+    #[test]
+    fn test_frame_filter() {
+        let (mut cpu, lines) = traced_cpu();
+        cpu.tracer_mut().unwrap().set_frames(Some(1..=1));
+        cpu.step(false, false).unwrap();
+        assert!(lines.lock().unwrap().is_empty());
+
+        cpu.tracer_mut().unwrap().next_frame();
+        cpu.step(false, false).unwrap();
+        cpu.tracer_mut().unwrap().next_frame();
+        cpu.step(false, false).unwrap();
+        let lines = lines.lock().unwrap();
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].starts_with("00/0002 NOP "));
+    }
+}
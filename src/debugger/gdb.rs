@@ -0,0 +1,534 @@
+use std::{io::{self, Read, Write}, net::{TcpListener, TcpStream}};
+
+use crate::{console::Console, cpu::debug::{Register, WatchKind, Watchpoint}};
+
+use super::{Debugger, StopReason};
+
+/// Port the `gdb` subcommand listens on unless `--port` is given
+pub const DEFAULT_PORT: u16 = 2345;
+
+/// Largest packet gdb may send, advertised in `qSupported`. Replies to `m` are clamped to fit as well
+const PACKET_SIZE: usize = 0x4000;
+
+/// Instructions executed between checks for an interrupt request from gdb while continuing
+const CONTINUE_CHUNK: usize = 100_000;
+
+/// Registers in the order of the `g` packet with their size in bytes, all little endian
+const REGISTERS: [(Register, &str, usize); 9] = [
+    (Register::A, "a", 2),
+    (Register::X, "x", 2),
+    (Register::Y, "y", 2),
+    (Register::S, "s", 2),
+    (Register::D, "d", 2),
+    (Register::DB, "db", 1),
+    (Register::PB, "pb", 1),
+    (Register::P, "p", 1),
+    (Register::PC, "pc", 2),
+];
+
+/// Serves the GDB remote serial protocol for a single connection, so debugger frontends can attach to the console
+///
+/// Supported are reading and writing registers and memory, software breakpoints (`Z0`), watchpoints (`Z2`-`Z4`),
+/// single stepping, continuing and interrupting with Ctrl-C. Addresses are 24 bit long addresses.
+/// The register layout is described by `target.xml`, which gdb reads with `qXfer:features:read`
+pub struct GdbStub<'a> {
+    debugger: &'a mut Debugger,
+    stream: TcpStream,
+    /// Packets are acknowledged with `+` until gdb asks for `QStartNoAckMode`
+    ack: bool,
+}
+
+impl<'a> GdbStub<'a> {
+    pub fn new(debugger: &'a mut Debugger, stream: TcpStream) -> GdbStub<'a> {
+        GdbStub { debugger, stream, ack: true }
+    }
+
+    /// Handles packets until gdb detaches, kills the target or closes the connection
+    pub fn serve(&mut self) -> io::Result<()> {
+        while let Some(packet) = self.read_packet()? {
+            let reply = match packet.as_bytes().first() {
+                Some(b'k') => return Ok(()),
+                Some(b'D') => {
+                    self.write_packet("OK")?;
+                    return Ok(());
+                },
+                Some(b'c') => {
+                    self.jump(&packet[1..]);
+                    self.resume()?
+                },
+                Some(b's') => {
+                    self.jump(&packet[1..]);
+                    let reason = self.debugger.step_into();
+                    self.stop_reply(reason)
+                },
+                Some(_) => self.handle(&packet),
+                None => String::new(),
+            };
+            self.write_packet(&reply)?;
+        }
+        Ok(())
+    }
+
+    /// Reply to packets that don't run the CPU, an empty reply tells gdb the packet is not supported
+    fn handle(&mut self, packet: &str) -> String {
+        // Packets start with an ASCII command, anything else can't be split after its first byte
+        let Some((command, args)) = packet.split_at_checked(1) else { return String::new() };
+        match command {
+            "?" => "S05".to_string(),
+            "g" => REGISTERS.iter().map(|(reg, _, size)| to_hex_le(self.register(*reg), *size)).collect(),
+            "G" => {
+                let mut rest = args;
+                for (reg, _, size) in REGISTERS {
+                    let Some((value, tail)) = rest.split_at_checked(size * 2) else { return "E01".to_string() };
+                    match from_hex_le(value) {
+                        Some(val) => self.debugger.set_register(reg, val as u16),
+                        None => return "E01".to_string(),
+                    }
+                    rest = tail;
+                }
+                "OK".to_string()
+            },
+            "p" => match usize::from_str_radix(args, 16).ok().and_then(|n| REGISTERS.get(n)) {
+                Some((reg, _, size)) => to_hex_le(self.register(*reg), *size),
+                None => "E01".to_string(),
+            },
+            "P" => {
+                let parsed = args.split_once('=').and_then(|(n, val)| {
+                    Some((REGISTERS.get(usize::from_str_radix(n, 16).ok()?)?.0, from_hex_le(val)?))
+                });
+                match parsed {
+                    Some((reg, val)) => {
+                        self.debugger.set_register(reg, val as u16);
+                        "OK".to_string()
+                    },
+                    None => "E01".to_string(),
+                }
+            },
+            "m" => match parse_addr_len(args) {
+                Some((addr, len)) => {
+                    // Only the bytes up to the first one that can't be peeked are sent, as two hex digits each
+                    let bytes: String = self.debugger.read_memory(addr, len.min(PACKET_SIZE / 2)).into_iter()
+                        .map_while(|byte| byte.map(|b| format!("{b:02x}")))
+                        .collect();
+                    if bytes.is_empty() { "E14".to_string() } else { bytes }
+                },
+                None => "E01".to_string(),
+            },
+            "M" => {
+                let parsed = args.split_once(':').and_then(|(addr_len, data)| Some((parse_addr_len(addr_len)?, parse_hex_bytes(data)?)));
+                match parsed {
+                    Some(((addr, len), bytes)) if bytes.len() == len => {
+                        self.debugger.write_memory(addr, &bytes);
+                        "OK".to_string()
+                    },
+                    _ => "E01".to_string(),
+                }
+            },
+            "Z" | "z" => self.set_breakpoint(args, command == "Z"),
+            "H" => "OK".to_string(),
+            "T" => "OK".to_string(),
+            "q" | "Q" => self.query(packet),
+            _ => String::new(),
+        }
+    }
+
+    fn query(&mut self, packet: &str) -> String {
+        if packet.starts_with("qSupported") {
+            return format!("PacketSize={PACKET_SIZE:x};qXfer:features:read+;QStartNoAckMode+;swbreak+");
+        }
+        if let Some(args) = packet.strip_prefix("qXfer:features:read:target.xml:") {
+            return match parse_addr_len(args) {
+                Some((offset, len)) => xfer_chunk(&target_xml(), offset as usize, len),
+                None => "E01".to_string(),
+            };
+        }
+
+        match packet {
+            "QStartNoAckMode" => {
+                self.ack = false;
+                "OK".to_string()
+            },
+            "qAttached" => "1".to_string(),
+            "qC" => "QC1".to_string(),
+            "qfThreadInfo" => "m1".to_string(),
+            "qsThreadInfo" => "l".to_string(),
+            _ => String::new(),
+        }
+    }
+
+    /// `Z<type>,<addr>,<kind>` and `z<type>,<addr>,<kind>`, type 0 is a software breakpoint, 2 to 4 are write, read and access watchpoints
+    fn set_breakpoint(&mut self, args: &str, insert: bool) -> String {
+        let mut parts = args.split(',');
+        let (Some(kind), Some(addr), len) = (parts.next(), parts.next().and_then(|a| u32::from_str_radix(a, 16).ok()), parts.next()) else {
+            return "E01".to_string();
+        };
+
+        let watch_kind = match kind {
+            "0" => {
+                match insert {
+                    true => self.debugger.add_breakpoint(addr),
+                    false => self.debugger.remove_breakpoint(addr),
+                };
+                return "OK".to_string();
+            },
+            "2" => WatchKind::Write,
+            "3" => WatchKind::Read,
+            "4" => WatchKind::Access,
+            _ => return String::new(),
+        };
+
+        let len = len.and_then(|l| u32::from_str_radix(l, 16).ok()).unwrap_or(1).max(1);
+        let watchpoint = Watchpoint { range: addr..=addr.wrapping_add(len - 1), kind: watch_kind, condition: None };
+        if insert {
+            self.debugger.add_watchpoint(watchpoint);
+        } else if let Some((id, _)) = self.debugger.watchpoints().into_iter().find(|(_, w)| *w == watchpoint) {
+            self.debugger.remove_watchpoint(id);
+        }
+        "OK".to_string()
+    }
+
+    /// `c` and `s` may give the long address to continue at
+    fn jump(&mut self, addr: &str) {
+        if let Ok(addr) = u32::from_str_radix(addr, 16) {
+            self.debugger.set_register(Register::PB, (addr >> 16) as u16);
+            self.debugger.set_register(Register::PC, addr as u16);
+        }
+    }
+
+    /// Runs until something stops execution or gdb sends Ctrl-C, which is checked between chunks of instructions
+    fn resume(&mut self) -> io::Result<String> {
+        loop {
+            match self.debugger.resume(CONTINUE_CHUNK) {
+                StopReason::Limit => {},
+                reason => return Ok(self.stop_reply(reason)),
+            }
+            if self.debugger.at_breakpoint() {
+                return Ok(self.stop_reply(StopReason::Breakpoint(self.debugger.console.cpu.get_pc_addr())));
+            }
+            if self.interrupt_requested()? {
+                return Ok("S02".to_string());
+            }
+        }
+    }
+
+    /// Stop reply packet for `reason`, all stops are reported as SIGTRAP
+    fn stop_reply(&self, reason: StopReason) -> String {
+        match reason {
+            StopReason::Breakpoint(_) => "T05swbreak:;".to_string(),
+            StopReason::Watchpoint(hit) => {
+                let kind = self.debugger.watchpoints().into_iter().find(|(id, _)| *id == hit.id).map(|(_, w)| w.kind);
+                let name = match kind {
+                    Some(WatchKind::Read) => "rwatch",
+                    Some(WatchKind::Access) => "awatch",
+                    _ => "watch",
+                };
+                format!("T05{name}:{:x};", hit.access.addr)
+            },
+            _ => "S05".to_string(),
+        }
+    }
+
+    fn register(&self, reg: Register) -> u32 {
+        let regs = self.debugger.registers();
+        match reg {
+            Register::A => regs.acc as u32,
+            Register::X => regs.x as u32,
+            Register::Y => regs.y as u32,
+            Register::S => regs.sp as u32,
+            Register::D => regs.dp as u32,
+            Register::DB => regs.dbr as u32,
+            Register::PB => regs.pbr as u32,
+            Register::P => regs.status.get_stack_bits() as u32,
+            Register::PC => regs.pc as u32,
+        }
+    }
+
+    /// Returns true if gdb sent Ctrl-C, without waiting for it
+    fn interrupt_requested(&mut self) -> io::Result<bool> {
+        self.stream.set_nonblocking(true)?;
+        let mut byte = [0];
+        let result = match self.stream.read(&mut byte) {
+            Ok(1) => Ok(byte[0] == 0x03),
+            Ok(_) => Ok(false),
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => Ok(false),
+            Err(e) => Err(e),
+        };
+        self.stream.set_nonblocking(false)?;
+        result
+    }
+
+    fn read_byte(&mut self) -> io::Result<Option<u8>> {
+        let mut byte = [0];
+        match self.stream.read(&mut byte)? {
+            0 => Ok(None),
+            _ => Ok(Some(byte[0])),
+        }
+    }
+
+    /// Reads the next `$<data>#<checksum>` packet, skipping acknowledgements and stray Ctrl-C. `None` when the connection is closed
+    fn read_packet(&mut self) -> io::Result<Option<String>> {
+        loop {
+            match self.read_byte()? {
+                None => return Ok(None),
+                Some(b'$') => {},
+                Some(_) => continue,
+            }
+
+            let mut data = Vec::new();
+            loop {
+                match self.read_byte()? {
+                    None => return Ok(None),
+                    Some(b'#') => break,
+                    Some(byte) => data.push(byte),
+                }
+            }
+            let mut checksum = [0; 2];
+            self.stream.read_exact(&mut checksum)?;
+
+            let valid = std::str::from_utf8(&checksum).ok()
+                .and_then(|c| u8::from_str_radix(c, 16).ok())
+                .is_some_and(|c| c == checksum_of(&data));
+            if self.ack {
+                self.stream.write_all(if valid { b"+" } else { b"-" })?;
+            }
+            if valid {
+                return Ok(Some(String::from_utf8_lossy(&data).into_owned()));
+            }
+        }
+    }
+
+    /// Sends `$<data>#<checksum>`, and again until gdb acknowledges it while acknowledgements are on
+    fn write_packet(&mut self, data: &str) -> io::Result<()> {
+        let packet = format!("${data}#{:02x}", checksum_of(data.as_bytes()));
+        loop {
+            self.stream.write_all(packet.as_bytes())?;
+            self.stream.flush()?;
+            if !self.ack {
+                return Ok(());
+            }
+            match self.read_byte()? {
+                Some(b'-') => continue,
+                _ => return Ok(()),
+            }
+        }
+    }
+}
+
+fn checksum_of(data: &[u8]) -> u8 {
+    data.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte))
+}
+
+/// Formats the low `size` bytes of `val` as little endian hex
+fn to_hex_le(val: u32, size: usize) -> String {
+    (0..size).map(|i| format!("{:02x}", (val >> (i * 8)) as u8)).collect()
+}
+
+/// Parses little endian hex of up to 4 bytes
+fn from_hex_le(hex: &str) -> Option<u32> {
+    let bytes = parse_hex_bytes(hex)?;
+    (bytes.len() <= 4).then(|| bytes.iter().rev().fold(0, |val, byte| (val << 8) | *byte as u32))
+}
+
+fn parse_hex_bytes(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok()).collect()
+}
+
+/// Parses `<addr>,<len>` in hex
+fn parse_addr_len(s: &str) -> Option<(u32, usize)> {
+    let (addr, len) = s.split_once(',')?;
+    Some((u32::from_str_radix(addr, 16).ok()?, usize::from_str_radix(len, 16).ok()?))
+}
+
+/// Reply to a `qXfer` read of `len` bytes from `offset`, `l` marks the last chunk and `m` means there is more
+fn xfer_chunk(data: &str, offset: usize, len: usize) -> String {
+    let end = offset.saturating_add(len);
+    let chunk = data.get(offset.min(data.len())..end.min(data.len())).unwrap_or("");
+    let marker = if end >= data.len() { 'l' } else { 'm' };
+    format!("{marker}{chunk}")
+}
+
+/// Target description with the registers of `REGISTERS`
+fn target_xml() -> String {
+    let registers: String = REGISTERS.iter().enumerate().map(|(i, (_, name, size))| {
+        let ty = match *name {
+            "pc" => "code_ptr",
+            "s" => "data_ptr",
+            _ => "int",
+        };
+        format!("<reg name=\"{name}\" bitsize=\"{}\" type=\"{ty}\" regnum=\"{i}\"/>", size * 8)
+    }).collect();
+    format!("<?xml version=\"1.0\"?><!DOCTYPE target SYSTEM \"gdb-target.dtd\"><target version=\"1.0\"><feature name=\"org.snesemu.w65c816\">{registers}</feature></target>")
+}
+
+/// Runs the `gdb` subcommand, waits for gdb to connect on localhost and serves it until it detaches
+///
+/// `--port <n>` sets the port, defaults to `DEFAULT_PORT`. Connect with `target remote localhost:<port>`
+pub fn run(args: impl Iterator<Item = String>, console: Console) {
+    let mut port = DEFAULT_PORT;
+    let mut iter = args;
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--port" => port = iter.next().and_then(|n| n.parse().ok()).unwrap_or(port),
+            _ => eprintln!("Unknown argument: {arg}"),
+        }
+    }
+
+    let mut debugger = Debugger::new(console);
+    let result = TcpListener::bind(("127.0.0.1", port)).and_then(|listener| {
+        println!("Waiting for gdb on localhost:{port}");
+        let (stream, addr) = listener.accept()?;
+        println!("gdb connected from {addr}");
+        stream.set_nodelay(true)?;
+        GdbStub::new(&mut debugger, stream).serve()
+    });
+    if let Err(e) = result {
+        eprintln!("gdb stub: {e}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Connected stream for a `GdbStub`, the other end is returned as well so the connection stays open
+    fn loopback() -> (TcpStream, TcpStream) {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        (listener.accept().unwrap().0, client)
+    }
+
+    #[test]
+    fn test_checksum() {
+        assert_eq!(checksum_of(b""), 0);
+        assert_eq!(checksum_of(b"OK"), 0x9A);
+        assert_eq!(checksum_of(b"qSupported"), 0x37);
+    }
+
+    #[test]
+    fn test_hex() {
+        assert_eq!(to_hex_le(0x123456, 3), "563412");
+        assert_eq!(to_hex_le(0x1234, 1), "34");
+        assert_eq!(from_hex_le("563412"), Some(0x123456));
+        assert_eq!(from_hex_le("0102030405"), None);
+        assert_eq!(parse_hex_bytes("00ff7E"), Some(vec![0x00, 0xFF, 0x7E]));
+        assert_eq!(parse_hex_bytes("abc"), None);
+        assert_eq!(parse_hex_bytes("zz"), None);
+    }
+
+    #[test]
+    fn test_parse_addr_len() {
+        assert_eq!(parse_addr_len("7e0100,10"), Some((0x7E0100, 0x10)));
+        assert_eq!(parse_addr_len("0,0"), Some((0, 0)));
+        assert_eq!(parse_addr_len("7e0100"), None);
+        assert_eq!(parse_addr_len("7e0100,"), None);
+        assert_eq!(parse_addr_len("xyz,10"), None);
+    }
+
+    #[test]
+    fn test_xfer_chunk() {
+        assert_eq!(xfer_chunk("abcdef", 0, 4), "mabcd");
+        assert_eq!(xfer_chunk("abcdef", 4, 4), "lef");
+        assert_eq!(xfer_chunk("abcdef", 2, 4), "lcdef");
+        assert_eq!(xfer_chunk("abcdef", 10, 4), "l");
+        assert_eq!(xfer_chunk("abcdef", 2, usize::MAX), "lcdef");
+        assert_eq!(xfer_chunk("abcdef", usize::MAX, usize::MAX), "l");
+    }
+
+    #[test]
+    fn test_memory_packets() {
+        let mut debugger = Debugger::new(Console::new());
+        let (stream, _client) = loopback();
+        let mut stub = GdbStub::new(&mut debugger, stream);
+
+        assert_eq!(stub.handle("M7e0100,2:abcd"), "OK");
+        assert_eq!(stub.handle("m7e0100,3"), "abcd00");
+        assert_eq!(stub.handle("m7e0100"), "E01");
+        // Replies are clamped to the packet size, two hex digits per byte
+        assert_eq!(stub.handle("m7e0000,10000").len(), PACKET_SIZE);
+    }
+
+    #[test]
+    fn test_non_ascii_packet_is_unsupported() {
+        let mut debugger = Debugger::new(Console::new());
+        let (stream, _client) = loopback();
+        let mut stub = GdbStub::new(&mut debugger, stream);
+
+        assert_eq!(stub.handle("\u{e9}00"), "");
+    }
+
+    /// Sends `data` framed as a packet and returns the acknowledgement
+    fn send(client: &mut TcpStream, data: &[u8]) -> u8 {
+        client.write_all(&[b"$", data, format!("#{:02x}", checksum_of(data)).as_bytes()].concat()).unwrap();
+        let mut ack = [0];
+        client.read_exact(&mut ack).unwrap();
+        ack[0]
+    }
+
+    /// Receives a packet, checks its checksum and acknowledges it
+    fn receive(client: &mut TcpStream) -> String {
+        let mut byte = [0];
+        client.read_exact(&mut byte).unwrap();
+        assert_eq!(byte[0], b'$');
+
+        let mut data = Vec::new();
+        loop {
+            client.read_exact(&mut byte).unwrap();
+            if byte[0] == b'#' {
+                break;
+            }
+            data.push(byte[0]);
+        }
+        let mut checksum = [0; 2];
+        client.read_exact(&mut checksum).unwrap();
+        assert_eq!(std::str::from_utf8(&checksum).unwrap(), format!("{:02x}", checksum_of(&data)));
+
+        client.write_all(b"+").unwrap();
+        String::from_utf8(data).unwrap()
+    }
+
+    #[test]
+    fn test_session() {
+        // 7E/0000 NOP, 7E/0001 NOP, 7E/0002 JMP $0002
+        let mut debugger = Debugger::new(Console::new());
+        debugger.write_memory(0x7E0000, &[0xEA, 0xEA, 0x4C, 0x02, 0x00]);
+        debugger.set_register(Register::PB, 0x7E);
+        debugger.set_register(Register::PC, 0x0000);
+        let (stream, mut client) = loopback();
+
+        let gdb = std::thread::spawn(move || {
+            // A packet with a bad checksum is rejected
+            client.write_all(b"$?#00").unwrap();
+            let mut nack = [0];
+            client.read_exact(&mut nack).unwrap();
+            assert_eq!(nack[0], b'-');
+
+            let mut replies = Vec::new();
+            let mut exchange = |data: &[u8]| {
+                assert_eq!(send(&mut client, data), b'+');
+                replies.push(receive(&mut client));
+            };
+            exchange(b"?");
+            exchange(b"s");
+            exchange(b"p8");
+            exchange(b"Z0,7e0002,1");
+            exchange(b"c");
+            exchange(b"p8");
+            exchange(b"z0,7e0002,1");
+
+            // Continuing runs the endless loop until Ctrl-C
+            assert_eq!(send(&mut client, b"c"), b'+');
+            client.write_all(&[0x03]).unwrap();
+            replies.push(receive(&mut client));
+
+            assert_eq!(send(&mut client, b"k"), b'+');
+            replies
+        });
+
+        GdbStub::new(&mut debugger, stream).serve().unwrap();
+        assert_eq!(gdb.join().unwrap(), ["S05", "S05", "0100", "OK", "T05swbreak:;", "0200", "OK", "S02"]);
+        assert_eq!(debugger.breakpoints().count(), 0);
+    }
+}
@@ -0,0 +1,392 @@
+pub mod repl;
+pub mod gdb;
+
+use std::{collections::BTreeSet, fmt};
+
+use crate::{
+    console::Console,
+    cpu::{debug::{Register, Registers, Step, WatchHit, Watchpoint, AccessKind}, instructions::{decode::DecodedInstruction, instructions::Instruction, interrupt::Interrupt}},
+    disassembler::disassemble_linear,
+};
+
+/// Events that stop execution when they are caught, see `Debugger::catch`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum BreakEvent {
+    Nmi,
+    Irq,
+    Brk,
+    Cop,
+    /// A write to MDMAEN that starts at least one DMA channel
+    DmaStart,
+}
+
+impl BreakEvent {
+    /// Parses `nmi`, `irq`, `brk`, `cop` or `dma`
+    pub fn from_name(name: &str) -> Option<BreakEvent> {
+        match name {
+            "nmi" => Some(BreakEvent::Nmi),
+            "irq" => Some(BreakEvent::Irq),
+            "brk" => Some(BreakEvent::Brk),
+            "cop" => Some(BreakEvent::Cop),
+            "dma" => Some(BreakEvent::DmaStart),
+            _ => None,
+        }
+    }
+
+    fn from_interrupt(interrupt: Interrupt) -> Option<BreakEvent> {
+        match interrupt {
+            Interrupt::Nmi => Some(BreakEvent::Nmi),
+            Interrupt::Irq => Some(BreakEvent::Irq),
+            Interrupt::Brk => Some(BreakEvent::Brk),
+            Interrupt::Cop => Some(BreakEvent::Cop),
+            Interrupt::Abort => None,
+        }
+    }
+}
+
+/// Why the debugger stopped executing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    /// The step that was asked for is done
+    Step,
+    /// The next instruction is at a breakpoint
+    Breakpoint(u32),
+    Watchpoint(WatchHit),
+    /// A caught interrupt was entered, the next instruction is the first of its handler
+    Interrupt(Interrupt),
+    /// A caught DMA start, with the channels that were started as bit mask
+    DmaStart(u8),
+    /// The beam reached the scanline that was asked for
+    Scanline(usize),
+    /// The instruction limit was reached before anything else happened
+    Limit,
+    CpuError,
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StopReason::Step => write!(f, "Step done"),
+            StopReason::Breakpoint(addr) => write!(f, "Breakpoint at {:02X}/{:04X}", addr >> 16, addr & 0xFFFF),
+            StopReason::Watchpoint(hit) => {
+                let kind = match hit.access.kind {
+                    AccessKind::Read => "Read",
+                    AccessKind::Write => "Write",
+                };
+                write!(f, "Watchpoint {}: {kind} ${:02X} at {:02X}/{:04X}", hit.id, hit.access.value, hit.access.addr >> 16, hit.access.addr & 0xFFFF)
+            },
+            StopReason::Interrupt(interrupt) => write!(f, "Entered {interrupt:?} handler"),
+            StopReason::DmaStart(channels) => write!(f, "DMA started on channels {channels:08b}"),
+            StopReason::Scanline(line) => write!(f, "Reached scanline {line}"),
+            StopReason::Limit => write!(f, "Instruction limit reached"),
+            StopReason::CpuError => write!(f, "CPU error"),
+        }
+    }
+}
+
+/// Runs the console one instruction at a time, stopping at breakpoints, watchpoints and caught events
+///
+/// Instructions are executed with `Console::step`, so the PPU keeps running along with the CPU.
+/// Execution always stops after `limit` instructions, so a hanging game returns control to the caller
+pub struct Debugger {
+    pub console: Console,
+    /// Long addresses of instructions to stop at
+    breakpoints: BTreeSet<u32>,
+    caught: BTreeSet<BreakEvent>,
+}
+
+impl Debugger {
+    pub fn new(console: Console) -> Debugger {
+        Debugger { console, breakpoints: BTreeSet::new(), caught: BTreeSet::new() }
+    }
+
+    /// Stops before executing the instruction at long address `addr`, returns false if there already was a breakpoint
+    pub fn add_breakpoint(&mut self, addr: u32) -> bool {
+        self.breakpoints.insert(addr & 0xFFFFFF)
+    }
+
+    pub fn remove_breakpoint(&mut self, addr: u32) -> bool {
+        self.breakpoints.remove(&(addr & 0xFFFFFF))
+    }
+
+    pub fn breakpoints(&self) -> impl Iterator<Item = u32> + '_ {
+        self.breakpoints.iter().copied()
+    }
+
+    /// Returns true if there is a breakpoint at the next instruction
+    pub fn at_breakpoint(&self) -> bool {
+        self.breakpoints.contains(&self.console.cpu.get_pc_addr())
+    }
+
+    /// Adds a watchpoint, returns the id to remove it with
+    pub fn add_watchpoint(&mut self, watchpoint: Watchpoint) -> usize {
+        self.console.cpu.watch_mut().add(watchpoint)
+    }
+
+    pub fn remove_watchpoint(&mut self, id: usize) -> bool {
+        self.console.cpu.watch_mut().remove(id)
+    }
+
+    /// Watchpoints with their ids
+    pub fn watchpoints(&self) -> Vec<(usize, Watchpoint)> {
+        self.console.cpu.watch().watchpoints().map(|(id, watchpoint)| (id, watchpoint.clone())).collect()
+    }
+
+    /// Stops execution when `event` happens, or no longer does when `enabled` is false
+    pub fn catch(&mut self, event: BreakEvent, enabled: bool) {
+        match enabled {
+            true => self.caught.insert(event),
+            false => self.caught.remove(&event),
+        };
+    }
+
+    pub fn caught(&self) -> impl Iterator<Item = BreakEvent> + '_ {
+        self.caught.iter().copied()
+    }
+
+    /// Enters the NMI handler before the next instruction, see `Console::raise_nmi`
+    pub fn raise_nmi(&mut self) {
+        self.console.raise_nmi();
+    }
+
+    /// Raises an IRQ, see `Console::raise_irq`
+    pub fn raise_irq(&mut self) {
+        self.console.raise_irq();
+    }
+
+    /// Executes a single instruction, or enters a pending interrupt
+    pub fn step_into(&mut self) -> StopReason {
+        match self.step_once() {
+            Ok(_) => StopReason::Step,
+            Err(reason) => reason,
+        }
+    }
+
+    /// Executes the next instruction, running subroutines called with `JSR` or `JSL` until they return
+    pub fn step_over(&mut self, limit: usize) -> StopReason {
+        let instr = self.next_instruction();
+        if !matches!(instr.instruction, Instruction::JsrAbs | Instruction::JsrAbsIX | Instruction::JslAbsLong) {
+            return self.step_into();
+        }
+
+        let (return_addr, sp) = (instr.next_addr(), self.console.cpu.registers().sp);
+        self.run_until(limit, |debugger, _| {
+            let regs = debugger.console.cpu.registers();
+            (debugger.console.cpu.get_pc_addr() == return_addr && regs.sp == sp).then_some(StopReason::Step)
+        })
+    }
+
+    /// Runs until the current subroutine or interrupt handler returns with `RTS`, `RTL` or `RTI`
+    pub fn step_out(&mut self, limit: usize) -> StopReason {
+        let sp = self.console.cpu.registers().sp;
+        self.run_until(limit, |debugger, step| {
+            let returned = step.instruction.is_some_and(|instr| matches!(instr.instruction, Instruction::Rts | Instruction::Rtl | Instruction::Rti));
+            (returned && debugger.console.cpu.registers().sp > sp).then_some(StopReason::Step)
+        })
+    }
+
+    /// Runs until a breakpoint, watchpoint or caught event stops execution
+    pub fn resume(&mut self, limit: usize) -> StopReason {
+        self.run_until(limit, |_, _| None)
+    }
+
+    /// Runs until the beam moves onto scanline `line`
+    pub fn run_to_scanline(&mut self, line: usize, limit: usize) -> StopReason {
+        let mut previous = self.console.ppu.beam_position().1;
+        self.run_until(limit, |debugger, _| {
+            let current = debugger.console.ppu.beam_position().1;
+            let reached = current == line && previous != line;
+            previous = current;
+            reached.then_some(StopReason::Scanline(line))
+        })
+    }
+
+    pub fn registers(&self) -> Registers {
+        self.console.cpu.registers()
+    }
+
+    pub fn set_register(&mut self, reg: Register, val: u16) {
+        self.console.cpu.set_register(reg, val);
+    }
+
+    /// Reads `addr` without side effects, see `CpuMemory::peek`
+    pub fn peek(&self, addr: u32) -> Option<u8> {
+        self.console.cpu.memory.peek(addr & 0xFFFFFF)
+    }
+
+    /// Reads `len` bytes from `addr` onwards, see `Debugger::peek`
+    pub fn read_memory(&self, addr: u32, len: usize) -> Vec<Option<u8>> {
+        (0..len as u32).map(|i| self.peek(addr.wrapping_add(i))).collect()
+    }
+
+    /// Writes `bytes` from `addr` onwards without side effects, see `CpuMemory::poke`
+    pub fn write_memory(&mut self, addr: u32, bytes: &[u8]) {
+        for (i, byte) in bytes.iter().enumerate() {
+            self.console.cpu.memory.poke(addr.wrapping_add(i as u32) & 0xFFFFFF, *byte);
+        }
+    }
+
+    /// Disassembles `count` instructions from `addr`, using the current register sizes for the first one
+    pub fn disassemble(&self, addr: u32, count: usize) -> Vec<DecodedInstruction> {
+        let status = self.console.cpu.registers().status;
+        disassemble_linear(|a| self.peek(a), addr, count, status)
+    }
+
+    /// Decodes the instruction at the program counter without executing it
+    pub fn next_instruction(&self) -> DecodedInstruction {
+        let (pc, status) = (self.console.cpu.get_pc_addr(), self.console.cpu.registers().status);
+        DecodedInstruction::decode(pc, status, |a| self.peek(a).unwrap_or(0))
+    }
+
+    /// Steps until `done` returns a reason to stop, or until a breakpoint, watchpoint or caught event stops execution
+    ///
+    /// The breakpoint at the first instruction is ignored, so execution can continue from a breakpoint
+    fn run_until(&mut self, limit: usize, mut done: impl FnMut(&mut Debugger, &Step) -> Option<StopReason>) -> StopReason {
+        for i in 0..limit {
+            let pc = self.console.cpu.get_pc_addr();
+            if i > 0 && self.breakpoints.contains(&pc) {
+                return StopReason::Breakpoint(pc);
+            }
+
+            let step = match self.step_once() {
+                Ok(step) => step,
+                Err(reason) => return reason,
+            };
+            if let Some(reason) = done(self, &step) {
+                return reason;
+            }
+        }
+        StopReason::Limit
+    }
+
+    /// Executes one instruction or interrupt and runs the PPU for the time it took,
+    /// returns why execution should stop if a watchpoint or caught event was hit
+    fn step_once(&mut self) -> Result<Step, StopReason> {
+        let step = self.console.step().map_err(|_| StopReason::CpuError)?;
+
+        let hits = self.console.cpu.watch_mut().take_hits();
+        let dma_start = self.console.cpu.watch_mut().take_dma_start();
+        if let Some(hit) = hits.first() {
+            return Err(StopReason::Watchpoint(*hit));
+        }
+        if dma_start != 0 && self.caught.contains(&BreakEvent::DmaStart) {
+            return Err(StopReason::DmaStart(dma_start));
+        }
+        if let Some(interrupt) = step.interrupt {
+            if BreakEvent::from_interrupt(interrupt).is_some_and(|event| self.caught.contains(&event)) {
+                return Err(StopReason::Interrupt(interrupt));
+            }
+        }
+        Ok(step)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cpu::debug::{MemoryAccess, ValueCondition, WatchKind};
+
+    /// Debugger stopped at `$7E:0000` in front of a small program in WRAM:
+    ///
+    /// ```text
+    /// 7E/0000  JSR $0010
+    /// 7E/0003  STA $0100
+    /// 7E/0006  NOP
+    /// 7E/0007  BRA $0007
+    /// 7E/0010  LDA #$42
+    /// 7E/0012  RTS
+    /// ```
+    fn debugger() -> Debugger {
+        let mut debugger = Debugger::new(Console::new());
+        debugger.write_memory(0x7E0000, &[0x20, 0x10, 0x00, 0x8D, 0x00, 0x01, 0xEA, 0x80, 0xFE]);
+        debugger.write_memory(0x7E0010, &[0xA9, 0x42, 0x60]);
+        debugger.set_register(Register::PB, 0x7E);
+        debugger.set_register(Register::DB, 0x7E);
+        debugger.set_register(Register::PC, 0x0000);
+        debugger
+    }
+
+    fn pc(debugger: &Debugger) -> u32 {
+        debugger.console.cpu.get_pc_addr()
+    }
+
+    #[test]
+    fn test_step_into_and_out() {
+        let mut debugger = debugger();
+        assert_eq!(debugger.step_into(), StopReason::Step);
+        assert_eq!(pc(&debugger), 0x7E0010);
+
+        assert_eq!(debugger.step_out(100), StopReason::Step);
+        assert_eq!(pc(&debugger), 0x7E0003);
+        assert_eq!(debugger.registers().acc & 0xFF, 0x42);
+    }
+
+    #[test]
+    fn test_step_over() {
+        let mut debugger = debugger();
+        assert_eq!(debugger.step_over(100), StopReason::Step);
+        assert_eq!(pc(&debugger), 0x7E0003);
+        assert_eq!(debugger.registers().acc & 0xFF, 0x42);
+
+        // Instructions other than calls are stepped into
+        assert_eq!(debugger.step_over(100), StopReason::Step);
+        assert_eq!(pc(&debugger), 0x7E0006);
+    }
+
+    #[test]
+    fn test_breakpoint() {
+        let mut debugger = debugger();
+        assert!(debugger.add_breakpoint(0x7E0006));
+        assert!(!debugger.add_breakpoint(0x7E0006));
+
+        assert_eq!(debugger.resume(100), StopReason::Breakpoint(0x7E0006));
+        assert!(debugger.at_breakpoint());
+        // Resuming from a breakpoint doesn't stop at it again, the loop after it runs into the limit
+        assert_eq!(debugger.resume(100), StopReason::Limit);
+
+        assert!(debugger.remove_breakpoint(0x7E0006));
+        assert_eq!(debugger.breakpoints().count(), 0);
+    }
+
+    #[test]
+    fn test_breakpoint_in_subroutine_stops_step_over() {
+        let mut debugger = debugger();
+        debugger.add_breakpoint(0x7E0012);
+        assert_eq!(debugger.step_over(100), StopReason::Breakpoint(0x7E0012));
+    }
+
+    #[test]
+    fn test_write_watchpoint() {
+        let mut debugger = debugger();
+        let id = debugger.add_watchpoint(Watchpoint { range: 0x7E0100..=0x7E0100, kind: WatchKind::Write, condition: None });
+
+        let StopReason::Watchpoint(hit) = debugger.resume(100) else { panic!("expected a watchpoint hit") };
+        assert_eq!(hit.id, id);
+        assert_eq!(hit.access, MemoryAccess { addr: 0x7E0100, value: 0x42, kind: AccessKind::Write });
+        // Execution stops after the instruction that accessed the memory
+        assert_eq!(pc(&debugger), 0x7E0006);
+        assert_eq!(debugger.peek(0x7E0100), Some(0x42));
+    }
+
+    #[test]
+    fn test_watchpoint_condition() {
+        let mut debugger = debugger();
+        let condition = Some(ValueCondition::NotEqual(0x42));
+        debugger.add_watchpoint(Watchpoint { range: 0x7E0100..=0x7E01FF, kind: WatchKind::Write, condition });
+        assert_eq!(debugger.resume(100), StopReason::Limit);
+    }
+
+    #[test]
+    fn test_read_watchpoint_sees_op_code_fetch() {
+        let mut debugger = debugger();
+        let id = debugger.add_watchpoint(Watchpoint { range: 0x7E0012..=0x7E0012, kind: WatchKind::Read, condition: None });
+
+        let StopReason::Watchpoint(hit) = debugger.resume(100) else { panic!("expected a watchpoint hit") };
+        assert_eq!(hit.id, id);
+        assert_eq!(hit.access, MemoryAccess { addr: 0x7E0012, value: 0x60, kind: AccessKind::Read });
+        assert_eq!(pc(&debugger), 0x7E0003);
+
+        assert!(debugger.remove_watchpoint(id));
+        assert_eq!(debugger.resume(100), StopReason::Limit);
+    }
+}
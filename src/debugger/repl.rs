@@ -0,0 +1,305 @@
+use std::io::{self, BufRead, Write};
+
+use crate::{
+    console::Console,
+    cpu::debug::{Register, ValueCondition, WatchKind, Watchpoint},
+    disassembler::{format_listing, symbols::{self, SymbolTable}},
+};
+
+use super::{BreakEvent, Debugger, StopReason};
+
+/// Instructions a command runs at most before returning to the prompt, so a hanging game can be inspected
+pub const RUN_LIMIT: usize = 10_000_000;
+
+const HELP: &str = "\
+Commands, addresses are BB:HHLL, $BBHHLL or a label and values are $hex, 0xhex or decimal:
+  s, step [n]            execute n instructions, defaults to 1
+  n, next                step over JSR and JSL
+  finish                 run until the current subroutine or interrupt handler returns
+  c, continue [n]        run until something stops execution, at most n instructions
+  scanline <line>        run until the beam reaches a scanline
+  b, break <addr>        stop before executing the instruction at addr
+  delete <addr>          remove a breakpoint
+  watch <r|w|rw> <addr>[-<end>] [==|!=|<|> <value>]
+                         stop when the CPU accesses memory, optionally only for some values
+  unwatch <id>           remove a watchpoint
+  catch <event>          stop on nmi, irq, brk, cop or dma
+  uncatch <event>        no longer stop on an event
+  info                   list breakpoints, watchpoints and caught events
+  nmi, irq               raise an interrupt before the next instruction
+  r, regs                show the registers
+  set <reg> <value>      set A, X, Y, S, D, DB, PB, PC or P
+  x <addr> [len]         show memory, bytes that can't be read show as ??
+  poke <addr> <byte>...  write memory
+  dis [addr] [count]     disassemble, defaults to the next instruction
+  q, quit                leave the debugger
+An empty line repeats the last command
+";
+
+/// Text interface to a `Debugger`, reads one command per line
+pub struct Repl {
+    debugger: Debugger,
+    symbols: Option<SymbolTable>,
+    last_command: String,
+}
+
+impl Repl {
+    pub fn new(debugger: Debugger, symbols: Option<SymbolTable>) -> Repl {
+        Repl { debugger, symbols, last_command: String::new() }
+    }
+
+    pub fn debugger_mut(&mut self) -> &mut Debugger {
+        &mut self.debugger
+    }
+
+    /// Reads commands from `input` until `quit` or the end of the input, writes their output to `output`
+    pub fn run(&mut self, input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+        write!(output, "{}(dbg) ", self.location())?;
+        output.flush()?;
+        for line in input.lines() {
+            match self.execute(&line?) {
+                Some(response) => write!(output, "{response}(dbg) ")?,
+                None => break,
+            }
+            output.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Executes a single command and returns its output, `None` when the command is `quit`
+    pub fn execute(&mut self, line: &str) -> Option<String> {
+        let line = match line.trim() {
+            "" => self.last_command.clone(),
+            line => line.to_string(),
+        };
+        self.last_command = line.clone();
+
+        let args: Vec<&str> = line.split_whitespace().collect();
+        let Some(&command) = args.first() else { return Some(String::new()) };
+        let result = match command {
+            "q" | "quit" => return None,
+            "h" | "help" => Ok(HELP.to_string()),
+            "s" | "step" => self.step(&args),
+            "n" | "next" => {
+                let reason = self.debugger.step_over(RUN_LIMIT);
+                Ok(self.stopped(reason))
+            },
+            "finish" => {
+                let reason = self.debugger.step_out(RUN_LIMIT);
+                Ok(self.stopped(reason))
+            },
+            "c" | "continue" => self.count(args.get(1), RUN_LIMIT).map(|limit| {
+                let reason = self.debugger.resume(limit);
+                self.stopped(reason)
+            }),
+            "scanline" => self.value(args.get(1)).map(|line| {
+                let reason = self.debugger.run_to_scanline(line as usize, RUN_LIMIT);
+                self.stopped(reason)
+            }),
+            "b" | "break" => self.addr(args.get(1)).map(|addr| match self.debugger.add_breakpoint(addr) {
+                true => format!("Breakpoint at {}\n", self.format_addr(addr)),
+                false => "Breakpoint already set\n".to_string(),
+            }),
+            "delete" => self.addr(args.get(1)).map(|addr| match self.debugger.remove_breakpoint(addr) {
+                true => "Breakpoint removed\n".to_string(),
+                false => "No breakpoint there\n".to_string(),
+            }),
+            "watch" => self.watch(&args),
+            "unwatch" => self.value(args.get(1)).map(|id| match self.debugger.remove_watchpoint(id as usize) {
+                true => "Watchpoint removed\n".to_string(),
+                false => "No such watchpoint\n".to_string(),
+            }),
+            "catch" | "uncatch" => match args.get(1).and_then(|name| BreakEvent::from_name(name)) {
+                Some(event) => {
+                    self.debugger.catch(event, command == "catch");
+                    Ok(String::new())
+                },
+                None => Err("Expected nmi, irq, brk, cop or dma".to_string()),
+            },
+            "info" => Ok(self.info()),
+            "nmi" => {
+                self.debugger.raise_nmi();
+                Ok(String::new())
+            },
+            "irq" => {
+                self.debugger.raise_irq();
+                Ok(String::new())
+            },
+            "r" | "regs" => Ok(format!("{}\n", self.debugger.registers())),
+            "set" => match args.get(1).and_then(|name| Register::from_name(name)) {
+                Some(reg) => self.value(args.get(2)).map(|val| {
+                    self.debugger.set_register(reg, val as u16);
+                    format!("{}\n", self.debugger.registers())
+                }),
+                None => Err("Expected a register: A, X, Y, S, D, DB, PB, PC or P".to_string()),
+            },
+            "x" => self.addr(args.get(1)).and_then(|addr| {
+                let len = self.count(args.get(2), 64)?;
+                Ok(self.hex_dump(addr, len))
+            }),
+            "poke" => self.addr(args.get(1)).and_then(|addr| {
+                let bytes = args[2..].iter().map(|arg| self.value(Some(arg)).map(|v| v as u8)).collect::<Result<Vec<u8>, String>>()?;
+                self.debugger.write_memory(addr, &bytes);
+                Ok(String::new())
+            }),
+            "dis" => {
+                let addr = match args.get(1) {
+                    Some(_) => self.addr(args.get(1)),
+                    None => Ok(self.debugger.console.cpu.get_pc_addr()),
+                };
+                addr.and_then(|addr| {
+                    let count = self.count(args.get(2), 10)?;
+                    let instructions = self.debugger.disassemble(addr, count);
+                    Ok(format_listing(&instructions, self.symbols.as_ref()))
+                })
+            },
+            _ => Err(format!("Unknown command: {command}, try help")),
+        };
+
+        Some(result.unwrap_or_else(|e| format!("{e}\n")))
+    }
+
+    fn step(&mut self, args: &[&str]) -> Result<String, String> {
+        let count = self.count(args.get(1), 1)?;
+        let mut reason = StopReason::Step;
+        for _ in 0..count {
+            reason = self.debugger.step_into();
+            if reason != StopReason::Step {
+                break;
+            }
+        }
+        Ok(self.stopped(reason))
+    }
+
+    /// `watch <r|w|rw> <addr>[-<end>] [<op> <value>]`, the operator may also be written against the value as in `==$12`
+    fn watch(&mut self, args: &[&str]) -> Result<String, String> {
+        let kind = args.get(1).and_then(|kind| WatchKind::from_name(kind)).ok_or("Expected r, w or rw")?;
+        let range = args.get(2).ok_or("Expected an address")?;
+        let (start, end) = match range.split_once('-') {
+            Some((start, end)) => (self.addr(Some(&start))?, self.addr(Some(&end))?),
+            None => {
+                let addr = self.addr(Some(range))?;
+                (addr, addr)
+            },
+        };
+
+        let condition = args[3.min(args.len())..].concat();
+        let condition = match condition.as_str() {
+            "" => None,
+            c => {
+                let (make, value): (fn(u8) -> ValueCondition, &str) = if let Some(v) = c.strip_prefix("==") {
+                    (ValueCondition::Equal, v)
+                } else if let Some(v) = c.strip_prefix("!=") {
+                    (ValueCondition::NotEqual, v)
+                } else if let Some(v) = c.strip_prefix('<') {
+                    (ValueCondition::Less, v)
+                } else if let Some(v) = c.strip_prefix('>') {
+                    (ValueCondition::Greater, v)
+                } else {
+                    return Err("Expected a condition as ==, !=, < or > followed by a value".to_string());
+                };
+                Some(make(self.value(Some(&value))? as u8))
+            },
+        };
+
+        let id = self.debugger.add_watchpoint(Watchpoint { range: start..=end, kind, condition });
+        Ok(format!("Watchpoint {id}\n"))
+    }
+
+    fn info(&self) -> String {
+        let mut info = String::from("Breakpoints:\n");
+        for addr in self.debugger.breakpoints() {
+            info.push_str(&format!("  {}\n", self.format_addr(addr)));
+        }
+        info.push_str("Watchpoints:\n");
+        for (id, watchpoint) in self.debugger.watchpoints() {
+            info.push_str(&format!("  {id}: {:?} {}-{}", watchpoint.kind, self.format_addr(*watchpoint.range.start()), self.format_addr(*watchpoint.range.end())));
+            if let Some(condition) = watchpoint.condition {
+                info.push_str(&format!(" {condition:?}"));
+            }
+            info.push('\n');
+        }
+        let caught: Vec<String> = self.debugger.caught().map(|event| format!("{event:?}")).collect();
+        info.push_str(&format!("Caught: {}\n", caught.join(", ")));
+        info
+    }
+
+    fn hex_dump(&mut self, addr: u32, len: usize) -> String {
+        let bytes = self.debugger.read_memory(addr, len);
+        let mut dump = String::new();
+        for (i, line) in bytes.chunks(16).enumerate() {
+            let line_addr = addr.wrapping_add(i as u32 * 16) & 0xFFFFFF;
+            let hex: Vec<String> = line.iter().map(|byte| byte.map_or("??".to_string(), |b| format!("{b:02X}"))).collect();
+            dump.push_str(&format!("{:02X}/{:04X}  {}\n", line_addr >> 16, line_addr & 0xFFFF, hex.join(" ")));
+        }
+        dump
+    }
+
+    /// Reason execution stopped followed by the next instruction and the registers
+    fn stopped(&mut self, reason: StopReason) -> String {
+        format!("{reason}\n{}", self.location())
+    }
+
+    /// Next instruction and the registers
+    fn location(&mut self) -> String {
+        let instr = self.debugger.next_instruction();
+        format!("{}{}\n", format_listing(&[instr], self.symbols.as_ref()), self.debugger.registers())
+    }
+
+    fn format_addr(&self, addr: u32) -> String {
+        let text = format!("{:02X}/{:04X}", addr >> 16, addr & 0xFFFF);
+        match self.symbols.as_ref().and_then(|s| s.get(addr)) {
+            Some(label) => format!("{text} ({label})"),
+            None => text,
+        }
+    }
+
+    /// Parses a long address or a label
+    fn addr(&self, arg: Option<&&str>) -> Result<u32, String> {
+        let arg = arg.ok_or("Expected an address")?;
+        symbols::parse_addr(arg)
+            .or_else(|| self.symbols.as_ref()?.find(arg))
+            .ok_or_else(|| format!("Invalid address: {arg}"))
+    }
+
+    /// Parses `$hex`, `0xhex` or decimal
+    fn value(&self, arg: Option<&&str>) -> Result<u32, String> {
+        let arg = arg.ok_or("Expected a value")?;
+        let parsed = match arg.strip_prefix('$').or_else(|| arg.strip_prefix("0x")) {
+            Some(hex) => u32::from_str_radix(hex, 16),
+            None => arg.parse(),
+        };
+        parsed.map_err(|_| format!("Invalid value: {arg}"))
+    }
+
+    /// Parses an optional count, `default` if it's missing
+    fn count(&self, arg: Option<&&str>, default: usize) -> Result<usize, String> {
+        match arg {
+            Some(_) => self.value(arg).map(|n| n as usize),
+            None => Ok(default),
+        }
+    }
+}
+
+/// Runs the `debug` subcommand, a debugger reading commands from the terminal
+///
+/// `--symbols <path>` loads labels from a symbol file, see `SymbolTable::parse`
+pub fn run(args: impl Iterator<Item = String>, console: Console) {
+    let mut symbols = None;
+    let mut iter = args;
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--symbols" => match iter.next().map(|path| (SymbolTable::load(&path), path)) {
+                Some((Ok(table), _)) => symbols = Some(table),
+                Some((Err(e), path)) => eprintln!("Failed to load symbols from {path}: {e}"),
+                None => eprintln!("Expected a path after --symbols"),
+            },
+            _ => eprintln!("Unknown argument: {arg}"),
+        }
+    }
+
+    let mut repl = Repl::new(Debugger::new(console), symbols);
+    if let Err(e) = repl.run(io::stdin().lock(), io::stdout()) {
+        eprintln!("{e}");
+    }
+}
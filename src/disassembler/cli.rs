@@ -0,0 +1,99 @@
+use crate::{console::Console, cpu::{RESET_VECTOR, processorstatusflag::ProcessorStatusFlags, instructions::interrupt::Interrupt}};
+
+use super::{disassemble, disassemble_linear, disassemble_recursive, format_listing, is_rom_addr, symbols::{self, SymbolTable}};
+
+/// Options of the `disasm` subcommand
+///
+/// * `--symbols <path>`: replace addresses by labels from a symbol file, see `SymbolTable::parse`
+/// * `--recursive`: disassemble all code reachable from the interrupt vectors instead of a linear range
+/// * `--start <addr>`: first address of a linear disassembly as `BB:HHLL` or `$BBHHLL`, defaults to the reset vector
+/// * `--count <n>`: number of instructions of a linear disassembly, defaults to 64
+/// * `--m16`, `--x16`: start a linear disassembly with a 16 bit accumulator or index registers
+/// * `--file <path>`: disassemble a raw binary file instead of the ROM, loaded at `--start`
+pub struct DisasmArgs {
+    symbols: Option<String>,
+    recursive: bool,
+    start: Option<u32>,
+    count: usize,
+    m16: bool,
+    x16: bool,
+    file: Option<String>,
+}
+
+impl DisasmArgs {
+    pub fn parse(args: impl Iterator<Item = String>) -> DisasmArgs {
+        let mut parsed = DisasmArgs { symbols: None, recursive: false, start: None, count: 64, m16: false, x16: false, file: None };
+
+        let mut iter = args;
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--symbols" => parsed.symbols = iter.next(),
+                "--recursive" => parsed.recursive = true,
+                "--start" => match iter.next().as_deref().and_then(symbols::parse_addr) {
+                    Some(addr) => parsed.start = Some(addr),
+                    None => eprintln!("Invalid start address, expected BB:HHLL or $BBHHLL"),
+                },
+                "--count" => parsed.count = iter.next().and_then(|n| n.parse().ok()).unwrap_or(parsed.count),
+                "--m16" => parsed.m16 = true,
+                "--x16" => parsed.x16 = true,
+                "--file" => parsed.file = iter.next(),
+                _ => eprintln!("Unknown argument: {arg}"),
+            }
+        }
+
+        parsed
+    }
+}
+
+/// Runs the `disasm` subcommand on the cartridge in `console` and prints the listing
+pub fn run(args: DisasmArgs, console: &Console) {
+    let symbols = match &args.symbols {
+        Some(path) => match SymbolTable::load(path) {
+            Ok(symbols) => Some(symbols),
+            Err(e) => {
+                eprintln!("Failed to load symbols from {path}: {e}");
+                None
+            },
+        },
+        None => None,
+    };
+
+    let memory = &console.cpu.memory;
+    let read = |addr: u32| if is_rom_addr(addr) { memory.peek(addr) } else { None };
+    let read_vector = |vector: u32| Some(((read(vector + 1)? as u32) << 8) | read(vector)? as u32);
+
+    let instructions = if let Some(path) = &args.file {
+        match std::fs::read(path) {
+            Ok(bytes) => disassemble(&bytes, args.start.unwrap_or(0), !args.m16, !args.x16),
+            Err(e) => {
+                eprintln!("Failed to read {path}: {e}");
+                return;
+            },
+        }
+    } else if args.recursive {
+        let emulation = ProcessorStatusFlags::Emulation | ProcessorStatusFlags::Accumulator8bit | ProcessorStatusFlags::XYreg8bit;
+        let native = ProcessorStatusFlags::Accumulator8bit | ProcessorStatusFlags::XYreg8bit;
+
+        let mut vectors = vec![(RESET_VECTOR, emulation)];
+        for interrupt in [Interrupt::Cop, Interrupt::Brk, Interrupt::Abort, Interrupt::Nmi, Interrupt::Irq] {
+            vectors.push((interrupt.vector(false), native));
+            vectors.push((interrupt.vector(true), emulation));
+        }
+        let entries: Vec<_> = vectors.into_iter()
+            .filter_map(|(vector, status)| read_vector(vector).filter(|addr| is_rom_addr(*addr)).map(|addr| (addr, status)))
+            .collect();
+
+        disassemble_recursive(read, &entries)
+    } else {
+        let Some(start) = args.start.or_else(|| read_vector(RESET_VECTOR)) else {
+            eprintln!("Could not read reset vector");
+            return;
+        };
+        let mut status = ProcessorStatusFlags::new();
+        status.set(ProcessorStatusFlags::Accumulator8bit, !args.m16);
+        status.set(ProcessorStatusFlags::XYreg8bit, !args.x16);
+        disassemble_linear(read, start, args.count, status)
+    };
+
+    print!("{}", format_listing(&instructions, symbols.as_ref()));
+}
@@ -0,0 +1,74 @@
+use crate::cpu::instructions::{AddressingMode, instructions::Instruction, decode::DecodedInstruction};
+
+use super::symbols::SymbolTable;
+
+impl DecodedInstruction {
+    /// Formats this instruction in standard assembler syntax, ie `LDA $1234,X`, `LDA [$12],Y` or `MVN $7E,$00`
+    ///
+    /// Addresses of jumps, branches and absolute accesses are replaced by their label if `symbols` has one
+    pub fn format_asm(&self, symbols: Option<&SymbolTable>) -> String {
+        let mnemonic = self.instruction.get_mnemonic();
+        match self.format_operand(symbols) {
+            Some(operand) => format!("{mnemonic} {operand}"),
+            None => mnemonic.to_string(),
+        }
+    }
+
+    /// Operand in assembler syntax, `None` for instructions without one
+    fn format_operand(&self, symbols: Option<&SymbolTable>) -> Option<String> {
+        use AddressingMode::*;
+        use Instruction::*;
+
+        let arg = self.operand();
+        let byte = format!("${arg:02X}");
+        let word = format!("${arg:04X}");
+        // Absolute addresses use the bank of the instruction for labels, this is right for jumps and the usual case for data
+        let absolute = || self.label(symbols, (self.addr & 0xFF0000) | arg).unwrap_or_else(|| word.clone());
+        let long = || self.label(symbols, arg).unwrap_or_else(|| format!("${arg:06X}"));
+
+        let operand = match self.mode {
+            Absolute => absolute(),
+            AbsoluteX => format!("{},X", absolute()),
+            AbsoluteY => format!("{},Y", absolute()),
+            AbsoluteIndirect => format!("({word})"),
+            AbsoluteIndirectX => format!("({word},X)"),
+            AbsoluteIndirectLong => format!("[{word}]"),
+            Direct => byte,
+            DirectX => format!("{byte},X"),
+            DirectY => format!("{byte},Y"),
+            Immediate => format!("#{byte}"),
+            ImmediateLong => format!("#{word}"),
+            Indirect => format!("({byte})"),
+            IndirectX => format!("({byte},X)"),
+            IndirectY => format!("({byte}),Y"),
+            IndirectLong => format!("[{byte}]"),
+            IndirectLongY => format!("[{byte}],Y"),
+            Long => long(),
+            LongX => format!("{},X", long()),
+            Relative | RelativeLong => self.format_target(symbols)?,
+            StackRelative => format!("{byte},S"),
+            StackRelativeY => format!("({byte},S),Y"),
+            // Operands are stored as destination bank, source bank but written as source, destination
+            Move => format!("${:02X},${:02X}", self.operand_bytes()[1], self.operand_bytes()[0]),
+            Implied => match self.instruction {
+                Asl | Lsr | Rol | Ror | Dea | Ina => "A".to_string(),
+                Pea => absolute(),
+                Pei => format!("({byte})"),
+                Per => self.format_target(symbols)?,
+                Brk | Cop | Wdm => format!("#{byte}"),
+                _ => return None,
+            },
+        };
+        Some(operand)
+    }
+
+    /// Target of a branch as label or long address
+    fn format_target(&self, symbols: Option<&SymbolTable>) -> Option<String> {
+        let target = self.branch_target()?;
+        Some(self.label(symbols, target).unwrap_or_else(|| format!("${:04X}", target & 0xFFFF)))
+    }
+
+    fn label(&self, symbols: Option<&SymbolTable>, addr: u32) -> Option<String> {
+        symbols?.get(addr).map(str::to_string)
+    }
+}
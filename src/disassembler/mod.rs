@@ -0,0 +1,233 @@
+pub mod symbols;
+pub mod cli;
+mod format;
+
+use std::collections::{BTreeMap, VecDeque};
+
+use crate::cpu::{processorstatusflag::ProcessorStatusFlags, instructions::{instructions::Instruction, decode::DecodedInstruction}};
+
+use self::symbols::SymbolTable;
+
+/// Disassembles `bytes` as a linear sequence of instructions, the first byte is at long address `addr`
+///
+/// `m_flag` and `x_flag` are the values of the M and X status flags at the first instruction, set means 8 bit registers.
+/// `REP` and `SEP` change them for the instructions that follow. Instructions run on within the bank of `addr`,
+/// disassembly stops at the first instruction that does not fit in `bytes`
+pub fn disassemble(bytes: &[u8], addr: u32, m_flag: bool, x_flag: bool) -> Vec<DecodedInstruction> {
+    let mut status = ProcessorStatusFlags::new();
+    status.set(ProcessorStatusFlags::Accumulator8bit, m_flag);
+    status.set(ProcessorStatusFlags::XYreg8bit, x_flag);
+
+    let mut instructions = Vec::new();
+    let (mut pc, mut offset) = (addr, 0);
+    while offset < bytes.len() {
+        let instr = DecodedInstruction::decode(pc, status, |a| {
+            bytes.get(offset + (a.wrapping_sub(pc) & 0xFFFF) as usize).copied().unwrap_or(0)
+        });
+        if offset + instr.length() > bytes.len() {
+            break;
+        }
+
+        track_status(&mut status, &instr);
+        offset += instr.length();
+        pc = instr.next_addr();
+        instructions.push(instr);
+    }
+    instructions
+}
+
+/// Disassembles `count` instructions starting at `addr`, reading memory with `read`
+///
+/// `read` returns `None` for addresses that can't be disassembled, disassembly stops there
+pub fn disassemble_linear(mut read: impl FnMut(u32) -> Option<u8>, addr: u32, count: usize, mut status: ProcessorStatusFlags) -> Vec<DecodedInstruction> {
+    let mut instructions = Vec::new();
+    let mut pc = addr;
+    while instructions.len() < count {
+        let Some(instr) = decode_at(&mut read, pc, status) else { break };
+        track_status(&mut status, &instr);
+        pc = instr.next_addr();
+        instructions.push(instr);
+    }
+    instructions
+}
+
+/// Disassembles all code reachable from `entries` by following branches, jumps and subroutine calls,
+/// returns the instructions sorted by address
+///
+/// Every entry point has the status the code is entered with. Register sizes are tracked along every path,
+/// an instruction reached with different register sizes is only disassembled the first way it was reached.
+/// Indirect jumps and returns end a path, since their target is only known at run time
+pub fn disassemble_recursive(mut read: impl FnMut(u32) -> Option<u8>, entries: &[(u32, ProcessorStatusFlags)]) -> Vec<DecodedInstruction> {
+    use Instruction::*;
+
+    let mut found = BTreeMap::new();
+    let mut pending: VecDeque<(u32, ProcessorStatusFlags)> = entries.iter().copied().collect();
+
+    while let Some((addr, mut status)) = pending.pop_front() {
+        if found.contains_key(&addr) {
+            continue;
+        }
+        let Some(instr) = decode_at(&mut read, addr, status) else { continue };
+        found.insert(addr, instr);
+        track_status(&mut status, &instr);
+
+        let bank = instr.addr & 0xFF0000;
+        let (target, falls_through) = match instr.instruction {
+            Bra | Brl => (instr.branch_target(), false),
+            Blt | Bge | Beq | Bmi | Bne | Bpl | Bvc | Bvs => (instr.branch_target(), true),
+            JmpAbs => (Some(bank | instr.operand()), false),
+            JmlAbsLong => (Some(instr.operand()), false),
+            JsrAbs => (Some(bank | instr.operand()), true),
+            JslAbsLong => (Some(instr.operand()), true),
+            JmpIndirect | JmpAbsIX | Jml | Rts | Rtl | Rti | Stp | Brk => (None, false),
+            _ => (None, true),
+        };
+
+        if let Some(target) = target {
+            pending.push_back((target, status));
+        }
+        if falls_through {
+            pending.push_back((instr.next_addr(), status));
+        }
+    }
+
+    found.into_values().collect()
+}
+
+/// Formats instructions as a listing with one instruction per line, ie `00/8000  A9 00        LDA #$00`
+///
+/// Labels from `symbols` are written on their own line before the instruction at their address,
+/// gaps between instructions that don't follow each other are marked with an empty line
+pub fn format_listing(instructions: &[DecodedInstruction], symbols: Option<&SymbolTable>) -> String {
+    let mut listing = String::new();
+    let mut next = None;
+    for instr in instructions {
+        if next.is_some_and(|next| next != instr.addr) {
+            listing.push('\n');
+        }
+        next = Some(instr.next_addr());
+
+        if let Some(label) = symbols.and_then(|s| s.get(instr.addr)) {
+            listing.push_str(&format!("{label}:\n"));
+        }
+
+        let bytes: Vec<String> = std::iter::once(instr.opcode).chain(instr.operand_bytes().iter().copied())
+            .map(|b| format!("{b:02X}"))
+            .collect();
+        listing.push_str(&format!("{:02X}/{:04X}  {:<11}  {}\n", instr.addr >> 16, instr.addr & 0xFFFF, bytes.join(" "), instr.format_asm(symbols)));
+    }
+    listing
+}
+
+/// Returns true if `addr` is in a region that can hold code in ROM, which can be read without side effects
+///
+/// I/O registers and open bus are excluded, WRAM in banks `$7E` and `$7F` is excluded since it's empty when disassembling a ROM
+pub fn is_rom_addr(addr: u32) -> bool {
+    let (bank, hhll) = ((addr >> 16) as u8, addr as u16);
+    match bank {
+        0x7E | 0x7F => false,
+        0x40..=0x7D | 0xC0..=0xFF => true,
+        _ => hhll >= 0x8000,
+    }
+}
+
+/// Decodes the instruction at `addr`, `None` if any of its bytes can't be read
+fn decode_at(read: &mut impl FnMut(u32) -> Option<u8>, addr: u32, status: ProcessorStatusFlags) -> Option<DecodedInstruction> {
+    let mut readable = true;
+    let instr = DecodedInstruction::decode(addr, status, |a| read(a).unwrap_or_else(|| {
+        readable = false;
+        0
+    }));
+    readable.then_some(instr)
+}
+
+/// Updates `status` with the effect `instr` has on the register sizes
+///
+/// `CLC` and `SEC` are tracked because they are used to pick the mode with `XCE`, emulation mode forces 8 bit registers
+fn track_status(status: &mut ProcessorStatusFlags, instr: &DecodedInstruction) {
+    use Instruction::*;
+    match instr.instruction {
+        RepImm => status.clear_bits(instr.operand() as u8),
+        SepImm => status.set_bits(instr.operand() as u8),
+        Clc => status.clear_flag(ProcessorStatusFlags::Carry),
+        Sec => status.set_flag(ProcessorStatusFlags::Carry),
+        Xce => {
+            let carry = status.contains(ProcessorStatusFlags::Carry);
+            status.set(ProcessorStatusFlags::Carry, status.contains(ProcessorStatusFlags::Emulation));
+            status.set(ProcessorStatusFlags::Emulation, carry);
+        },
+        _ => return,
+    }
+
+    if status.contains(ProcessorStatusFlags::Emulation) {
+        status.set_flag(ProcessorStatusFlags::Accumulator8bit | ProcessorStatusFlags::XYreg8bit);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disassemble_tracks_register_sizes() {
+        // REP #$20, LDA #$1234, SEP #$20, LDA #$12
+        let bytes = [0xC2, 0x20, 0xA9, 0x34, 0x12, 0xE2, 0x20, 0xA9, 0x12];
+        let instructions = disassemble(&bytes, 0x008000, true, true);
+
+        let lengths: Vec<usize> = instructions.iter().map(|i| i.length()).collect();
+        assert_eq!(lengths, [2, 3, 2, 2]);
+        assert_eq!(instructions[3].addr, 0x008007);
+    }
+
+    #[test]
+    fn test_disassemble_stops_at_partial_instruction() {
+        // LDA #$1234 with 16 bit accumulator, missing its last byte
+        let instructions = disassemble(&[0xEA, 0xA9, 0x34], 0x008000, false, false);
+        assert_eq!(instructions.len(), 1);
+    }
+
+    #[test]
+    fn test_format_asm() {
+        let cases: [(&[u8], &str); 7] = [
+            (&[0xB1, 0x12], "LDA ($12),Y"),
+            (&[0xB7, 0x12], "LDA [$12],Y"),
+            (&[0xBD, 0x34, 0x12], "LDA $1234,X"),
+            (&[0x54, 0x7E, 0x00], "MVN $00,$7E"),
+            (&[0x0A], "ASL A"),
+            (&[0xD0, 0xFE], "BNE $8000"),
+            (&[0xEA], "NOP"),
+        ];
+        for (bytes, asm) in cases {
+            let instr = &disassemble(bytes, 0x008000, true, true)[0];
+            assert_eq!(instr.format_asm(None), asm);
+        }
+    }
+
+    #[test]
+    fn test_format_listing() {
+        let symbols = SymbolTable::parse("00:8000 Reset\n$008006 Init\n");
+        // JSL Init, BRA Reset, then the subroutine
+        let mut instructions = disassemble(&[0x22, 0x06, 0x80, 0x00, 0x80, 0xFA], 0x008000, true, true);
+        instructions.extend(disassemble(&[0x6B], 0x008006, true, true));
+
+        let listing = format_listing(&instructions, Some(&symbols));
+        assert_eq!(listing, "\
+Reset:
+00/8000  22 06 80 00  JSL Init
+00/8004  80 FA        BRA Reset
+Init:
+00/8006  6B           RTL
+");
+    }
+
+    #[test]
+    fn test_disassemble_recursive() {
+        // $8000: BEQ $8005, $8002: JSR $8006, $8005: RTS, $8006: RTS. The byte after the last RTS is never reached
+        let rom = [0xF0, 0x03, 0x20, 0x06, 0x80, 0x60, 0x60, 0xFF];
+        let read = |addr: u32| rom.get((addr as usize).checked_sub(0x8000)?).copied();
+        let instructions = disassemble_recursive(read, &[(0x008000, ProcessorStatusFlags::new())]);
+
+        let addrs: Vec<u32> = instructions.iter().map(|i| i.addr).collect();
+        assert_eq!(addrs, [0x008000, 0x008002, 0x008005, 0x008006]);
+    }
+}
@@ -0,0 +1,83 @@
+use std::{collections::BTreeMap, fs, io, path::Path};
+
+/// Labels for long addresses, used to substitute operands in disassembly
+///
+/// Symbol files have one label per line as `BB:HHLL name` (WLA DX and bsnes style) or `$BBHHLL name`.
+/// Lines starting with `;` or `#`, section headers like `[labels]` and lines that don't start with an address are ignored
+pub struct SymbolTable {
+    labels: BTreeMap<u32, String>,
+}
+
+impl SymbolTable {
+    pub fn new() -> SymbolTable {
+        SymbolTable { labels: BTreeMap::new() }
+    }
+
+    /// Reads a symbol file from `path`, see `SymbolTable::parse`
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<SymbolTable> {
+        Ok(SymbolTable::parse(&fs::read_to_string(path)?))
+    }
+
+    /// Parses the contents of a symbol file, lines that are not labels are skipped
+    pub fn parse(text: &str) -> SymbolTable {
+        let mut table = SymbolTable::new();
+        for line in text.lines() {
+            let line = line.split(';').next().unwrap_or("").trim();
+            if line.starts_with('#') || line.starts_with('[') {
+                continue;
+            }
+
+            let mut parts = line.split_whitespace();
+            if let (Some(addr), Some(name)) = (parts.next().and_then(parse_addr), parts.next()) {
+                table.insert(addr, name);
+            }
+        }
+        table
+    }
+
+    pub fn insert(&mut self, addr: u32, name: &str) {
+        self.labels.insert(addr & 0xFFFFFF, name.to_string());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+
+    /// Returns the label of `addr`
+    ///
+    /// The first 8 KiB of WRAM and the I/O registers are mirrored in banks `$00-$3F` and `$80-$BF`,
+    /// so addresses below `$8000` in those banks also find labels defined in bank `$00`
+    pub fn get(&self, addr: u32) -> Option<&str> {
+        let addr = addr & 0xFFFFFF;
+        let (bank, hhll) = (addr >> 16, addr & 0xFFFF);
+        self.labels.get(&addr)
+            .or_else(|| match (bank & 0x7F) < 0x40 && hhll < 0x8000 {
+                true => self.labels.get(&hhll),
+                false => None,
+            })
+            .map(String::as_str)
+    }
+
+    /// Returns the address of the label `name`
+    pub fn find(&self, name: &str) -> Option<u32> {
+        self.labels.iter().find(|(_, label)| label.as_str() == name).map(|(addr, _)| *addr)
+    }
+}
+
+/// Parses a long address written as `BB:HHLL`, `$BBHHLL` or `BBHHLL`
+pub fn parse_addr(s: &str) -> Option<u32> {
+    let addr = match s.split_once(':') {
+        Some((bank, hhll)) if bank.len() == 2 && hhll.len() == 4 => {
+            (u32::from_str_radix(bank, 16).ok()? << 16) | u32::from_str_radix(hhll, 16).ok()?
+        },
+        Some(_) => return None,
+        None => {
+            let hex = s.strip_prefix('$').unwrap_or(s);
+            if hex.len() != 6 {
+                return None;
+            }
+            u32::from_str_radix(hex, 16).ok()?
+        },
+    };
+    Some(addr)
+}
@@ -3,14 +3,17 @@
 mod ppu;
 mod cpu;
 mod apu;
+mod region;
+mod savestate;
+mod console;
+mod disassembler;
+mod debugger;
 pub mod bit_macros;
 pub mod addr_macros;
 
-use apu::memory::ApuMemory;
+use std::ops::RangeInclusive;
 
-use ppu::memory::PpuMemory;
-
-use crate::{cpu::Cpu, ppu::Ppu, apu::Apu};
+use crate::{region::RegionSetting, console::Console, cpu::trace::Tracer, disassembler::{cli::DisasmArgs, symbols::parse_addr}, ppu::screenapp::{filter::VideoFilter, colorprofile::ColorProfile}};
 
 
 #[macro_export]
@@ -25,20 +28,153 @@ fn main() {
     // let rom = include_bytes!("../resources/rom/Legend of Zelda, The - A Link to the Past.smc");
     let rom = include_bytes!("../resources/rom/Super Mario World.smc");
 
-    let mut cpu = Cpu::new();
-    let mut ppu = Ppu::new();
-    let mut apu = Apu::new();
+    let mut console = Console::new();
+    let _ = console.insert_cartridge(rom);
+
+    // `snesemu disasm [options]` prints a disassembly of the ROM instead of running it
+    if std::env::args().nth(1).as_deref() == Some("disasm") {
+        disassembler::cli::run(DisasmArgs::parse(std::env::args().skip(2)), &console);
+        return;
+    }
+    // `snesemu debug [--symbols <path>]` runs the ROM in a debugger on the terminal
+    if std::env::args().nth(1).as_deref() == Some("debug") {
+        debugger::repl::run(std::env::args().skip(2), console);
+        return;
+    }
+    // `snesemu gdb [--port <n>]` waits for gdb to attach over TCP, see `GdbStub`
+    if std::env::args().nth(1).as_deref() == Some("gdb") {
+        debugger::gdb::run(std::env::args().skip(2), console);
+        return;
+    }
+
+    println!("{:#?}", console.cpu.memory.cartridge_metadata);
+
+    let args = Args::parse();
+    let region = args.region.resolve(console.cpu.memory.cartridge_metadata.get_region());
+    println!("Region: {:?}", region);
+    console.ppu.set_region(region);
+    console.ppu.set_video_filter(args.filter);
+    console.ppu.set_color_profile(args.color_profile);
+
+    if let Some(path) = &args.trace {
+        match Tracer::to_file(path) {
+            Ok(mut tracer) => {
+                tracer.set_pc_range(args.trace_pc.clone());
+                tracer.set_frames(args.trace_frames.clone());
+                console.cpu.set_tracer(Some(tracer));
+            },
+            Err(e) => eprintln!("Failed to create trace file: {e}"),
+        }
+    }
+
+    if let Some(path) = &args.load_state {
+        match std::fs::read(path).map_err(Into::into).and_then(|state| console.load_state(&state)) {
+            Ok(_) => println!("Loaded state from {path}"),
+            Err(e) => eprintln!("Failed to load state: {e}"),
+        }
+    }
 
-    let ppumem = arc_mut!(PpuMemory::new());
-    cpu.memory.set_ppumemory_ref(ppumem.clone());
-    ppu.set_ppumemory_ref(ppumem);
+    if args.headless {
+        if let Err(e) = console.run_headless(args.frames) {
+            eprintln!("CPU error: {e:?}");
+        }
+        if let Some(path) = &args.screenshot {
+            if let Err(e) = console.ppu.save_screenshot(path) {
+                eprintln!("Failed to save screenshot: {e}");
+            }
+        }
+        if let Some(path) = &args.save_state {
+            if let Err(e) = std::fs::write(path, console.save_state()) {
+                eprintln!("Failed to save state: {e}");
+            }
+        }
+        return;
+    }
 
-    let apumem = arc_mut!(ApuMemory::new());
-    cpu.memory.set_apumemory_ref(apumem.clone());
-    apu.set_apumemory_ref(apumem);
+    if args.rewind_budget_mib > 0 {
+        console.enable_rewind(args.rewind_interval, args.rewind_budget_mib << 20);
+    }
+    if let Err(e) = console.run() {
+        eprintln!("{e}");
+    }
+}
+
+/// Command line options
+/// 
+/// * `--headless`: run without a window
+/// * `--frames <n>`: number of frames to run in headless mode, defaults to 1
+/// * `--screenshot <path>`: save the last frame after running headless, as `.png`, `.ppm` or `.rgb`/`.raw`
+/// * `--filter <name>`: video filter, one of `none`, `ntsc`, `scanlines`, `scale2x`, `hq2x` or `xbr`
+/// * `--region <name>`: console region, one of `auto` (from the cartridge header), `ntsc` or `pal`
+/// * `--color <name>`: color profile, one of `raw`, `gamma`, `crt` or `lcd`
+/// * `--load-state <path>`: load a save state before running
+/// * `--save-state <path>`: save the state of the console after running headless
+/// * `--rewind-interval <n>`: capture a rewind snapshot every `n` frames, defaults to 1
+/// * `--rewind-budget <MiB>`: memory used for rewinding, defaults to 64, `0` disables rewinding
+/// * `--trace <path>`: log every executed instruction to a file
+/// * `--trace-pc <start>-<end>`: only log instructions in this range of long addresses, written as `BB:HHLL` or `$BBHHLL`
+/// * `--trace-frames <first>-<last>`: only log instructions in this range of frames, counting from 0
+struct Args {
+    headless: bool,
+    frames: usize,
+    screenshot: Option<String>,
+    filter: VideoFilter,
+    color_profile: ColorProfile,
+    region: RegionSetting,
+    load_state: Option<String>,
+    save_state: Option<String>,
+    rewind_interval: usize,
+    rewind_budget_mib: usize,
+    trace: Option<String>,
+    trace_pc: Option<RangeInclusive<u32>>,
+    trace_frames: Option<RangeInclusive<u64>>,
+}
 
-    let _ = cpu.memory.insert_cartridge(rom);
-    println!("{:#?}", cpu.memory.cartridge_metadata);
+impl Args {
+    fn parse() -> Args {
+        let mut args = Args { headless: false, frames: 1, screenshot: None, filter: VideoFilter::None, color_profile: ColorProfile::Raw, region: RegionSetting::Auto, load_state: None, save_state: None, rewind_interval: 1, rewind_budget_mib: 64, trace: None, trace_pc: None, trace_frames: None };
+
+        let mut iter = std::env::args().skip(1);
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--headless" => args.headless = true,
+                "--frames" => args.frames = iter.next().and_then(|n| n.parse().ok()).unwrap_or(args.frames),
+                "--screenshot" => args.screenshot = iter.next(),
+                "--load-state" => args.load_state = iter.next(),
+                "--save-state" => args.save_state = iter.next(),
+                "--rewind-interval" => args.rewind_interval = iter.next().and_then(|n| n.parse().ok()).unwrap_or(args.rewind_interval),
+                "--rewind-budget" => args.rewind_budget_mib = iter.next().and_then(|n| n.parse().ok()).unwrap_or(args.rewind_budget_mib),
+                "--trace" => args.trace = iter.next(),
+                "--trace-pc" => match iter.next().as_deref().and_then(|r| parse_range(r, parse_addr)) {
+                    Some(range) => args.trace_pc = Some(range),
+                    None => eprintln!("Invalid trace address range, expected <start>-<end> as BB:HHLL or $BBHHLL"),
+                },
+                "--trace-frames" => match iter.next().as_deref().and_then(|r| parse_range(r, |n| n.parse().ok())) {
+                    Some(range) => args.trace_frames = Some(range),
+                    None => eprintln!("Invalid trace frame range, expected <first>-<last>"),
+                },
+                "--region" => match iter.next().as_deref().and_then(RegionSetting::from_name) {
+                    Some(region) => args.region = region,
+                    None => eprintln!("Unknown region, expected auto, ntsc or pal"),
+                },
+                "--color" => match iter.next().as_deref().and_then(ColorProfile::from_name) {
+                    Some(profile) => args.color_profile = profile,
+                    None => eprintln!("Unknown color profile, expected raw, gamma, crt or lcd"),
+                },
+                "--filter" => match iter.next().as_deref().and_then(VideoFilter::from_name) {
+                    Some(filter) => args.filter = filter,
+                    None => eprintln!("Unknown video filter, expected none, ntsc, scanlines, scale2x, hq2x or xbr"),
+                },
+                _ => eprintln!("Unknown argument: {arg}"),
+            }
+        }
+
+        args
+    }
+}
 
-    ppu.run();
+/// Parses `<start>-<end>` into an inclusive range, using `parse` for both ends
+fn parse_range<T>(s: &str, parse: impl Fn(&str) -> Option<T>) -> Option<RangeInclusive<T>> {
+    let (start, end) = s.split_once('-')?;
+    Some(parse(start)?..=parse(end)?)
 }
@@ -1,4 +1,4 @@
-use crate::to_word;
+use crate::{to_word, savestate::{Snapshot, StateWriter, StateReader, SaveStateError}};
 
 use super::masklogic::MaskLogic;
 
@@ -111,3 +111,31 @@ impl Background {
         }
     }
 }
+
+impl Snapshot for Background {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u16(self.tilemap_vram_addr);
+        w.write_u8(self.vertical_tilemap_count);
+        w.write_u8(self.horizontal_tilemap_count);
+        w.write_u16(self.chr_base_addr);
+        w.write_u16(self.char_size);
+        w.write_u16(self.scroll_x);
+        w.write_u16(self.scroll_y);
+        w.write_bools(&[self.enable_main, self.enable_sub, self.mosaic]);
+        self.mask_logic.save_state(w);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), SaveStateError> {
+        self.tilemap_vram_addr = r.read_u16()?;
+        self.vertical_tilemap_count = r.read_u8()?;
+        self.horizontal_tilemap_count = r.read_u8()?;
+        self.chr_base_addr = r.read_u16()?;
+        self.char_size = r.read_u16()?;
+        self.scroll_x = r.read_u16()?;
+        self.scroll_y = r.read_u16()?;
+        let mut flags = [false; 3];
+        r.read_bools(&mut flags)?;
+        [self.enable_main, self.enable_sub, self.mosaic] = flags;
+        self.mask_logic.load_state(r)
+    }
+}
@@ -1,5 +1,6 @@
-use crate::{bit_set, bit_slice, ppu::{rgb::Rgba, layer::Layer}};
+use crate::{bit_set, bit_slice, ppu::{rgb::Rgba, layer::Layer}, savestate::{Snapshot, StateWriter, StateReader, SaveStateError}};
 
+#[derive(Clone, Copy)]
 enum Region {
     Enabled,
     Window,
@@ -7,11 +8,13 @@ enum Region {
     Disabled,
 }
 
+#[derive(Clone, Copy)]
 pub enum Addend {
     FixedColor,
     Subscreen,
 }
 
+#[derive(Clone, Copy)]
 enum OperatorType {
     /// Main + Sub
     Add,
@@ -212,4 +215,50 @@ impl ColorMath {
         }
     }
 
-}
\ No newline at end of file
+}
+impl Region {
+    fn from_index(i: u8) -> Region {
+        match i {
+            0 => Region::Enabled,
+            1 => Region::Window,
+            2 => Region::WindowInverted,
+            _ => Region::Disabled,
+        }
+    }
+}
+
+impl Snapshot for ColorMath {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u8(self.sub_transparent_region as u8);
+        w.write_u8(self.main_black_region as u8);
+        w.write_u8(self.operator_type as u8);
+        w.write_u8(self.addend as u8);
+        w.write_bools(&[self.direct_color_mode, self.obj_math_enable, self.backdrop_enable]);
+        w.write_bools(&self.bg_math_enable);
+        let (r, g, b, a) = self.fixed_color.as_rgba_tuple();
+        w.write_bytes(&[r, g, b, a]);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), SaveStateError> {
+        self.sub_transparent_region = Region::from_index(r.read_index(4, "color math region")?);
+        self.main_black_region = Region::from_index(r.read_index(4, "color math region")?);
+        self.operator_type = match r.read_index(4, "color math operator")? {
+            0 => OperatorType::Add,
+            1 => OperatorType::Sub,
+            2 => OperatorType::Average,
+            _ => OperatorType::SubHalf,
+        };
+        self.addend = match r.read_index(2, "color math addend")? {
+            0 => Addend::FixedColor,
+            _ => Addend::Subscreen,
+        };
+        let mut flags = [false; 3];
+        r.read_bools(&mut flags)?;
+        [self.direct_color_mode, self.obj_math_enable, self.backdrop_enable] = flags;
+        r.read_bools(&mut self.bg_math_enable)?;
+        let mut rgba = [0; 4];
+        r.read_bytes(&mut rgba)?;
+        self.fixed_color = Rgba::new(rgba[0], rgba[1], rgba[2], rgba[3]);
+        Ok(())
+    }
+}
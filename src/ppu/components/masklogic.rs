@@ -1,3 +1,5 @@
+use crate::savestate::{Snapshot, StateWriter, StateReader, SaveStateError};
+
 pub enum MaskLogic {
     Or,
     And,
@@ -26,4 +28,19 @@ impl MaskLogic {
             Self::Xnor => !(lhs ^ rhs),
         }
     }
-}
\ No newline at end of file
+}
+impl Snapshot for MaskLogic {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u8(match self {
+            Self::Or => 0b00,
+            Self::And => 0b01,
+            Self::Xor => 0b10,
+            Self::Xnor => 0b11,
+        });
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), SaveStateError> {
+        *self = Self::from_bits(r.read_index(4, "window mask logic")?);
+        Ok(())
+    }
+}
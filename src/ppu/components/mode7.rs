@@ -1,3 +1,5 @@
+use crate::savestate::{Snapshot, StateWriter, StateReader, SaveStateError};
+
 pub struct Mode7 {
     /// RF.. ..YX, tilemap repeat (R), fill (F), flip vertical (Y), flip horizontal (X)
     pub m7sel: u8,
@@ -63,4 +65,20 @@ impl Mode7 {
         self.vscroll = self.write_latch(byte);
     }
 
-}
\ No newline at end of file
+}
+impl Snapshot for Mode7 {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u8(self.m7sel);
+        w.write_words(&[self.a, self.b, self.c, self.d, self.x, self.y, self.hscroll, self.vscroll]);
+        w.write_u8(self.latch);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), SaveStateError> {
+        self.m7sel = r.read_u8()?;
+        let mut regs = [0; 8];
+        r.read_words(&mut regs)?;
+        [self.a, self.b, self.c, self.d, self.x, self.y, self.hscroll, self.vscroll] = regs;
+        self.latch = r.read_u8()?;
+        Ok(())
+    }
+}
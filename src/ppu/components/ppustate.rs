@@ -1,5 +1,10 @@
 use crate::{bit_set, bit_slice, fv_blanking};
 
+use crate::{ppu::{F_BLANK, rgb::Rgba}, region::Region, savestate::{Snapshot, StateWriter, StateReader, SaveStateError}};
+
+/// Version number of PPU2 in STAT78
+const PPU2_VERSION: u8 = 3;
+
 use super::masklogic::MaskLogic;
 
 
@@ -27,6 +32,9 @@ pub struct PpuState {
 
     pub bg3_prio: bool,
 
+    /// Region of the console, visible to games through STAT78
+    pub region: Region,
+
     /// Background size per background.
     /// 
     /// False: 8x8, true: 16x16
@@ -35,6 +43,19 @@ pub struct PpuState {
     pub window_obj_masklogic: MaskLogic,
     pub window_clr_masklogic: MaskLogic,
 
+    /// Position of the beam in dots and scanlines, kept up to date by the PPU so reading SLHV can latch it
+    pub beam_x: u16,
+    pub beam_y: u16,
+    /// Latched horizontal counter, read through OPHCT
+    pub ophct: u16,
+    /// Latched vertical counter, read through OPVCT
+    pub opvct: u16,
+    /// Set when the counters are latched, read through STAT78
+    pub counter_latched: bool,
+    /// Flip-flop selecting the high byte for the next read of OPHCT
+    ophct_high: bool,
+    /// Flip-flop selecting the high byte for the next read of OPVCT
+    opvct_high: bool,
 }
 
 impl PpuState {
@@ -55,17 +76,36 @@ impl PpuState {
             enable_obj_sub: false,
             background_mode: 0,
             bg3_prio: false,
+            region: Region::Ntsc,
             bg_size: [false; 4],
             window_obj_masklogic: MaskLogic::from_bits(0),
             window_clr_masklogic: MaskLogic::from_bits(0),
+            beam_x: 0,
+            beam_y: 0,
+            ophct: 0,
+            opvct: 0,
+            counter_latched: false,
+            ophct_high: false,
+            opvct_high: false,
         }
     }
 
     /// Write to `$2100`
+    /// 
+    /// `F... BBBB`, force blank (F), master brightness (B). 
+    /// Unlike most registers this can be written at any time, games use it to fade the screen in and out
     pub fn write_inidisp(&mut self, byte: u8) {
-        if fv_blanking!() {
-            self.force_blank = bit_set!(byte, 7);
-            self.brightness = bit_slice!(byte, 0, 3);
+        self.force_blank = bit_set!(byte, 7);
+        self.brightness = bit_slice!(byte, 0, 3);
+        *F_BLANK.write().unwrap() = self.force_blank;
+    }
+
+    /// Final color of a pixel, force blank outputs black, otherwise `color` is scaled by master brightness
+    pub fn apply_inidisp(&self, color: Rgba) -> Rgba {
+        if self.force_blank {
+            Rgba::BLACK
+        } else {
+            color.apply_brightness(self.brightness)
         }
     }
 
@@ -103,12 +143,60 @@ impl PpuState {
     }
 
     /// Write to `$2133`
+    /// 
+    /// `EX.. HOIi`, external sync (E), EXTBG (X), pseudo hires (H), overscan (O), obj interlace (I), screen interlace (i)
     pub fn write_inisel(&mut self, byte: u8) {
         if fv_blanking!() {
-            todo!();
+            self.ph512_mode = bit_set!(byte, 3);
+            self.overscan_enabled = bit_set!(byte, 2);
+            self.obj_vertical_mode = bit_set!(byte, 1);
+            self.interlace = bit_set!(byte, 0);
         }
     }
 
+    /// Called by the PPU after every dot
+    pub fn set_beam_position(&mut self, x: usize, y: usize) {
+        self.beam_x = x as u16;
+        self.beam_y = y as u16;
+    }
+
+    /// Read SLHV (`$2137`), latches the position of the beam into OPHCT and OPVCT
+    pub fn latch_counters(&mut self) {
+        self.ophct = self.beam_x;
+        self.opvct = self.beam_y;
+        self.counter_latched = true;
+    }
+
+    /// Read OPHCT (`$213C`), the low byte first and the high bit on the next read
+    pub fn read_ophct(&mut self) -> u8 {
+        let byte = if self.ophct_high { bit_slice!(self.ophct, 8, 8) as u8 } else { self.ophct as u8 };
+        self.ophct_high = !self.ophct_high;
+        byte
+    }
+
+    /// Read OPVCT (`$213D`), the low byte first and the high bit on the next read
+    pub fn read_opvct(&mut self) -> u8 {
+        let byte = if self.opvct_high { bit_slice!(self.opvct, 8, 8) as u8 } else { self.opvct as u8 };
+        self.opvct_high = !self.opvct_high;
+        byte
+    }
+
+    /// Value of STAT78 (`$213F`) without the side effects of reading it
+    /// 
+    /// `LI.P vvvv`, interlace field (L), counter latched (I), PAL (P), PPU2 version (v)
+    pub fn stat78(&self) -> u8 {
+        (self.counter_latched as u8) << 6 | ((self.region == Region::Pal) as u8) << 4 | PPU2_VERSION
+    }
+
+    /// Read STAT78 (`$213F`), this resets the OPHCT and OPVCT flip-flops and the counter latched flag
+    pub fn read_stat78(&mut self) -> u8 {
+        let byte = self.stat78();
+        self.ophct_high = false;
+        self.opvct_high = false;
+        self.counter_latched = false;
+        byte
+    }
+
     /// Write to `$212E`
     pub fn write_tmw(&mut self, byte: u8) {
         if fv_blanking!() {
@@ -133,4 +221,117 @@ impl PpuState {
         self.window_obj_masklogic = MaskLogic::from_bits(bit_slice!(byte, 0, 1));
         self.window_clr_masklogic = MaskLogic::from_bits(bit_slice!(byte, 2, 3));
     }
-}
\ No newline at end of file
+}
+impl Snapshot for PpuState {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_bool(self.force_blank);
+        w.write_u8(self.brightness);
+        w.write_usize(self.mosaic_size);
+        w.write_bools(&[self.enable_obj_main, self.enable_obj_sub]);
+        w.write_bools(&self.enable_window_bg_main);
+        w.write_bools(&self.enable_window_bg_sub);
+        w.write_bools(&[
+            self.enabled_window_obj_main,
+            self.enabled_window_obj_sub,
+            self.overscan_enabled,
+            self.ph512_mode,
+            self.interlace,
+            self.obj_vertical_mode,
+        ]);
+        w.write_usize(self.background_mode);
+        w.write_bool(self.bg3_prio);
+        self.region.save_state(w);
+        w.write_bools(&self.bg_size);
+        self.window_obj_masklogic.save_state(w);
+        self.window_clr_masklogic.save_state(w);
+        w.write_u16(self.beam_x);
+        w.write_u16(self.beam_y);
+        w.write_u16(self.ophct);
+        w.write_u16(self.opvct);
+        w.write_bools(&[self.counter_latched, self.ophct_high, self.opvct_high]);
+    }
+
+    /// Also restores the global F-blank flag, since it follows `force_blank`
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), SaveStateError> {
+        self.force_blank = r.read_bool()?;
+        *F_BLANK.write().unwrap() = self.force_blank;
+        self.brightness = r.read_u8()? & 0x0F;
+        self.mosaic_size = r.read_usize()?;
+        let mut obj = [false; 2];
+        r.read_bools(&mut obj)?;
+        [self.enable_obj_main, self.enable_obj_sub] = obj;
+        r.read_bools(&mut self.enable_window_bg_main)?;
+        r.read_bools(&mut self.enable_window_bg_sub)?;
+        let mut flags = [false; 6];
+        r.read_bools(&mut flags)?;
+        [
+            self.enabled_window_obj_main,
+            self.enabled_window_obj_sub,
+            self.overscan_enabled,
+            self.ph512_mode,
+            self.interlace,
+            self.obj_vertical_mode,
+        ] = flags;
+        self.background_mode = match r.read_usize()? {
+            mode @ 0..=7 => mode,
+            _ => return Err(SaveStateError::InvalidValue("background mode")),
+        };
+        self.bg3_prio = r.read_bool()?;
+        self.region.load_state(r)?;
+        r.read_bools(&mut self.bg_size)?;
+        self.window_obj_masklogic.load_state(r)?;
+        self.window_clr_masklogic.load_state(r)?;
+        self.beam_x = r.read_u16()?;
+        self.beam_y = r.read_u16()?;
+        self.ophct = r.read_u16()? & 0x1FF;
+        self.opvct = r.read_u16()? & 0x1FF;
+        let mut counter = [false; 3];
+        r.read_bools(&mut counter)?;
+        [self.counter_latched, self.ophct_high, self.opvct_high] = counter;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_force_blank_outputs_black() {
+        let mut state = PpuState::new();
+        let color = Rgba::new(31, 16, 2, 1);
+        state.brightness = 15;
+        assert_eq!(state.apply_inidisp(color), color);
+
+        state.force_blank = true;
+        assert_eq!(state.apply_inidisp(color), Rgba::BLACK);
+    }
+
+    #[test]
+    fn test_latch_counters() {
+        let mut state = PpuState::new();
+        state.set_beam_position(0x123, 0x0AB);
+        assert_eq!(state.stat78() & 0x40, 0);
+
+        state.latch_counters();
+        state.set_beam_position(0, 0);
+        assert_eq!(state.stat78() & 0x40, 0x40);
+        assert_eq!([state.read_ophct(), state.read_ophct()], [0x23, 0x01]);
+        assert_eq!([state.read_opvct(), state.read_opvct()], [0xAB, 0x00]);
+    }
+
+    #[test]
+    fn test_stat78_read_resets_flip_flops_and_latch_flag() {
+        let mut state = PpuState::new();
+        state.set_beam_position(0x123, 0x0AB);
+        state.latch_counters();
+        state.read_ophct();
+        state.read_opvct();
+
+        assert_eq!(state.read_stat78() & 0x40, 0x40);
+        assert_eq!(state.stat78() & 0x40, 0);
+        // The next reads start with the low byte again
+        assert_eq!(state.read_ophct(), 0x23);
+        assert_eq!(state.read_opvct(), 0xAB);
+    }
+}
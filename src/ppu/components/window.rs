@@ -1,4 +1,4 @@
-use crate::{nth_bit, bit_set};
+use crate::{nth_bit, bit_set, savestate::{Snapshot, StateWriter, StateReader, SaveStateError}};
 
 pub struct Window {
     pub left: u8, // $2126 / $2127 for W1 / W2
@@ -36,8 +36,8 @@ impl Window {
     pub fn write_12sel(&mut self, byte: u8) {
         self.bg_inverted[0] = bit_set!(byte, 0);
         self.bg_enabled[0] = bit_set!(byte, 1);
-        self.bg_enabled[1] = bit_set!(byte, 4);
-        self.bg_inverted[1] = bit_set!(byte, 5);
+        self.bg_inverted[1] = bit_set!(byte, 4);
+        self.bg_enabled[1] = bit_set!(byte, 5);
     }
 
     /// Reads bits marked by x: `--xx --xx`
@@ -46,12 +46,18 @@ impl Window {
     pub fn write_34sel(&mut self, byte: u8) {
         self.bg_inverted[2] = bit_set!(byte, 0);
         self.bg_enabled[2] = bit_set!(byte, 1);
-        self.bg_enabled[3] = bit_set!(byte, 4);
-        self.bg_inverted[4] = bit_set!(byte, 5);
+        self.bg_inverted[3] = bit_set!(byte, 4);
+        self.bg_enabled[3] = bit_set!(byte, 5);
     }
 
+    /// Reads bits marked by x: `--xx --xx`, the obj bits at the bottom and the color bits at the top
+    /// 
+    /// Window 2 must have `byte` shifted by two to the right
     pub fn write_objsel(&mut self, byte: u8) {
-        todo!()
+        self.obj_inverted = bit_set!(byte, 0);
+        self.obj_enabled = bit_set!(byte, 1);
+        self.clr_inverted = bit_set!(byte, 4);
+        self.clr_enabled = bit_set!(byte, 5);
     }
 
     pub fn write_left_pos(&mut self, byte: u8) {
@@ -61,4 +67,49 @@ impl Window {
     pub fn write_right_pos(&mut self, byte: u8) {
         self.right = byte;
     }
-}
\ No newline at end of file
+}
+impl Snapshot for Window {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u8(self.left);
+        w.write_u8(self.right);
+        w.write_bools(&self.bg_enabled);
+        w.write_bools(&self.bg_inverted);
+        w.write_bools(&[self.obj_enabled, self.obj_inverted, self.clr_enabled, self.clr_inverted]);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), SaveStateError> {
+        self.left = r.read_u8()?;
+        self.right = r.read_u8()?;
+        r.read_bools(&mut self.bg_enabled)?;
+        r.read_bools(&mut self.bg_inverted)?;
+        let mut flags = [false; 4];
+        r.read_bools(&mut flags)?;
+        [self.obj_enabled, self.obj_inverted, self.clr_enabled, self.clr_inverted] = flags;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ppu::memory::PpuMemory;
+
+    #[test]
+    fn test_write_12sel() {
+        let mut mem = PpuMemory::new();
+        // W1 inverted for BG1, W2 enabled for BG1, W1 enabled for BG2
+        mem.write(0x2123, 0b0010_1001);
+
+        assert_eq!((mem.w1.bg_enabled, mem.w1.bg_inverted), ([false, true, false, false], [true, false, false, false]));
+        assert_eq!((mem.w2.bg_enabled, mem.w2.bg_inverted), ([true, false, false, false], [false; 4]));
+    }
+
+    #[test]
+    fn test_write_34sel() {
+        let mut mem = PpuMemory::new();
+        // W1 enabled for BG3, W1 inverted for BG4, W2 enabled and inverted for BG4
+        mem.write(0x2124, 0b1101_0010);
+
+        assert_eq!((mem.w1.bg_enabled, mem.w1.bg_inverted), ([false, false, true, false], [false, false, false, true]));
+        assert_eq!((mem.w2.bg_enabled, mem.w2.bg_inverted), ([false, false, false, true], [false, false, false, true]));
+    }
+}
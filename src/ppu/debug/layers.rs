@@ -0,0 +1,121 @@
+use crate::ppu::{Ppu, rgb::Rgba, layer::LayerStruct, SCREEN_WIDTH, PAL_SCREEN_HEIGHT};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Layers that can be toggled and inspected separately
+pub enum DebugLayer {
+    Bg1,
+    Bg2,
+    Bg3,
+    Bg4,
+    Obj,
+}
+
+impl DebugLayer {
+    pub const ALL: [DebugLayer; 5] = [DebugLayer::Bg1, DebugLayer::Bg2, DebugLayer::Bg3, DebugLayer::Bg4, DebugLayer::Obj];
+
+    fn index(&self) -> usize {
+        match self {
+            DebugLayer::Bg1 => 0,
+            DebugLayer::Bg2 => 1,
+            DebugLayer::Bg3 => 2,
+            DebugLayer::Bg4 => 3,
+            DebugLayer::Obj => 4,
+        }
+    }
+}
+
+/// Runtime switches to hide layers and disable color math.
+/// 
+/// These are applied on top of the PPU registers, so the game still reads back the values it wrote
+pub struct LayerToggles {
+    layers: [bool; 5],
+    pub color_math: bool,
+}
+
+impl LayerToggles {
+    pub fn new() -> LayerToggles {
+        LayerToggles {
+            layers: [true; 5],
+            color_math: true,
+        }
+    }
+
+    pub fn set_enabled(&mut self, layer: DebugLayer, enabled: bool) {
+        self.layers[layer.index()] = enabled;
+    }
+
+    pub fn is_enabled(&self, layer: DebugLayer) -> bool {
+        self.layers[layer.index()]
+    }
+
+    /// Clears the colors of all disabled layers in `layers`
+    pub fn apply(&self, layers: &mut LayerStruct) {
+        layers.set_bg1_enabled(self.layers[0], false, false);
+        layers.set_bg2_enabled(self.layers[1], false, false);
+        layers.set_bg3_enabled(self.layers[2], false, false);
+        layers.set_bg4_enabled(self.layers[3], false, false);
+        layers.set_obj_enabled(self.layers[4], false, false);
+    }
+}
+
+/// Output of every layer for every pixel of the frame, 
+/// stored before windows, main/sub screen selection and color math are applied
+pub struct LayerCapture {
+    buffers: [Vec<Rgba>; 5],
+}
+
+impl LayerCapture {
+    pub fn new() -> LayerCapture {
+        LayerCapture {
+            buffers: std::array::from_fn(|_| vec![Rgba::default(); SCREEN_WIDTH * PAL_SCREEN_HEIGHT]),
+        }
+    }
+
+    /// Store color of each layer for pixel `x`, `y`
+    pub fn store(&mut self, x: usize, y: usize, layers: &LayerStruct) {
+        let i = y * SCREEN_WIDTH + x;
+
+        // Only one of the low and high priority colors can be non transparent
+        let pick = |high: Rgba, low: Rgba| if high != Rgba::default() { high } else { low };
+
+        self.buffers[0][i] = pick(layers.bg1high, layers.bg1low);
+        self.buffers[1][i] = pick(layers.bg2high, layers.bg2low);
+        self.buffers[2][i] = pick(layers.bg3high, layers.bg3low);
+        self.buffers[3][i] = pick(layers.bg4high, layers.bg4low);
+        self.buffers[4][i] = [layers.sprite3, layers.sprite2, layers.sprite1, layers.sprite0]
+            .into_iter()
+            .find(|c| *c != Rgba::default())
+            .unwrap_or_default();
+    }
+
+    /// Returns pixels of `layer`, row by row
+    pub fn get(&self, layer: DebugLayer) -> &[Rgba] {
+        &self.buffers[layer.index()]
+    }
+}
+
+impl Ppu {
+    /// Show or hide `layer` on screen
+    pub fn set_layer_enabled(&mut self, layer: DebugLayer, enabled: bool) {
+        self.layer_toggles.set_enabled(layer, enabled);
+    }
+
+    /// Enable or disable color math, when disabled the main screen is drawn as is
+    pub fn set_color_math_enabled(&mut self, enabled: bool) {
+        self.layer_toggles.color_math = enabled;
+    }
+
+    /// Start or stop storing the output of each layer separately while drawing
+    pub fn set_layer_capture(&mut self, enabled: bool) {
+        self.layer_capture = match enabled {
+            true => Some(LayerCapture::new()),
+            false => None,
+        };
+    }
+
+    /// Returns the rendered output of `layer` for the current frame,
+    /// or `None` if layer capture is not enabled
+    pub fn get_layer_output(&self, layer: DebugLayer) -> Option<&[Rgba]> {
+        self.layer_capture.as_ref().map(|capture| capture.get(layer))
+    }
+}
@@ -0,0 +1,20 @@
+pub mod layers;
+pub mod window;
+pub mod tileviewer;
+pub mod tilemapviewer;
+pub mod spriteviewer;
+pub mod paletteviewer;
+
+use pix_engine::prelude::{Image, PixelFormat};
+
+use super::rgb::Rgba;
+
+/// Converts `pixels` to an 8 bit RGBA image that is `width` pixels wide, all pixels are made opaque
+pub fn image_from_pixels(width: usize, height: usize, pixels: &[Rgba]) -> Image {
+    let bytes = pixels.iter().flat_map(|rgba| {
+        let (r, g, b, _) = rgba.as_highrange_rgba_tuple();
+        [r, g, b, 0xFF]
+    }).collect::<Vec<u8>>();
+
+    Image::from_vec(width as u32, height as u32, bytes, PixelFormat::Rgba)
+}
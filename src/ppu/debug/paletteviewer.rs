@@ -0,0 +1,135 @@
+use std::sync::{Arc, Mutex};
+
+use pix_engine::prelude::{Engine, Image, Key, KeyEvent, KeyMod, Mouse, PixEngine, PixResult, PixState, Point};
+
+use crate::ppu::{Ppu, rgb::Rgba, memory::PpuMemory};
+
+use super::image_from_pixels;
+
+/// Number of CGRAM entries, shown as 16 rows of 16 swatches
+const CGRAM_ENTRIES: usize = 0x100;
+/// Width and height of a single swatch in pixels
+const SWATCH_SIZE: usize = 16;
+/// Width and height of the palette viewer image in pixels
+const VIEWER_SIZE: usize = 16 * SWATCH_SIZE;
+
+impl Ppu {
+    /// Returns CGRAM entry `index` as the raw BGR555 word and the color it is converted to
+    pub fn get_cgram_entry(&self, index: u8) -> (u16, Rgba) {
+        let raw = self.memory.lock().unwrap().cgram.read(index as u16);
+        (raw, Rgba::from_snes_palette(raw))
+    }
+
+    /// Overwrites CGRAM entry `index` with the BGR555 word `word`, this is visible from the next pixel that uses it
+    pub fn set_cgram_entry(&self, index: u8, word: u16) {
+        self.memory.lock().unwrap().cgram.write(index as u16, word);
+    }
+
+    /// Draws all 256 CGRAM entries as 16x16 swatches, each row is a single 16 color palette
+    pub fn render_palette(&self) -> Image {
+        render_cgram(&self.memory.lock().unwrap(), None)
+    }
+
+    /// Opens the palette editor, blocks until the window is closed
+    ///
+    /// Click on a swatch to select it, the raw word and converted color are shown in the window title.
+    /// `R`, `G` and `B` increase the red, green and blue channel of the selected entry, holding shift decreases them.
+    /// Edits are written to CGRAM immediately, so a running game shows them on its next frame
+    pub fn show_palette_editor(&self) -> PixResult<()> {
+        let mut engine = Engine::builder()
+            .dimensions(VIEWER_SIZE as u32, VIEWER_SIZE as u32)
+            .scale(2.0, 2.0)
+            .title("CGRAM")
+            .build()?;
+
+        engine.run(&mut PaletteEditor { memory: self.memory.clone(), selected: 0 })
+    }
+}
+
+/// Draws CGRAM as 16x16 swatches, `selected` gets a white outline
+fn render_cgram(mem: &PpuMemory, selected: Option<usize>) -> Image {
+    let mut pixels = vec![Rgba::default(); VIEWER_SIZE * VIEWER_SIZE];
+
+    for y in 0..VIEWER_SIZE {
+        for x in 0..VIEWER_SIZE {
+            let index = (y / SWATCH_SIZE) * 16 + x / SWATCH_SIZE;
+            let on_edge = [x, y].iter().any(|c| c % SWATCH_SIZE == 0 || c % SWATCH_SIZE == SWATCH_SIZE - 1);
+
+            pixels[y * VIEWER_SIZE + x] = if selected == Some(index) && on_edge {
+                Rgba::WHITE
+            } else {
+                Rgba::from_snes_palette(mem.cgram.read(index as u16))
+            };
+        }
+    }
+
+    image_from_pixels(VIEWER_SIZE, VIEWER_SIZE, &pixels)
+}
+
+/// Debug window for viewing and editing CGRAM while the emulator is running
+struct PaletteEditor {
+    memory: Arc<Mutex<PpuMemory>>,
+    selected: usize,
+}
+
+impl PaletteEditor {
+    fn update_title(&self, s: &mut PixState) -> PixResult<()> {
+        let raw = self.memory.lock().unwrap().cgram.read(self.selected as u16);
+        let (r, g, b) = Rgba::from_snes_palette(raw).as_rgb_tuple();
+        s.set_title(format!(
+            "CGRAM ${:02X} (palette {}, color {}) raw: ${:04X} r: {} g: {} b: {}",
+            self.selected, self.selected / 16, self.selected % 16, raw, r, g, b,
+        ))
+    }
+}
+
+impl PixEngine for PaletteEditor {
+    fn on_start(&mut self, s: &mut PixState) -> PixResult<()> {
+        self.update_title(s)
+    }
+
+    fn on_update(&mut self, s: &mut PixState) -> PixResult<()> {
+        // Redrawn every frame, since the game can change CGRAM as well
+        let image = render_cgram(&self.memory.lock().unwrap(), Some(self.selected));
+        s.image(&image, [0, 0])
+    }
+
+    fn on_mouse_pressed(&mut self, s: &mut PixState, btn: Mouse, pos: Point<i32>) -> PixResult<bool> {
+        if btn != Mouse::Left || pos.x() < 0 || pos.y() < 0 {
+            return Ok(false);
+        }
+
+        let index = (pos.y() as usize / SWATCH_SIZE) * 16 + pos.x() as usize / SWATCH_SIZE;
+        if index >= CGRAM_ENTRIES {
+            return Ok(false);
+        }
+
+        self.selected = index;
+        self.update_title(s)?;
+        Ok(true)
+    }
+
+    fn on_key_pressed(&mut self, s: &mut PixState, event: KeyEvent) -> PixResult<bool> {
+        let step = if event.keymod.intersects(KeyMod::SHIFT) { Rgba::MAX_RGB_VALUE } else { 1 };
+
+        {
+            let mut mem = self.memory.lock().unwrap();
+            let mut color = Rgba::from_snes_palette(mem.cgram.read(self.selected as u16));
+            let (r, g, b) = color.as_rgb_tuple();
+
+            // Channels wrap around, adding 31 is the same as subtracting 1
+            let wrap = |c: u8| (c + step) % (Rgba::MAX_RGB_VALUE + 1);
+            match event.key {
+                Key::R => color.set_r(wrap(r)),
+                Key::G => color.set_g(wrap(g)),
+                Key::B => color.set_b(wrap(b)),
+                _ => return Ok(false),
+            }
+
+            mem.cgram.write(self.selected as u16, color.to_snes_palette());
+        }
+
+        self.update_title(s)?;
+        Ok(true)
+    }
+}
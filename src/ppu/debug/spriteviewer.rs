@@ -0,0 +1,167 @@
+use std::fmt;
+
+use pix_engine::prelude::{Engine, Image, PixEngine, PixResult, PixState};
+
+use crate::ppu::{Ppu, rgb::Rgba, sprite::Sprite, spriteeval::{sprite_in_range, onscreen_slivers}};
+
+use super::image_from_pixels;
+
+/// Number of sprites on a single row of the sprite viewer image
+const SPRITES_PER_ROW: usize = 16;
+/// Size of the cell every sprite is drawn in, large enough for the biggest sprite size
+const CELL_SIZE: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// State of a sprite on the current scanline
+pub enum SpriteLineStatus {
+    /// Sprite is not on the current scanline
+    OffLine,
+    /// Sprite is on the current scanline and all of its on screen slivers are drawn
+    Drawn,
+    /// Sprite is on the current scanline, but some of its slivers were dropped because of the 34 sliver limit
+    TimeOver,
+    /// Sprite is on the current scanline, but was dropped because of the 32 sprite limit
+    RangeOver,
+}
+
+impl SpriteLineStatus {
+    /// Color of the border around this sprite in the sprite viewer
+    fn border_color(&self) -> Rgba {
+        match self {
+            SpriteLineStatus::OffLine => Rgba::default(),
+            SpriteLineStatus::Drawn => Rgba::GREEN,
+            SpriteLineStatus::TimeOver => Rgba::new(Rgba::MAX_RGB_VALUE, Rgba::MAX_RGB_VALUE, 0, 1),
+            SpriteLineStatus::RangeOver => Rgba::RED,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Decoded OAM entry together with its size and status on the current scanline
+pub struct SpriteInfo {
+    pub sprite: Sprite,
+    pub width: usize,
+    pub height: usize,
+    pub status: SpriteLineStatus,
+}
+
+impl fmt::Display for SpriteInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = &self.sprite;
+        write!(
+            f,
+            "#{:3} ({:4}, {:3}) {}x{} tile: ${:03X} palette: {} prio: {} hflip: {} vflip: {} {:?}",
+            s.index, s.signed_x(), s.y, self.width, self.height, s.tile_index,
+            s.palette, s.priority, s.flip_h as u8, s.flip_v as u8, self.status,
+        )
+    }
+}
+
+impl Ppu {
+    /// Returns all 128 sprites in OAM order, with their status on the current scanline
+    pub fn get_sprite_info(&self) -> Vec<SpriteInfo> {
+        let mem = self.memory.lock().unwrap();
+        let mut sprites = mem.oam.as_sprites();
+        sprites.sort_by_key(|s| s.index);
+
+        sprites.into_iter().map(|sprite| {
+            let (width, height) = mem.oam.get_sprite_size(sprite.big_size);
+            let line_sprite = self.line_sprites.iter().find(|ls| ls.sprite.index == sprite.index);
+
+            let status = match line_sprite {
+                Some(ls) if ls.loaded_slivers == onscreen_slivers(&sprite, width) => SpriteLineStatus::Drawn,
+                Some(_) => SpriteLineStatus::TimeOver,
+                None if sprite_in_range(&sprite, width, height, self.scanline.y) => SpriteLineStatus::RangeOver,
+                None => SpriteLineStatus::OffLine,
+            };
+
+            SpriteInfo { sprite, width, height, status }
+        }).collect()
+    }
+
+    /// Returns the pixels of sprite `s`, the image is the size of the sprite
+    pub fn render_sprite(&self, s: &Sprite) -> Image {
+        let mem = self.memory.lock().unwrap();
+        let (width, height) = mem.oam.get_sprite_size(s.big_size);
+
+        let pixels = (0..width * height)
+            .map(|i| self.get_sprite_color(&mem, s, i % width, i / width))
+            .collect::<Vec<Rgba>>();
+
+        image_from_pixels(width, height, &pixels)
+    }
+
+    /// Draws all 128 sprites in OAM order, 16 per row, each in a 64x64 cell
+    ///
+    /// Sprites on the current scanline get a green border, sprites that lost slivers to the time limit a yellow one
+    /// and sprites that were dropped by the range limit a red one
+    pub fn render_oam_sprites(&self) -> Image {
+        let sprites = self.get_sprite_info();
+        let mem = self.memory.lock().unwrap();
+
+        let width = SPRITES_PER_ROW * CELL_SIZE;
+        let height = (sprites.len() / SPRITES_PER_ROW) * CELL_SIZE;
+        let mut pixels = vec![Rgba::default(); width * height];
+
+        for (i, info) in sprites.iter().enumerate() {
+            let cell_x = (i % SPRITES_PER_ROW) * CELL_SIZE;
+            let cell_y = (i / SPRITES_PER_ROW) * CELL_SIZE;
+
+            for y in 0..info.height {
+                for x in 0..info.width {
+                    pixels[(cell_y + y) * width + cell_x + x] = self.get_sprite_color(&mem, &info.sprite, x, y);
+                }
+            }
+
+            let border = info.status.border_color();
+            for d in 0..CELL_SIZE {
+                pixels[cell_y * width + cell_x + d] = border;
+                pixels[(cell_y + CELL_SIZE - 1) * width + cell_x + d] = border;
+                pixels[(cell_y + d) * width + cell_x] = border;
+                pixels[(cell_y + d) * width + cell_x + CELL_SIZE - 1] = border;
+            }
+        }
+
+        image_from_pixels(width, height, &pixels)
+    }
+
+    /// Opens a debug window that shows the output of `render_oam_sprites`,
+    /// the attributes of the sprite under the mouse cursor are shown in the window title
+    pub fn show_oam_sprites(&self) -> PixResult<()> {
+        let image = self.render_oam_sprites();
+        let sprites = self.get_sprite_info();
+
+        let mut engine = Engine::builder()
+            .dimensions(image.width(), image.height())
+            .title("OAM sprites")
+            .build()?;
+
+        engine.run(&mut SpriteWindow { image, sprites, hovered: None })
+    }
+}
+
+/// Debug window that shows all sprites and reports the sprite under the mouse cursor
+struct SpriteWindow {
+    image: Image,
+    sprites: Vec<SpriteInfo>,
+    hovered: Option<usize>,
+}
+
+impl PixEngine for SpriteWindow {
+    fn on_update(&mut self, s: &mut PixState) -> PixResult<()> {
+        s.image(&self.image, [0, 0])?;
+
+        let mouse = s.mouse_pos();
+        if mouse.x() < 0 || mouse.y() < 0 {
+            return Ok(());
+        }
+
+        let i = (mouse.y() as usize / CELL_SIZE) * SPRITES_PER_ROW + mouse.x() as usize / CELL_SIZE;
+        if self.hovered == Some(i) || i >= self.sprites.len() {
+            return Ok(());
+        }
+
+        self.hovered = Some(i);
+        s.set_title(self.sprites[i].to_string())
+    }
+}
@@ -0,0 +1,148 @@
+use std::io;
+
+use pix_engine::prelude::{Engine, Image, PixEngine, PixResult, PixState};
+
+use crate::ppu::{Ppu, rgb::Rgba, tile::Tile, SCREEN_WIDTH};
+
+use super::image_from_pixels;
+
+/// Color of the outline that marks the visible part of the tilemap
+const VIEWPORT_COLOR: Rgba = Rgba::RED;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Information about a single tilemap entry of a background
+pub struct TileInfo {
+    /// Tile position on the tilemap
+    pub tx: usize,
+    pub ty: usize,
+    /// VRAM address of the tilemap entry
+    pub vram_addr: u16,
+    pub tile_num: u16,
+    pub palette: u8,
+    pub prio: bool,
+    pub h_flip: bool,
+    pub v_flip: bool,
+}
+
+impl Ppu {
+    /// Returns tilemap entry `tx`, `ty` of background `bg_index` (`0` to `3` for BG1 to BG4), `None` for any other index
+    ///
+    /// Tile coordinates wrap around at the edge of the tilemap, like they do when drawing
+    pub fn get_tile_info(&self, bg_index: usize, tx: usize, ty: usize) -> Option<TileInfo> {
+        let mem = self.memory.lock().unwrap();
+        let bg = mem.get_bg(bg_index)?;
+
+        let vram_addr = self.get_tilemap_addr(bg, tx, ty);
+        let tile = Tile::new(mem.vram.read(vram_addr));
+
+        Some(TileInfo {
+            tx,
+            ty,
+            vram_addr,
+            tile_num: tile.tile_num,
+            palette: tile.palette,
+            prio: tile.prio,
+            h_flip: tile.h_flip,
+            v_flip: tile.v_flip,
+        })
+    }
+
+    /// Returns size of the whole tilemap of background `bg_index` in tiles, `None` if `bg_index` is not `0` to `3`
+    pub fn get_tilemap_size(&self, bg_index: usize) -> Option<(usize, usize)> {
+        let mem = self.memory.lock().unwrap();
+        let bg = mem.get_bg(bg_index)?;
+        Some((32 << bg.horizontal_tilemap_count, 32 << bg.vertical_tilemap_count))
+    }
+
+    /// Draws the whole tilemap of background `bg_index` (`0` to `3` for BG1 to BG4) using the current background mode,
+    /// `None` for any other index
+    ///
+    /// Scroll and offset-per-tile are ignored, instead the part of the tilemap that is visible on screen
+    /// is outlined, the outline wraps around the edges just like the background does
+    pub fn render_tilemap(&self, bg_index: usize) -> Option<Image> {
+        let mem = self.memory.lock().unwrap();
+        let bg = mem.get_bg(bg_index)?;
+
+        let char_size = bg.char_size as usize;
+        let width = (32 << bg.horizontal_tilemap_count) * char_size;
+        let height = (32 << bg.vertical_tilemap_count) * char_size;
+        let mut pixels = vec![Rgba::default(); width * height];
+
+        for by in 0..height {
+            for bx in 0..width {
+                let tile = Tile::new(mem.vram.read(self.get_tilemap_addr(bg, bx / char_size, by / char_size)));
+                let (x, y) = self.get_tile_xy(bg, tile.h_flip, tile.v_flip, bx, by);
+                pixels[by * width + bx] = self.get_bg_char_color(&mem, bg_index, &tile, x, y);
+            }
+        }
+
+        // Outline of the visible area
+        let (scroll_x, scroll_y) = (bg.scroll_x as usize, bg.scroll_y as usize);
+        let lines = self.visible_lines();
+        for x in 0..SCREEN_WIDTH {
+            let px = (scroll_x + x) % width;
+            pixels[(scroll_y % height) * width + px] = VIEWPORT_COLOR;
+            pixels[((scroll_y + lines - 1) % height) * width + px] = VIEWPORT_COLOR;
+        }
+        for y in 0..lines {
+            let py = (scroll_y + y) % height;
+            pixels[py * width + scroll_x % width] = VIEWPORT_COLOR;
+            pixels[py * width + (scroll_x + SCREEN_WIDTH - 1) % width] = VIEWPORT_COLOR;
+        }
+
+        Some(image_from_pixels(width, height, &pixels))
+    }
+
+    /// Opens a debug window that shows the output of `render_tilemap`,
+    /// the tile under the mouse cursor is shown in the window title
+    pub fn show_tilemap(&self, bg_index: usize) -> PixResult<()> {
+        let (Some(image), Some((w, h))) = (self.render_tilemap(bg_index), self.get_tilemap_size(bg_index)) else {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("no background {bg_index}, expected 0 to 3")).into());
+        };
+        let char_size = image.width() as usize / w;
+
+        let tiles = (0..h).flat_map(|ty| (0..w).map(move |tx| (tx, ty)))
+            .filter_map(|(tx, ty)| self.get_tile_info(bg_index, tx, ty))
+            .collect();
+
+        let mut engine = Engine::builder()
+            .dimensions(image.width(), image.height())
+            .title(format!("BG{} tilemap", bg_index + 1))
+            .build()?;
+
+        engine.run(&mut TilemapWindow { image, tiles, width: w, char_size, hovered: None })
+    }
+}
+
+/// Debug window that shows a tilemap and reports the tile under the mouse cursor
+struct TilemapWindow {
+    image: Image,
+    tiles: Vec<TileInfo>,
+    /// Width of the tilemap in tiles
+    width: usize,
+    char_size: usize,
+    hovered: Option<usize>,
+}
+
+impl PixEngine for TilemapWindow {
+    fn on_update(&mut self, s: &mut PixState) -> PixResult<()> {
+        s.image(&self.image, [0, 0])?;
+
+        let mouse = s.mouse_pos();
+        if mouse.x() < 0 || mouse.y() < 0 {
+            return Ok(());
+        }
+
+        let i = (mouse.y() as usize / self.char_size) * self.width + mouse.x() as usize / self.char_size;
+        if self.hovered == Some(i) || i >= self.tiles.len() {
+            return Ok(());
+        }
+
+        self.hovered = Some(i);
+        let t = self.tiles[i];
+        s.set_title(format!(
+            "({}, {}) addr: ${:04X} tile: ${:03X} palette: {} prio: {} hflip: {} vflip: {}",
+            t.tx, t.ty, t.vram_addr, t.tile_num, t.palette, t.prio as u8, t.h_flip as u8, t.v_flip as u8,
+        ))
+    }
+}
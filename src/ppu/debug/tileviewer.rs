@@ -0,0 +1,70 @@
+use pix_engine::prelude::{Image, PixResult};
+
+use crate::ppu::{Ppu, rgb::Rgba, memory::vram::VRAM_SIZE};
+
+use super::{image_from_pixels, window::show_image};
+
+/// Number of tiles on a single row of the tile viewer image
+const TILES_PER_ROW: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Bit depth used to interpret character data
+pub enum BitDepth {
+    /// 2 bitplanes, 4 colors per palette
+    Two,
+    /// 4 bitplanes, 16 colors per palette
+    Four,
+    /// 8 bitplanes, uses all 256 colors of CGRAM
+    Eight,
+}
+
+impl BitDepth {
+    /// Size of a single 8x8 tile in VRAM words
+    pub fn words_per_tile(&self) -> usize {
+        match self {
+            BitDepth::Two => 8,
+            BitDepth::Four => 16,
+            BitDepth::Eight => 32,
+        }
+    }
+}
+
+impl Ppu {
+    /// Decodes all of VRAM as 8x8 tiles of bit depth `bpp`, colored using `palette` from CGRAM
+    /// 
+    /// Tiles are placed 16 per row in the order they appear in VRAM, so the image is always 128 pixels wide.
+    /// `palette` is ignored for 8bpp, since those tiles use all of CGRAM
+    pub fn render_vram_tiles(&self, bpp: BitDepth, palette: u16) -> Image {
+        let mem = self.memory.lock().unwrap();
+
+        let tile_count = VRAM_SIZE / bpp.words_per_tile();
+        let width = TILES_PER_ROW * 8;
+        let height = (tile_count / TILES_PER_ROW) * 8;
+        let mut pixels = vec![Rgba::default(); width * height];
+
+        for tile in 0..tile_count {
+            let char_addr = (tile * bpp.words_per_tile()) as u16;
+            let tile_x = (tile % TILES_PER_ROW) * 8;
+            let tile_y = (tile / TILES_PER_ROW) * 8;
+
+            for y in 0..8 {
+                for x in 0..8 {
+                    let color = match bpp {
+                        BitDepth::Two => self.get_2bpp_color(&mem, char_addr, x, y, palette),
+                        BitDepth::Four => self.get_4bpp_color(&mem, char_addr, x, y, palette),
+                        BitDepth::Eight => self.get_8bpp_color(&mem, char_addr, x, y),
+                    };
+                    pixels[(tile_y + y) * width + tile_x + x] = color;
+                }
+            }
+        }
+
+        image_from_pixels(width, height, &pixels)
+    }
+
+    /// Opens a debug window that shows the output of `render_vram_tiles`
+    pub fn show_vram_tiles(&self, bpp: BitDepth, palette: u16) -> PixResult<()> {
+        let image = self.render_vram_tiles(bpp, palette);
+        show_image("VRAM tiles", image, 2.0)
+    }
+}
@@ -0,0 +1,29 @@
+use pix_engine::prelude::{Engine, Image, PixEngine, PixResult, PixState};
+
+/// Debug window that shows a single image
+pub struct ImageWindow {
+    image: Image,
+}
+
+impl ImageWindow {
+    pub fn new(image: Image) -> ImageWindow {
+        ImageWindow { image }
+    }
+}
+
+impl PixEngine for ImageWindow {
+    fn on_update(&mut self, s: &mut PixState) -> PixResult<()> {
+        s.image(&self.image, [0, 0])
+    }
+}
+
+/// Opens a window with title `title` that shows `image` scaled up by `scale`, blocks until the window is closed
+pub fn show_image(title: &str, image: Image, scale: f32) -> PixResult<()> {
+    let mut engine = Engine::builder()
+        .dimensions(image.width(), image.height())
+        .scale(scale, scale)
+        .title(title)
+        .build()?;
+
+    engine.run(&mut ImageWindow::new(image))
+}
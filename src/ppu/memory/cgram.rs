@@ -1,4 +1,5 @@
 use crate::{fv_blanking, h_blanking};
+use crate::savestate::{Snapshot, StateWriter, StateReader, SaveStateError};
 const CGRAM_SIZE: usize = 0x100;
 pub struct CgRam {
     bytes: [u16; CGRAM_SIZE],
@@ -27,6 +28,13 @@ impl CgRam {
     pub fn read(&self, addr: u16) -> u16 {
         self.bytes[(addr as usize) % CGRAM_SIZE as usize]
     }
+
+    /// Write CGRAM directly, without going through the registers, used for debugging
+    /// 
+    /// Bit 15 is not stored in CGRAM and always reads back as 0
+    pub fn write(&mut self, addr: u16, word: u16) {
+        self.bytes[(addr as usize) % CGRAM_SIZE] = word & 0x7FFF;
+    }
         
     /// Read CGRAM using registers in cpu address space
     pub fn read_register(&mut self, addr: u16) -> Option<u8> {
@@ -64,6 +72,25 @@ impl CgRam {
         }
     }
 
+    /// Returns the byte `CGDATAREAD` would read next, without switching between the low and high byte
+    pub fn peek_data(&self) -> u8 {
+        let word = self.bytes[self.word_address as usize];
+        match self.rw_count {
+            true => (word >> 8) as u8,
+            false => word as u8,
+        }
+    }
+
+    /// Writes the byte `CGDATA` would write next directly, without the latch and without moving the address.
+    /// Unlike the register this works outside of blanking
+    pub fn poke_data(&mut self, byte: u8) {
+        let word = &mut self.bytes[self.word_address as usize];
+        *word = match self.rw_count {
+            true => (*word & 0x00FF) | ((byte as u16) << 8 & 0x7F00),
+            false => (*word & 0xFF00) | byte as u16,
+        };
+    }
+
     fn write_word_address(&mut self, addr: u8) {
         if fv_blanking!() | h_blanking!() {
             self.word_address = addr;
@@ -103,4 +130,22 @@ impl CgRam {
         };
         self.rw_count = !self.rw_count;
     }
-}
\ No newline at end of file
+}
+impl Snapshot for CgRam {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_words(&self.bytes);
+        w.write_bool(self.rw_count);
+        w.write_u8(self.latch);
+        w.write_u8(self.word_address);
+        w.write_bool(self.is_fvhblanking);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), SaveStateError> {
+        r.read_words(&mut self.bytes)?;
+        self.rw_count = r.read_bool()?;
+        self.latch = r.read_u8()?;
+        self.word_address = r.read_u8()?;
+        self.is_fvhblanking = r.read_bool()?;
+        Ok(())
+    }
+}
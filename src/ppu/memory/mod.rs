@@ -1,4 +1,4 @@
-use crate::{low_byte, high_byte, bank_byte, bit_set, bit_slice};
+use crate::{low_byte, high_byte, bank_byte, bit_set, bit_slice, savestate::{Snapshot, StateWriter, StateReader, SaveStateError}};
 
 use self::{cgram::CgRam, oam::Oam, vram::Vram};
 
@@ -51,6 +51,17 @@ impl PpuMemory {
         }
     }
 
+    /// Returns background `index`, `0` to `3` for BG1 to BG4, `None` for any other index
+    pub fn get_bg(&self, index: usize) -> Option<&Background> {
+        match index {
+            0 => Some(&self.bg1),
+            1 => Some(&self.bg2),
+            2 => Some(&self.bg3),
+            3 => Some(&self.bg4),
+            _ => None,
+        }
+    }
+
     pub fn read(&mut self, addr: u16) -> Option<u8> {
         match addr {
 
@@ -61,11 +72,10 @@ impl PpuMemory {
             // Upper bytes of mul register
             0x2136 => Some(bank_byte!(self.mpy_mulres)),
 
-            // Software latch for H/V counter
+            // Software latch for H/V counter, sets the current horizontal and vertical scanline
+            // in $213C and $213D respectively. The value read is open bus
             0x2137 => {
-                // set current horizontal and vertical scanline
-                // in $213C and $213D respectively
-                todo!();
+                self.ppustate.latch_counters();
                 None
             },
 
@@ -79,23 +89,50 @@ impl PpuMemory {
             0x213B => self.cgram.read_register(addr),
 
             // horizontal scanline latch
-            0x213C => todo!(),
+            0x213C => Some(self.ppustate.read_ophct()),
 
             //vertical scanline latch
-            0x213D => todo!(),
+            0x213D => Some(self.ppustate.read_opvct()),
 
             // STAT77
-            0x213E => todo!(),
+            0x213E => Some(self.oam.read_stat77()),
 
             // STAT78
-            // Also resets h/v scanline read var and counter_latch = 0
-            0x213F => todo!(),
+            0x213F => Some(self.ppustate.read_stat78()),
+
 
+            _ => None,
+        }
+    }
 
+    /// Reads a register like `PpuMemory::read` without side effects, the data ports return the byte they would read next
+    /// 
+    /// SLHV, OPHCT and OPVCT return `None`, since reading them latches the counters or moves their flip-flops
+    pub fn peek(&self, addr: u16) -> Option<u8> {
+        match addr {
+            0x2134 => Some(low_byte!(self.mpy_mulres)),
+            0x2135 => Some(high_byte!(self.mpy_mulres)),
+            0x2136 => Some(bank_byte!(self.mpy_mulres)),
+            0x2138 => Some(self.oam.peek_data()),
+            0x2139 | 0x213A => self.vram.peek_register(addr),
+            0x213B => Some(self.cgram.peek_data()),
+            0x213E => Some(self.oam.read_stat77()),
+            0x213F => Some(self.ppustate.stat78()),
             _ => None,
         }
     }
 
+    /// Writes a register for tools, the data ports write straight to OAM, VRAM and CGRAM without moving their address.
+    /// Other registers are written like `PpuMemory::write`, since setting them is their only effect
+    pub fn poke(&mut self, addr: u16, byte: u8) {
+        match addr {
+            0x2104 => self.oam.poke_data(byte),
+            0x2118 | 0x2119 => self.vram.poke_data(addr, byte),
+            0x2122 => self.cgram.poke_data(byte),
+            _ => self.write(addr, byte),
+        }
+    }
+
     pub fn write(&mut self, addr: u16, byte: u8) {
         match addr {
             0x2100 => self.ppustate.write_inidisp(byte),
@@ -164,7 +201,7 @@ impl PpuMemory {
 
             0x2125 => {
                 self.w1.write_objsel(byte);
-                self.w2.write_objsel(byte);
+                self.w2.write_objsel(byte >> 2);
             }
 
             0x2126 => self.w1.write_left_pos(byte),
@@ -227,7 +264,7 @@ impl PpuMemory {
                 self.mode7.write_vscroll(byte); 
             }
 
-            0x201F => self.bg2.write_hscroll(byte, self.bg_latch),
+            0x210F => self.bg2.write_hscroll(byte, self.bg_latch),
             0x2110 => self.bg2.write_vscroll(byte, self.bg_latch),
 
             0x2111 => self.bg3.write_hscroll(byte, self.bg_latch),
@@ -246,4 +283,38 @@ impl PpuMemory {
         self.mpy_mulres = self.mode7.a as u32 * high_byte!(self.mode7.b) as u32;
     }
 
-}
\ No newline at end of file
+}
+impl Snapshot for PpuMemory {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.vram.save_state(w);
+        self.cgram.save_state(w);
+        for bg in [&self.bg1, &self.bg2, &self.bg3, &self.bg4] {
+            bg.save_state(w);
+        }
+        self.w1.save_state(w);
+        self.w2.save_state(w);
+        self.mode7.save_state(w);
+        self.oam.save_state(w);
+        self.ppustate.save_state(w);
+        self.colormath.save_state(w);
+        w.write_u32(self.mpy_mulres);
+        w.write_u8(self.bg_latch);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), SaveStateError> {
+        self.vram.load_state(r)?;
+        self.cgram.load_state(r)?;
+        for bg in [&mut self.bg1, &mut self.bg2, &mut self.bg3, &mut self.bg4] {
+            bg.load_state(r)?;
+        }
+        self.w1.load_state(r)?;
+        self.w2.load_state(r)?;
+        self.mode7.load_state(r)?;
+        self.oam.load_state(r)?;
+        self.ppustate.load_state(r)?;
+        self.colormath.load_state(r)?;
+        self.mpy_mulres = r.read_u32()?;
+        self.bg_latch = r.read_u8()?;
+        Ok(())
+    }
+}
@@ -1,4 +1,5 @@
 use crate::{nth_bit, bit_slice, to_word, bit_set, fv_blanking, ppu::sprite::Sprite};
+use crate::savestate::{Snapshot, StateWriter, StateReader, SaveStateError};
 
 pub struct Oam {
     bytes: [u8; 544],
@@ -19,6 +20,11 @@ pub struct Oam {
 
     pub highest_prio_obj: usize,
 
+    /// More than 32 sprites were in range of a single scanline this frame (STAT77 bit 6)
+    pub range_over: bool,
+    /// More than 34 sprite slivers were on a single scanline this frame (STAT77 bit 7)
+    pub time_over: bool,
+
     pub oamaddl: u8, //$2102
     pub oamaddh: u8, //$2103
 }
@@ -35,7 +41,9 @@ impl Oam {
             bigobj_size: (16, 16),
             page0_addr: 0,
             page1_offs: 0,
-            highest_prio_obj: 0,          
+            highest_prio_obj: 0,
+            range_over: false,
+            time_over: false,
         }
     }
 
@@ -46,6 +54,7 @@ impl Oam {
         let mut i = self.highest_prio_obj;
         (0..128).map(|_| {
             let mut s = Sprite::new();
+            s.index = i;
             // Get 1st part of sprite info from first 512 bytes
             let idx = 4 * i;
 
@@ -82,6 +91,47 @@ impl Oam {
     }
 
 
+    /// Returns `(width, height)` of a sprite in pixels
+    pub fn get_sprite_size(&self, big_size: bool) -> (usize, usize) {
+        if big_size {
+            self.bigobj_size
+        } else {
+            self.smallobj_size
+        }
+    }
+
+    /// Returns VRAM word address of the 8x8 tile at column `col` and row `row` of a sprite with `tile_index`
+    /// 
+    /// Tiles of larger sprites are laid out in a 16x16 grid of tiles per page, 
+    /// the column and row wrap around inside this grid and never cross to the other page
+    pub fn get_tile_addr(&self, tile_index: usize, col: usize, row: usize) -> u16 {
+        let tile_x = (tile_index + col) & 0x0F;
+        let tile_y = ((tile_index >> 4) + row) & 0x0F;
+        let name = (tile_y << 4) | tile_x;
+
+        // Every 4bpp tile is 16 words
+        let mut addr = self.page0_addr + name * 16;
+        if bit_set!(tile_index, 8) {
+            addr += self.page1_offs;
+        }
+
+        (addr & 0x7FFF) as u16
+    }
+
+    /// Read STAT77 (`$213E`)
+    /// 
+    /// `TRMV vvvv`, time over (T), range over (R), master/slave (M), PPU1 version (v)
+    pub fn read_stat77(&self) -> u8 {
+        const PPU1_VERSION: u8 = 1;
+        (self.time_over as u8) << 7 | (self.range_over as u8) << 6 | PPU1_VERSION
+    }
+
+    /// Clears range over and time over flags, happens at the end of V-blank
+    pub fn reset_overflow_flags(&mut self) {
+        self.range_over = false;
+        self.time_over = false;
+    }
+
     pub fn read(&mut self, addr: u16) -> Option<u8> {
         match addr {
             // OAMDATAREAD
@@ -132,7 +182,7 @@ impl Oam {
             return;
         }
 
-        self.smallobj_size = match (byte >> 5) & 0x3 {
+        self.smallobj_size = match (byte >> 5) & 0x7 {
             0..=2 => (8, 8),
             3..=4 => (16, 16),
             5 => (32, 32),
@@ -140,7 +190,7 @@ impl Oam {
             _ => unreachable!(),
         };
 
-        self.bigobj_size = match (byte >> 5) & 0x3 {
+        self.bigobj_size = match (byte >> 5) & 0x7 {
             0 => (16, 16),
             1 | 3 | 7 => (32, 32),
             2 | 4 | 5 => (64, 64),
@@ -170,54 +220,125 @@ impl Oam {
     /// On write: Update OAMADD
     ///           internal_oamadd = (OAMADD & $1FF) << 1
     fn process_oamadd(&mut self) {
-        if !fv_blanking!() {
-            return;
-        }
+        // Writing either byte of OAMADD reloads the internal address immediately
+        self.reload_address();
+    }
+
+    /// Reload internal OAM address from OAMADD and update the priority rotation index
+    /// 
+    /// Happens when OAMADD is written and at the start of V-blank (when not in force blank).
+    /// The internal address gets overwritten by the PPU during active display, which is why games
+    /// rely on this reload to have the address in a known state during V-blank
+    pub fn reload_address(&mut self) {
+        self.update_pointer();
 
-        // Priority rotation
+        // Priority rotation, the first sprite is selected by bits 1-7 of OAMADDL
         if bit_set!(self.oamaddh, 7) {
             self.highest_prio_obj = bit_slice!(self.oamaddl, 1, 7) as usize;
         } else {
             self.highest_prio_obj = 0;
-            self.update_pointer();
         }
     }
 
+    /// Set the internal OAM address to the sprite that the PPU is currently accessing
+    /// 
+    /// During active display the PPU uses the internal address to evaluate sprites,
+    /// so reads and writes to OAMDATA go to whichever sprite the PPU accessed last
+    pub fn set_render_address(&mut self, sprite_index: usize) {
+        self.pointer = (sprite_index % 128) << 2;
+    }
+
     fn update_pointer(&mut self) {
         let pointer = to_word!(self.oamaddh, self.oamaddl) as usize;
         self.pointer = (pointer & 0x1FF) << 1;
     }
-    
-    fn read_data(&mut self) -> Option<u8> {
-        // Read val if blanking, else read nothing (emulate open bus)
-        let val = if fv_blanking!() {
-            let val = self.bytes[self.pointer];
-            Some(val)
+
+    /// Returns index in `self.bytes` for internal address `pointer`
+    /// 
+    /// The internal address is 10 bits, the 32 byte high table is mirrored over the upper 512 bytes
+    fn byte_index(pointer: usize) -> usize {
+        if pointer >= 0x200 {
+            0x200 | (pointer & 0x1F)
         } else {
-            None
-        };
+            pointer
+        }
+    }
 
-        self.pointer = self.pointer.wrapping_add(1);
-        val
+    fn increment_pointer(&mut self) {
+        self.pointer = (self.pointer + 1) & 0x3FF;
+    }
+    
+    /// Returns the byte `OAMDATAREAD` would read, without moving the address
+    pub fn peek_data(&self) -> u8 {
+        self.bytes[Self::byte_index(self.pointer)]
+    }
+
+    /// Writes the byte at the current address directly, without the low table latch and without moving the address
+    pub fn poke_data(&mut self, byte: u8) {
+        self.bytes[Self::byte_index(self.pointer)] = byte;
     }
 
+    fn read_data(&mut self) -> Option<u8> {
+        let val = self.bytes[Self::byte_index(self.pointer)];
+        self.increment_pointer();
+        Some(val)
+    }
+
+    /// Writes to the low table are buffered, the even byte is stored in a latch and 
+    /// both bytes are written at once when the odd byte is written. 
+    /// Writes to the high table are instant
+    /// 
     /// [Source](https://snes.nesdev.org/wiki/PPU_registers#OAMDATA)
     fn write_data(&mut self, byte: u8) {
-        if fv_blanking!() {
-            // Write in B part of OAM (write is instant)
-            if self.pointer >= 0x200 {
-                self.bytes[self.pointer] = byte;
-            }
-            // Write first byte in A part of OAM
-            else if !bit_set!(self.pointer, 0) {
-                self.latch = byte;
-            }
-            // Write second byte in A part of OAM
-            else if self.pointer < 0x200 && bit_set!(self.pointer, 1) {
-                self.bytes[self.pointer.wrapping_sub(1)] = self.latch;
-                self.bytes[self.pointer] = byte;
-            }
+        // Even address always sets latch
+        if !bit_set!(self.pointer, 0) {
+            self.latch = byte;
+        }
+
+        if self.pointer >= 0x200 {
+            // Write in high table (write is instant)
+            self.bytes[Self::byte_index(self.pointer)] = byte;
+        } else if bit_set!(self.pointer, 0) {
+            // Write both bytes of word in low table
+            self.bytes[self.pointer - 1] = self.latch;
+            self.bytes[self.pointer] = byte;
+        }
+
+        self.increment_pointer();
+    }
+}
+
+impl Snapshot for Oam {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_bytes(&self.bytes);
+        w.write_u8(self.latch);
+        w.write_usize(self.pointer);
+        for (width, height) in [self.smallobj_size, self.bigobj_size] {
+            w.write_usize(width);
+            w.write_usize(height);
         }
-        self.pointer = self.pointer.wrapping_add(1);
+        w.write_usize(self.page0_addr);
+        w.write_usize(self.page1_offs);
+        w.write_usize(self.highest_prio_obj);
+        w.write_bool(self.range_over);
+        w.write_bool(self.time_over);
+        w.write_u8(self.oamaddl);
+        w.write_u8(self.oamaddh);
     }
-}
\ No newline at end of file
+
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), SaveStateError> {
+        r.read_bytes(&mut self.bytes)?;
+        self.latch = r.read_u8()?;
+        self.pointer = r.read_usize()? % self.bytes.len();
+        self.smallobj_size = (r.read_usize()?, r.read_usize()?);
+        self.bigobj_size = (r.read_usize()?, r.read_usize()?);
+        self.page0_addr = r.read_usize()?;
+        self.page1_offs = r.read_usize()?;
+        self.highest_prio_obj = r.read_usize()? % 128;
+        self.range_over = r.read_bool()?;
+        self.time_over = r.read_bool()?;
+        self.oamaddl = r.read_u8()?;
+        self.oamaddh = r.read_u8()?;
+        Ok(())
+    }
+}
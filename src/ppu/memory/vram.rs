@@ -1,6 +1,9 @@
 use crate::{to_word, nth_bit, bit_slice, high_byte, low_byte, fv_blanking};
 
-const VRAM_SIZE: usize = 0x8000;
+use crate::savestate::{Snapshot, StateWriter, StateReader, SaveStateError};
+
+/// Size of VRAM in words
+pub const VRAM_SIZE: usize = 0x8000;
 
 #[derive(Debug, Clone, Copy)]
 /// Increment mode for VRAM based on byte written to `$2115`
@@ -73,8 +76,10 @@ impl Vram {
     /// Read directly from bytes, without using registers
     /// 
     /// This is only to be used inside the PPU, as other components cannot actually access this
+    /// 
+    /// The highest bit of `addr` is ignored, since VRAM is only 32k words
     pub fn read(&self, addr: u16) -> u16 {
-        self.bytes[addr as usize]
+        self.bytes[addr as usize % VRAM_SIZE]
     }
 
     /// Read from VRAM registers `$2139` and `$213A`
@@ -105,6 +110,26 @@ impl Vram {
         }
     }
 
+    /// Returns the byte `$2139` or `$213A` would read, without reloading the latch or moving the address
+    pub fn peek_register(&self, addr: u16) -> Option<u8> {
+        match addr {
+            0x2139 => Some(low_byte!(self.latch)),
+            0x213A => Some(high_byte!(self.latch)),
+            _ => None,
+        }
+    }
+
+    /// Writes the byte of `$2118` or `$2119` to the word at the current address, without moving the address.
+    /// Unlike the register this works outside of blanking
+    pub fn poke_data(&mut self, addr: u16, byte: u8) {
+        let word = &mut self.bytes[self.pointer % VRAM_SIZE];
+        match addr {
+            0x2118 => *word = (*word & 0xFF00) | byte as u16,
+            0x2119 => *word = (*word & 0x00FF) | (byte as u16) << 8,
+            _ => {},
+        }
+    }
+
     /// Write to VRAM registers `$2115` to and including `$2119`
     pub fn write_register(&mut self, addr: u16, byte: u8) {
         match addr {
@@ -244,4 +269,38 @@ fn get_remapped_address(remap: &AddrRemap, addrl: u8, addrh: u8) -> u16 {
             to_word!(hh, ll)
         },
     }
-}
\ No newline at end of file
+}
+impl Snapshot for Vram {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_words(&self.bytes);
+        w.write_u16(self.latch);
+        w.write_usize(self.pointer);
+        w.write_u8(self.incr_mode as u8);
+        w.write_u8(self.incr_amount);
+        w.write_u8(self.addr_remap as u8);
+        w.write_bytes(&[self.vmain, self.vmaddl, self.vmaddh, self.vmdatal, self.vmdatah]);
+        w.write_bool(self.is_fvblanking);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), SaveStateError> {
+        r.read_words(&mut self.bytes)?;
+        self.latch = r.read_u16()?;
+        self.pointer = r.read_usize()? % VRAM_SIZE;
+        self.incr_mode = match r.read_index(2, "VRAM increment mode")? {
+            0 => IncrementMode::LowByte,
+            _ => IncrementMode::HighByte,
+        };
+        self.incr_amount = r.read_u8()?;
+        self.addr_remap = match r.read_index(4, "VRAM address remapping")? {
+            0 => AddrRemap::NoRemap,
+            1 => AddrRemap::TwoBpp,
+            2 => AddrRemap::FourBpp,
+            _ => AddrRemap::EightBpp,
+        };
+        let mut regs = [0; 5];
+        r.read_bytes(&mut regs)?;
+        [self.vmain, self.vmaddl, self.vmaddh, self.vmdatal, self.vmdatah] = regs;
+        self.is_fvblanking = r.read_bool()?;
+        Ok(())
+    }
+}
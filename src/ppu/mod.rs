@@ -7,15 +7,17 @@ pub mod sprite;
 pub mod scanline;
 pub mod tile;
 pub mod layer;
+pub mod offsetpertile;
+pub mod spriteeval;
+pub mod debug;
 
-use std::{sync::{Mutex, Arc, RwLock}, thread, time::Instant};
-use std::time::Duration;
+use std::{sync::{Mutex, Arc, RwLock}, path::Path};
 
-use crate::{arc_mut};
+use crate::{arc_mut, region::Region, savestate::{Snapshot, StateWriter, StateReader, SaveStateError, Thumbnail}};
 use lazy_static::lazy_static;
-use pix_engine::prelude::{Engine, PixResult};
+use pix_engine::prelude::PixResult;
 
-use self::{memory::PpuMemory, screenapp::ScreenApp, scanline::Scanline};
+use self::{memory::PpuMemory, screenapp::{ScreenApp, presenter::Presenter, filter::VideoFilter, colorprofile::ColorProfile}, scanline::Scanline, spriteeval::{LineSprite, MAX_SPRITES_PER_LINE}, sprite::Sprite, debug::layers::{LayerToggles, LayerCapture}};
 
 lazy_static! {
     static ref V_BLANK: RwLock<bool> = RwLock::new(false);
@@ -48,6 +50,12 @@ pub struct Ppu {
     screen: ScreenApp,
     memory: Arc<Mutex<PpuMemory>>,
     scanline: Scanline,
+    /// Sprites that are drawn on the current scanline, selected by `Ppu::evaluate_sprites`
+    line_sprites: Vec<LineSprite>,
+    /// Debug switches for hiding layers
+    layer_toggles: LayerToggles,
+    /// Output of each separate layer, only stored when enabled with `Ppu::set_layer_capture`
+    layer_capture: Option<LayerCapture>,
 }
 
 
@@ -56,6 +64,9 @@ impl Ppu {
         Ppu {
             screen: ScreenApp::new(),
             scanline: Scanline::new(),
+            line_sprites: Vec::new(),
+            layer_toggles: LayerToggles::new(),
+            layer_capture: None,
             memory: arc_mut!(PpuMemory::new()),
         }
     }
@@ -64,25 +75,94 @@ impl Ppu {
         self.memory = memref;
     }
 
-    pub fn run(&mut self) -> PixResult<()> {
+    /// Returns the position of the beam as `(x, y)`, in dots and scanlines
+    pub fn beam_position(&self) -> (usize, usize) {
+        (self.scanline.x, self.scanline.y)
+    }
 
-       
-        loop {
-            let t = Instant::now();
-            self.tick();
-            println!("t: {0:?}", t.elapsed());
-            thread::sleep(Duration::from_millis(10))
-        }
+    /// Number of scanlines drawn in the current frame, changes to overscan take effect from the next frame
+    pub fn visible_lines(&self) -> usize {
+        self.scanline.visible_lines()
+    }
+
+    /// Returns true if the beam is at the top left of the screen, i.e. a new frame starts
+    pub fn at_frame_start(&self) -> bool {
+        self.scanline.at_frame_start()
+    }
+
+    /// Returns the last drawn frame as 8 bit RGBA bytes, row by row
+    pub fn get_frame_rgba(&self) -> Vec<u8> {
+        self.screen.frame_rgba()
+    }
 
-        let mut engine = Engine::builder()
-        .dimensions(SCREEN_WIDTH as u32, NTSC_SCREEN_HEIGHT as u32)
-        .title("MyApp")
-        .show_frame_rate()
-        .resizable()
-        // .target_frame_rate(60)
-        .build()?;
+    /// Writes the last drawn frame to `path`, as PNG, PPM or raw RGB depending on the extension of `path`
+    pub fn save_screenshot<P: AsRef<Path>>(&self, path: P) -> PixResult<()> {
+        self.screen.save_screenshot(path)
+    }
+
+    /// Sets the region of the console, this changes the number of scanlines, the frame rate and the PAL bit in STAT78
+    /// 
+    /// Overscan is reset to the default of the region, games can still change it through SETINI
+    pub fn set_region(&mut self, region: Region) {
+        let mut mem = self.memory.lock().unwrap();
+        mem.ppustate.region = region;
+        mem.ppustate.overscan_enabled = region.default_overscan();
+        self.scanline.overscan = region.default_overscan();
+        self.scanline.region = region;
+    }
 
-        engine.run(&mut self.screen)
+    /// Returns the last drawn frame at half size, for the header of save states
+    pub fn thumbnail(&self) -> Thumbnail {
+        self.screen.thumbnail()
+    }
 
+    pub fn get_region(&self) -> Region {
+        self.scanline.region
     }
-}
\ No newline at end of file
+
+    /// Sets the video filter used for screenshots and as the initial filter of the window
+    pub fn set_video_filter(&mut self, filter: VideoFilter) {
+        self.screen.set_filter(filter);
+    }
+
+    /// Sets the color profile used for screenshots and as the initial color profile of the window
+    pub fn set_color_profile(&mut self, profile: ColorProfile) {
+        self.screen.set_color_profile(profile);
+    }
+
+    /// Creates a window frontend that presents the frames of this PPU, with its filter and color profile
+    pub fn create_presenter(&self) -> Presenter {
+        Presenter::new(self.screen.frame_ref(), self.screen.get_filter(), self.screen.get_color_profile())
+    }
+}
+
+impl Snapshot for Ppu {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.memory.lock().unwrap().save_state(w);
+        self.scanline.save_state(w);
+        self.screen.save_state(w);
+        w.write_usize(self.line_sprites.len());
+        for line_sprite in &self.line_sprites {
+            line_sprite.save_state(w);
+        }
+    }
+
+    /// Sprites of the current scanline are saved as they were evaluated, since OAM may have changed since then
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), SaveStateError> {
+        self.memory.lock().unwrap().load_state(r)?;
+        self.scanline.load_state(r)?;
+        self.screen.load_state(r)?;
+
+        let count = r.read_usize()?;
+        if count > MAX_SPRITES_PER_LINE {
+            return Err(SaveStateError::InvalidValue("line sprites"));
+        }
+        self.line_sprites.clear();
+        for _ in 0..count {
+            let mut line_sprite = LineSprite { sprite: Sprite::new(), loaded_slivers: 0 };
+            line_sprite.load_state(r)?;
+            self.line_sprites.push(line_sprite);
+        }
+        Ok(())
+    }
+}
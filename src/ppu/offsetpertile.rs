@@ -0,0 +1,68 @@
+use crate::bit_set;
+
+use super::{Ppu, memory::PpuMemory, components::background::Background};
+
+/// Offset-per-tile entries only contain the upper 7 bits of the horizontal scroll,
+/// the lower 3 bits are still taken from the background's own scroll register
+const OPT_HSCROLL_MASK: u16 = 0x3F8;
+const OPT_VSCROLL_MASK: u16 = 0x3FF;
+
+impl Ppu {
+
+    /// Returns `(scroll_x, scroll_y)` that `bg` uses on the current pixel
+    ///
+    /// In background modes 2, 4 and 6, BG3 does not get drawn, instead its tilemap is used as a table of
+    /// scroll values for every 8 pixel column of BG1 and BG2 (offset-per-tile).
+    ///
+    /// `bg_index` is `0` to `3` for BG1 to BG4, only BG1 and BG2 can be affected by offset-per-tile
+    ///
+    /// Source: [snes wiki](https://snes.nesdev.org/wiki/Offset-per-tile)
+    pub fn get_bg_scroll(&self, mem: &PpuMemory, bg: &Background, bg_index: usize) -> (usize, usize) {
+        let mut scroll_x = bg.scroll_x;
+        let mut scroll_y = bg.scroll_y;
+
+        if !matches!(mem.ppustate.background_mode, 2 | 4 | 6) || bg_index > 1 {
+            return (scroll_x as usize, scroll_y as usize);
+        }
+
+        // Column of this background the current pixel is in, the first (partially) visible column is never affected
+        let column = (self.scanline.x + (scroll_x & 0x7) as usize) / 8;
+        if column == 0 {
+            return (scroll_x as usize, scroll_y as usize);
+        }
+
+        // Entries are read from BG3's tilemap, BG3's scroll selects which part of the tilemap is used
+        let bg3 = &mem.bg3;
+        let tx = (column - 1) + (bg3.scroll_x as usize >> 3);
+        let ty = bg3.scroll_y as usize >> 3;
+
+        // Bit 13 of an entry enables it for BG1, bit 14 for BG2
+        let valid_bit = 13 + bg_index;
+
+        if mem.ppustate.background_mode == 4 {
+            // Mode 4 only has a single row of entries, bit 15 selects whether it applies to vertical or horizontal scroll
+            let entry = mem.vram.read(self.get_tilemap_addr(bg3, tx, ty));
+            if bit_set!(entry, valid_bit) {
+                if bit_set!(entry, 15) {
+                    scroll_y = entry & OPT_VSCROLL_MASK;
+                } else {
+                    scroll_x = (entry & OPT_HSCROLL_MASK) | (scroll_x & 0x7);
+                }
+            }
+        } else {
+            // Modes 2 and 6 have horizontal entries on the first row and vertical entries on the row below it
+            let h_entry = mem.vram.read(self.get_tilemap_addr(bg3, tx, ty));
+            let v_entry = mem.vram.read(self.get_tilemap_addr(bg3, tx, ty + 1));
+
+            if bit_set!(h_entry, valid_bit) {
+                scroll_x = (h_entry & OPT_HSCROLL_MASK) | (scroll_x & 0x7);
+            }
+
+            if bit_set!(v_entry, valid_bit) {
+                scroll_y = v_entry & OPT_VSCROLL_MASK;
+            }
+        }
+
+        (scroll_x as usize, scroll_y as usize)
+    }
+}
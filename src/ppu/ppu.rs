@@ -2,7 +2,7 @@ use std::{cmp::Ordering, time::Instant};
 
 use crate::{ppu::{SCREEN_WIDTH, tile::Tile}, bit_set, low_byte, high_byte, to_word, nth_bit, main};
 
-use super::{rgb::Rgba, Ppu, sprite::Sprite, memory::PpuMemory, components::{background::Background, colormath::Addend}, layer::{LayerStruct, Layer}, NTSC_SCREEN_HEIGHT, scanline::{HOR_SCANLINES, NTSC_VER_SCANLINES}};
+use super::{rgb::Rgba, Ppu, F_BLANK, memory::PpuMemory, components::{background::Background, colormath::Addend}, layer::{LayerStruct, Layer}, sprite::Sprite, scanline::{HOR_SCANLINES, NTSC_VER_SCANLINES}};
 
 
 macro_rules! invert_if {
@@ -27,12 +27,39 @@ impl Ppu {
 
     /// Single clock cycle of ppu
     pub fn tick(&mut self) {
+        // Overscan setting is latched at the start of the frame
+        if self.scanline.at_frame_start() {
+            self.scanline.overscan = self.memory.lock().unwrap().ppustate.overscan_enabled;
+        }
+        let visible_lines = self.scanline.visible_lines();
+
+        // Sprite overflow flags are reset at the end of V-blank, unless force blank is on
+        if self.scanline.x == 0 && self.scanline.y == 0 && !*F_BLANK.read().unwrap() {
+            self.memory.lock().unwrap().oam.reset_overflow_flags();
+        }
+
+        // OAM address is reloaded at the start of V-blank, unless force blank is on
+        if self.scanline.x == 0 && self.scanline.y == visible_lines && !*F_BLANK.read().unwrap() {
+            self.memory.lock().unwrap().oam.reload_address();
+        }
+
+        // All visible scanlines are drawn once V-blank starts
+        if self.scanline.x == 0 && self.scanline.y == visible_lines {
+            self.screen.finish_frame(visible_lines);
+        }
+
+        // Select sprites for this scanline before drawing its first pixel
+        if self.scanline.x == 0 && self.scanline.y < visible_lines {
+            self.evaluate_sprites();
+        }
+
         // draw pixel at current scanline position
-        if self.scanline.x < SCREEN_WIDTH && self.scanline.y < NTSC_SCREEN_HEIGHT {
+        if self.scanline.x < SCREEN_WIDTH && self.scanline.y < visible_lines {
             self.draw_pixel();
         }
         // move scanline to next position
         self.scanline.goto_next();
+        self.memory.lock().unwrap().ppustate.set_beam_position(self.scanline.x, self.scanline.y);
     }
     
     /// Draw pixel on position denoted by current state of `self.scanline`
@@ -49,6 +76,13 @@ impl Ppu {
         
         // Get main and sub screen layers (they are copies of each other)
         let mut main_layers = self.get_layers(&mem);
+
+        if let Some(capture) = &mut self.layer_capture {
+            capture.store(self.scanline.x, self.scanline.y, &main_layers);
+        }
+
+        // Remove layers that are hidden by the debug toggles
+        self.layer_toggles.apply(&mut main_layers);
         let mut sub_layers = main_layers;
 
         // Enable and disable main screen layers based on enable flags set by TM and window set by TMW
@@ -86,7 +120,14 @@ impl Ppu {
             sub_screen_layer = Layer::FallBack(Rgba::default());
         }
 
-        let pixel_color = mem.colormath.apply_math(main_screen_layer, sub_screen_layer);
+        let pixel_color = if self.layer_toggles.color_math {
+            mem.colormath.apply_math(main_screen_layer, sub_screen_layer)
+        } else {
+            // Without a sub screen, color math just outputs the main screen
+            mem.colormath.apply_math(main_screen_layer, Layer::FallBack(Rgba::default()))
+        };
+
+        let pixel_color = mem.ppustate.apply_inidisp(pixel_color);
 
         self.screen.set_pixel(self.scanline.x, self.scanline.y, pixel_color);
         
@@ -115,13 +156,10 @@ impl Ppu {
             (c, true) => layers.bg4high = c,
         };
         
-        let sprites = mem.oam.as_sprites();
-        
-
-        (layers.sprite0, layers.sprite0_palette) = self.get_obj_color(&mem, &sprites, 0);
-        (layers.sprite1, layers.sprite1_palette) = self.get_obj_color(&mem, &sprites, 1);
-        (layers.sprite2, layers.sprite2_palette) = self.get_obj_color(&mem, &sprites, 2);
-        (layers.sprite3, layers.sprite3_palette) = self.get_obj_color(&mem, &sprites, 3);
+        (layers.sprite0, layers.sprite0_palette) = self.get_obj_color(&mem, 0);
+        (layers.sprite1, layers.sprite1_palette) = self.get_obj_color(&mem, 1);
+        (layers.sprite2, layers.sprite2_palette) = self.get_obj_color(&mem, 2);
+        (layers.sprite3, layers.sprite3_palette) = self.get_obj_color(&mem, 3);
 
         layers
     }
@@ -171,55 +209,37 @@ impl Ppu {
     }
     
     /// Gets color for `Sprite0..3` layers, where `0..3` denotes the priority
-    fn get_obj_color(&self, mem: &PpuMemory, sprites: &[Sprite], priority: usize) -> (Rgba, usize) {
+    /// 
+    /// Only sprites that were selected by `evaluate_sprites` for this scanline are drawn
+    fn get_obj_color(&self, mem: &PpuMemory, priority: usize) -> (Rgba, usize) {
         // Get first sprite that should be currently visible on scanline x and y position
-        // iterator returns (Rgba, prio) tuple
-        for s in sprites {
+        for line_sprite in &self.line_sprites {
+            let s = &line_sprite.sprite;
             // Priority difference -> wrong sprite
             if s.priority != priority {
                 continue;
             }
 
             // Get sprite tile size          
-            let (x_size, y_size) = if s.big_size {
-                mem.oam.bigobj_size
-            } else {
-                mem.oam.smallobj_size
-            };
+            let (x_size, _) = mem.oam.get_sprite_size(s.big_size);
             
-            // Check if x and y are within range of current pixel being drawn
-            let in_x_range: bool = self.scanline.x >= s.x && self.scanline.x <= s.x + x_size;
-            let in_y_range: bool = self.scanline.y >= s.y && self.scanline.y <= s.y + y_size;
-            
-            if !(in_x_range && in_y_range) {
+            // Check if x is within range of current pixel being drawn, y was already checked during evaluation
+            let sx = self.scanline.x as isize - s.signed_x();
+            if sx < 0 || sx >= x_size as isize {
                 continue;
             }
-            
-            // From here on -> correct sprite found, now finding color of this pixel
+            let sx = sx as usize;
 
-            // get x and y coordinates on current sprite
-            let sx = s.x + x_size - self.scanline.x;
-            let sy = s.y + y_size - self.scanline.y;
-            
-            let mut tile_index_offset = s.tile_index;
-            
-            if sx >= 8 {
-                tile_index_offset += 0x20;
-            }
-            
-            if sy >= 8 {
-                tile_index_offset += 0x200;
+            // Sliver was dropped during time evaluation
+            if !bit_set!(line_sprite.loaded_slivers, sx / 8) {
+                continue;
             }
             
-            // Select from first or second sprite page based on tile index
-            let addr = if s.tile_index < 0x100 {
-                mem.oam.page0_addr + tile_index_offset * 0x20
-            } else {
-                mem.oam.page0_addr + mem.oam.page1_offs + (tile_index_offset - 0x100) * 0x20
-            };
-            
-            // read tile form memory
-            let color = self.get_4bpp_color(mem, addr as u16, sx % 8, sx % 8, s.palette as u16);
+            // From here on -> correct sprite found, now finding color of this pixel
+
+            // get y coordinate on current sprite, sprites wrap around vertically
+            let sy = self.scanline.y.wrapping_sub(s.y) & 0xFF;
+            let color = self.get_sprite_color(mem, s, sx, sy);
             
             if color != Rgba::default() {
                 return (color, s.palette as usize);
@@ -228,253 +248,253 @@ impl Ppu {
 
         (Rgba::default(), 0)
     }
+
+    /// Returns color of pixel `x`, `y` of sprite `s`, where `x` and `y` are relative to the top left of the sprite on screen
+    /// 
+    /// Handles horizontal and vertical flip
+    pub(super) fn get_sprite_color(&self, mem: &PpuMemory, s: &Sprite, x: usize, y: usize) -> Rgba {
+        let (x_size, y_size) = mem.oam.get_sprite_size(s.big_size);
+        let x = if s.flip_h { x_size - 1 - x } else { x };
+        let y = if s.flip_v { y_size - 1 - y } else { y };
+
+        let addr = mem.oam.get_tile_addr(s.tile_index, x / 8, y / 8);
+
+        // Sprites use the last 8 palettes of CGRAM
+        self.get_4bpp_color(mem, addr, x % 8, y % 8, s.palette as u16 + 8)
+    }
     
     /// Returns color and priority for current pixel for bg1 layer as a tuple
     fn get_bg1_color(&self, mem: &PpuMemory) -> (Rgba, bool) {
-        
-        let tile = self.get_tile(mem, &mem.bg1);
-        let char_addr = mem.bg1.chr_base_addr + tile.tile_num;
-        let (x, y) = self.get_tile_xy(&mem.bg1, tile.h_flip, tile.v_flip, mem.ppustate.mosaic_size);
+        let (bx, by) = self.get_bg_position(mem, &mem.bg1, 0);
+        let tile = self.get_tile(mem, &mem.bg1, bx, by);
+        let (x, y) = self.get_tile_xy(&mem.bg1, tile.h_flip, tile.v_flip, bx, by);
 
-        
-        let c = match mem.ppustate.background_mode {
-            // 2 bpp
-            0 => self.get_2bpp_color(mem, char_addr, x, y, tile.palette as u16),
-            // 4 bpp
-            1 | 2 | 5 | 6 => self.get_4bpp_color(mem, char_addr, x, y, tile.palette as u16),
-            // 8 bpp (direct color)
-            3 | 4 | 7 => {
-                if mem.colormath.direct_color_mode {
-                    self.get_direct_color(mem, char_addr, x, y)
-                } else {
-                    self.get_8bpp_color(mem, char_addr, x, y)
-                }
-            },
-            _ => Rgba::default(),
-        };
-        
-        (c, tile.prio)
+        (self.get_bg_char_color(mem, 0, &tile, x, y), tile.prio)
     }
     
     /// Returns color and priority for current pixel for bg2 layer as a tuple
     fn get_bg2_color(&self, mem: &PpuMemory) -> (Rgba, bool) {
-        
-        
-        let tile = self.get_tile(mem, &mem.bg2);
-        let char_addr = mem.bg2.chr_base_addr + tile.tile_num;
-        let (x, y) = self.get_tile_xy(&mem.bg2, tile.h_flip, tile.v_flip, mem.ppustate.mosaic_size);
-        
-        let c = match mem.ppustate.background_mode {
-            // mode 0 = 2bpp with offset based on layer
-            0 => self.get_2bpp_color(mem, char_addr, x, y, tile.palette as u16 + 0x10),
-            // 2 bpp
-            4 | 5 => self.get_2bpp_color(mem, char_addr, x, y, tile.palette as u16),
-            // 4 bpp
-            1 | 2 | 3 => self.get_4bpp_color(mem, char_addr, x, y, tile.palette as u16),
-            
-            _ => Rgba::default(),
-        };
-        
-        (c, tile.prio)
+        let (bx, by) = self.get_bg_position(mem, &mem.bg2, 1);
+        let tile = self.get_tile(mem, &mem.bg2, bx, by);
+        let (x, y) = self.get_tile_xy(&mem.bg2, tile.h_flip, tile.v_flip, bx, by);
+
+        (self.get_bg_char_color(mem, 1, &tile, x, y), tile.prio)
     }
     
     /// Returns color and priority for current pixel for bg3 layer as a tuple
     fn get_bg3_color(&self, mem: &PpuMemory) -> (Rgba, bool) {
-        
-        let tile = self.get_tile(mem, &mem.bg3);
-        let char_addr = mem.bg3.chr_base_addr + tile.tile_num;
-        let (x, y) = self.get_tile_xy(&mem.bg3, tile.h_flip, tile.v_flip, mem.ppustate.mosaic_size);
-        
-        let c = match mem.ppustate.background_mode {
-            // 2 bpp
-            0 => self.get_2bpp_color(mem, char_addr, x, y, tile.palette as u16 + 0x20),
-            // 4 bpp
-            1 => self.get_2bpp_color(mem, char_addr, x, y, tile.palette as u16),
-            
-            _ => Rgba::default(),
-        };
-        
-        (c, tile.prio)
+        let (bx, by) = self.get_bg_position(mem, &mem.bg3, 2);
+        let tile = self.get_tile(mem, &mem.bg3, bx, by);
+        let (x, y) = self.get_tile_xy(&mem.bg3, tile.h_flip, tile.v_flip, bx, by);
+
+        (self.get_bg_char_color(mem, 2, &tile, x, y), tile.prio)
     }
     
     /// Returns color and priority for current pixel for bg4 layer as a tuple
     fn get_bg4_color(&self, mem: &PpuMemory) -> (Rgba, bool) {
-        
-        let tile = self.get_tile(mem, &mem.bg4);
-        let char_addr = mem.bg4.chr_base_addr + tile.tile_num;
-        let (x, y) = self.get_tile_xy(&mem.bg4, tile.h_flip, tile.v_flip, mem.ppustate.mosaic_size);
-        
-        let c = match mem.ppustate.background_mode {
+        let (bx, by) = self.get_bg_position(mem, &mem.bg4, 3);
+        let tile = self.get_tile(mem, &mem.bg4, bx, by);
+        let (x, y) = self.get_tile_xy(&mem.bg4, tile.h_flip, tile.v_flip, bx, by);
+
+        (self.get_bg_char_color(mem, 3, &tile, x, y), tile.prio)
+    }
+    
+    /// Returns color of pixel `x`, `y` of the character that `tile` refers to, as it is drawn on background `bg_index`
+    /// 
+    /// `bg_index` is `0` to `3` for BG1 to BG4, bit depth and palette offset of each background depend on the background mode.
+    /// Returns a transparent color if the background is not drawn in the current mode
+    pub(super) fn get_bg_char_color(&self, mem: &PpuMemory, bg_index: usize, tile: &Tile, x: usize, y: usize) -> Rgba {
+        let Some(bg) = mem.get_bg(bg_index) else {
+            return Rgba::default();
+        };
+        let palette = tile.palette as u16;
+
+        // 16x16 characters consist of 4 8x8 characters, the ones to the right and below are tile_num + 1 and tile_num + 16
+        let char_num = tile.tile_num + (x / 8) as u16 + 16 * (y / 8) as u16;
+        // A single 8x8 character takes up 4 words per bitplane
+        let char_addr = |bpp: u16| bg.chr_base_addr.wrapping_add(char_num.wrapping_mul(4 * bpp));
+
+        match (bg_index, mem.ppustate.background_mode) {
+            // Mode 0 gives every background its own 8 palettes of 4 colors
+            (0, 0) => self.get_2bpp_color(mem, char_addr(2), x, y, palette),
+            (1, 0) => self.get_2bpp_color(mem, char_addr(2), x, y, palette + 8),
+            (2, 0) => self.get_2bpp_color(mem, char_addr(2), x, y, palette + 16),
+            (3, 0) => self.get_2bpp_color(mem, char_addr(2), x, y, palette + 24),
             // 2 bpp
-            0 => self.get_2bpp_color(mem, char_addr, x, y, tile.palette as u16 + 0x30),
+            (1, 4 | 5) | (2, 1) => self.get_2bpp_color(mem, char_addr(2), x, y, palette),
+            // 4 bpp
+            (0, 1 | 2 | 5 | 6) | (1, 1 | 2 | 3) => self.get_4bpp_color(mem, char_addr(4), x, y, palette),
+            // 8 bpp (direct color)
+            (0, 3 | 4 | 7) => {
+                if mem.colormath.direct_color_mode {
+                    self.get_direct_color(mem, char_addr(8), x, y)
+                } else {
+                    self.get_8bpp_color(mem, char_addr(8), x, y)
+                }
+            },
             _ => Rgba::default(),
-        };
-        
-        (c, tile.prio)
+        }
     }
     
-    /// Get x and y coordinate of this pixel on tile, also handles horizontal and vertical flip
-    fn get_tile_xy(&self, bg: &Background, h_flip: bool, v_flip: bool, mosaic_size: usize) -> (usize, usize) {
-        let (mut x, mut y) = if bg.mosaic {
-            // The previous tile is selected in case mosaic size causes tile boundary crossing
-            // Meaning that this x/y should check what mosaic block it is and then check where
-            // that is on the current tile
-            let x = ((self.scanline.x / mosaic_size) * mosaic_size) % bg.char_size as usize;
-            let y = ((self.scanline.y / mosaic_size) * mosaic_size) % bg.char_size as usize;
-            (x, y)
+    /// Returns position of current pixel on the tilemap of `bg`, takes scroll, offset-per-tile and mosaic into account
+    /// 
+    /// `bg_index` is `0` to `3` for BG1 to BG4
+    fn get_bg_position(&self, mem: &PpuMemory, bg: &Background, bg_index: usize) -> (usize, usize) {
+        let (scroll_x, scroll_y) = self.get_bg_scroll(mem, bg, bg_index);
+
+        // Mosaic blocks are aligned to the screen, register value 0 means 1x1 blocks
+        let (x, y) = if bg.mosaic {
+            let size = mem.ppustate.mosaic_size + 1;
+            ((self.scanline.x / size) * size, (self.scanline.y / size) * size)
         } else {
-            (self.scanline.x % bg.char_size as usize, self.scanline.y % bg.char_size as usize)
+            (self.scanline.x, self.scanline.y)
         };
 
-        
+        (x + scroll_x, y + scroll_y)
+    }
+
+    /// Get x and y coordinate of this pixel on tile, also handles horizontal and vertical flip
+    /// 
+    /// `bx` and `by` are the position of this pixel on the tilemap, see `get_bg_position`
+    pub(super) fn get_tile_xy(&self, bg: &Background, h_flip: bool, v_flip: bool, bx: usize, by: usize) -> (usize, usize) {
+        let mut x = bx % bg.char_size as usize;
+        let mut y = by % bg.char_size as usize;
+
         if h_flip {
-            x = bg.char_size as usize - x;
+            x = bg.char_size as usize - 1 - x;
         }
         
         if v_flip {
-            y = bg.char_size as usize - y;
+            y = bg.char_size as usize - 1 - y;
         }
         
         (x, y)
     }
     
-    /// Get address of tilemap entry corresponding to current scanline x and y position for this background
-    fn get_tile(&self, mem: &PpuMemory, bg: &Background) -> Tile {
+    /// Get tilemap entry at position `bx` and `by` on the tilemap of this background
+    fn get_tile(&self, mem: &PpuMemory, bg: &Background, bx: usize, by: usize) -> Tile {
         // Get t_xth and t_yth tile on tilemap
-        // These are based on screen coordinates, and will have to be translated to the proper tilemap quadrant
-        
-        // mosaic effect
-        let (bx, by) = if mem.bg1.mosaic {
-            let x = ((self.scanline.x + bg.scroll_x as usize) / mem.ppustate.mosaic_size) * mem.ppustate.mosaic_size;
-            let y = ((self.scanline.y + bg.scroll_y as usize) / mem.ppustate.mosaic_size) * mem.ppustate.mosaic_size;
-            (x, y)
-        } else {
-            ((self.scanline.x + bg.scroll_x as usize), self.scanline.y + bg.scroll_y as usize)
-        };
-        
         let tx = bx / bg.char_size as usize;
         let ty = by / bg.char_size as usize;
         
-        // Tilemaps are always 32x32, the top left of each quadrant is tile (0, 0) + quad_offset
-        // where quad_offset increment by 0x800 for quadrants topleft, topright, bottomleft, bottomright
-        // in that order
-        let addr = bg.tilemap_vram_addr + match self.get_quadrant(tx, ty, bg) {
-            // Top left is 
-            TileMapQuadrant::TopLeft => ty * 32 + tx,
-            TileMapQuadrant::TopRight => ty * 32 + (tx - 32) + 0x800,
-            TileMapQuadrant::BottomLeft => (ty - 32) * 32 + tx + 0x1000,
-            TileMapQuadrant::BottomRight => (ty - 32) * 32 + (tx - 32) + 0x1800,
-        } as u16;
-        
-        let tiledata = mem.vram.read(addr);
+        let tiledata = mem.vram.read(self.get_tilemap_addr(bg, tx, ty));
         Tile::new(tiledata)
     }
+
+    /// Returns VRAM address of tilemap entry `tx`, `ty` of this background
+    /// 
+    /// Tile coordinates wrap around at the edge of the tilemap
+    pub(super) fn get_tilemap_addr(&self, bg: &Background, tx: usize, ty: usize) -> u16 {
+        let tx = tx % (32 << bg.horizontal_tilemap_count);
+        let ty = ty % (32 << bg.vertical_tilemap_count);
+
+        // Tilemaps are always 32x32 words, the top left of each quadrant is tile (0, 0) + quad_offset
+        // Quadrants are stored in order topleft, topright, bottomleft, bottomright, skipping those that are not used
+        let quad_offset = match self.get_quadrant(tx, ty, bg) {
+            TileMapQuadrant::TopLeft => 0,
+            TileMapQuadrant::TopRight => 0x400,
+            TileMapQuadrant::BottomLeft => 0x400 << bg.horizontal_tilemap_count,
+            TileMapQuadrant::BottomRight => 0xC00,
+        };
+
+        bg.tilemap_vram_addr.wrapping_add(((ty % 32) * 32 + tx % 32 + quad_offset) as u16)
+    }
     
     /// Returns quadrant in memory of tile in tilemap based on tile x and y position and 
     /// current horizontal/vertical tilemap size settings
     fn get_quadrant(&self, tx: usize, ty: usize, bg: &Background) -> TileMapQuadrant {
-        match (bg.horizontal_tilemap_count, bg.vertical_tilemap_count) {
-            (0, 0) => TileMapQuadrant::TopLeft,
-            (0, 1) => {
-                if tx < 32 {
-                    return TileMapQuadrant::TopLeft;
-                }
-                return TileMapQuadrant::BottomLeft;
-            },
-            (1, 0) => {
-                if ty < 32 {
-                    return TileMapQuadrant::TopLeft;
-                }
-                return TileMapQuadrant::BottomRight;
-            },
-            (1, 1) => {
-                match (tx < 32, ty < 32) {
-                    (false, false) => TileMapQuadrant::TopLeft,
-                    (false, true) => TileMapQuadrant::TopRight,
-                    (true, false) => TileMapQuadrant::BottomLeft,
-                    (true, true) => TileMapQuadrant::BottomRight,
-                }
-            },
-            _ => unreachable!(),
+        let right = bg.horizontal_tilemap_count == 1 && tx >= 32;
+        let bottom = bg.vertical_tilemap_count == 1 && ty >= 32;
+        match (right, bottom) {
+            (false, false) => TileMapQuadrant::TopLeft,
+            (true, false) => TileMapQuadrant::TopRight,
+            (false, true) => TileMapQuadrant::BottomLeft,
+            (true, true) => TileMapQuadrant::BottomRight,
         }
     }
     
     /// Get color of character at char_addr with pixel coord `x` and `y` using 2bpp palette
-    fn get_2bpp_color(&self, mem: &PpuMemory, char_addr: u16, x: usize, y: usize, palette: u16) -> Rgba {
-        // In 2bpp, characters are stored as [row0plane1, row0plane0, row1plane1, row1plane0, ...]
-        // To get the correct row, 2*y should be added
-        let addr_row = char_addr + 2*y as u16;
-        // Read plane 1 and 0 at the same time since vram reads a word in this implemenation
-        let plane10 = mem.vram.read(addr_row as u16);
-        
-        // Get 'column' from plane1 and plane0
-        let palette_offset = nth_bit!(plane10, x + 8) << 1 | nth_bit!(plane10, x);
-        let palette_addr = palette << 2 + palette_offset;
+    pub(super) fn get_2bpp_color(&self, mem: &PpuMemory, char_addr: u16, x: usize, y: usize, palette: u16) -> Rgba {
+        let palette_offset = self.get_char_palette_offset(mem, char_addr, x, y, 2);
+        let palette_addr = (palette << 2) + palette_offset;
         let color = mem.cgram.read(palette_addr);
         Rgba::from_snes_palette(color)
     }
     
     /// Get color of character at char_addr with pixel coord `x` and `y` using 4bpp palette
-    fn get_4bpp_color(&self, mem: &PpuMemory, char_addr: u16, x: usize, y: usize, palette: u16) -> Rgba {
-        // In 2bpp, characters are stored as [row0plane1, row0plane0, row1plane1, row1plane0, ...]
-        // To get the correct row, 2*y should be added
-        let addr_row = char_addr + 2*y as u16;
-        
-        let plane10 = mem.vram.read(addr_row);
-        let plane32 = mem.vram.read(addr_row.wrapping_add(8));
-        
-        let palette_offset = nth_bit!(plane32, x + 8) << 3 | nth_bit!(plane32, x) << 2 | 
-        nth_bit!(plane10, x + 8) << 1 | nth_bit!(plane10, x);
-        
-        let palette_addr = palette << 4 + palette_offset;
+    pub(super) fn get_4bpp_color(&self, mem: &PpuMemory, char_addr: u16, x: usize, y: usize, palette: u16) -> Rgba {
+        let palette_offset = self.get_char_palette_offset(mem, char_addr, x, y, 4);
+        let palette_addr = (palette << 4) + palette_offset;
         let color = mem.cgram.read(palette_addr);
         Rgba::from_snes_palette(color)
     }
     
     /// Get color of character at char_addr with pixel coord `x` and `y` using 8bpp palette
-    fn get_8bpp_color(&self, mem: &PpuMemory, char_addr: u16, x: usize, y: usize) -> Rgba {
-        // In 2bpp, characters are stored as [row0plane1, row0plane0, row1plane1, row1plane0, ...]
-        // To get the correct row, 2*y should be added
-        let addr_row = char_addr + 2*y as u16;
-        
-        // Read bitplanes from memory
-        let plane10 = mem.vram.read(addr_row);
-        let plane32 = mem.vram.read(addr_row.wrapping_add(0x08));
-        let plane54 = mem.vram.read(addr_row.wrapping_add(0x10));
-        let plane76 = mem.vram.read(addr_row.wrapping_add(0x18));
-        
-        // Get column for all bitplanes
-        let plane10_col = nth_bit!(plane10, x + 8) << 1 | nth_bit!(plane10, x);
-        let plane32_col = nth_bit!(plane32, x + 8) << 1 | nth_bit!(plane32, x);
-        let plane54_col = nth_bit!(plane54, x + 8) << 1 | nth_bit!(plane54, x);
-        let plane76_col = nth_bit!(plane76, x + 8) << 1 | nth_bit!(plane76, x);
-        let palette_offset = plane76_col << 6 | plane54_col << 4 | plane32_col << 2 | plane10_col;
-        
+    pub(super) fn get_8bpp_color(&self, mem: &PpuMemory, char_addr: u16, x: usize, y: usize) -> Rgba {
+        let palette_offset = self.get_char_palette_offset(mem, char_addr, x, y, 8);
         let color = mem.cgram.read(palette_offset);
         Rgba::from_snes_palette(color)
     }
     
     /// Direct color mode, similar to 8bpp, 
     /// the difference being that the bitplane is interpreted as a color instead of a palette index
-    fn get_direct_color(&self, mem: &PpuMemory, char_addr: u16, x: usize, y: usize) -> Rgba {
-        // In 2bpp, characters are stored as [row0plane1, row0plane0, row1plane1, row1plane0, ...]
-        // To get the correct row, 2*y should be added
-        let addr_row = char_addr + 2*y as u16;
-        
-        // Read bitplanes from memory
-        let plane10 = mem.vram.read(addr_row);
-        let plane32 = mem.vram.read(addr_row.wrapping_add(0x08));
-        let plane54 = mem.vram.read(addr_row.wrapping_add(0x10));
-        let plane76 = mem.vram.read(addr_row.wrapping_add(0x18));
-        
-        // Get column for all bitplanes
-        let plane10_col = nth_bit!(plane10, x + 8) << 1 | nth_bit!(plane10, x);
-        let plane32_col = nth_bit!(plane32, x + 8) << 1 | nth_bit!(plane32, x);
-        let plane54_col = nth_bit!(plane54, x + 8) << 1 | nth_bit!(plane54, x);
-        let plane76_col = nth_bit!(plane76, x + 8) << 1 | nth_bit!(plane76, x);
-        
-        let color_word = plane76_col << 6 | plane54_col << 4 | plane32_col << 2 | plane10_col;
+    pub(super) fn get_direct_color(&self, mem: &PpuMemory, char_addr: u16, x: usize, y: usize) -> Rgba {
+        let color_word = self.get_char_palette_offset(mem, char_addr, x, y, 8);
         Rgba::from_snes_palette(color_word)
     }
-    
+
+    /// Get palette offset of pixel `x`, `y` of the character at `char_addr` that has `bpp` bitplanes
+    /// 
+    /// Characters are stored as pairs of bitplanes, every row of a pair is a single word in VRAM
+    /// where the low byte holds the lower plane and the high byte holds the upper plane. 
+    /// The 8 rows of a pair come one after another, so the next pair of planes is 8 words further.
+    /// The leftmost pixel of a row is stored in the highest bit of each plane
+    fn get_char_palette_offset(&self, mem: &PpuMemory, char_addr: u16, x: usize, y: usize, bpp: usize) -> u16 {
+        let addr_row = char_addr.wrapping_add((y % 8) as u16);
+        let bit = 7 - (x % 8);
+
+        (0..bpp / 2).fold(0, |palette_offset, pair| {
+            let planes = mem.vram.read(addr_row.wrapping_add(8 * pair as u16));
+            let col = nth_bit!(planes, bit + 8) << 1 | nth_bit!(planes, bit);
+            palette_offset | col << (2 * pair)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn poke_word(mem: &mut PpuMemory, addr: u16, word: u16) {
+        mem.write(0x2116, low_byte!(addr));
+        mem.write(0x2117, high_byte!(addr));
+        mem.poke(0x2118, low_byte!(word));
+        mem.poke(0x2119, high_byte!(word));
+    }
+
+    #[test]
+    fn test_decode_2bpp_char() {
+        let ppu = Ppu::new();
+        let mut mem = PpuMemory::new();
+        // Row 0: plane 0 is 1000_0001, plane 1 is 1100_0000
+        poke_word(&mut mem, 0x0000, 0b1100_0000_1000_0001);
+
+        let row: Vec<u16> = (0..8).map(|x| ppu.get_char_palette_offset(&mem, 0x0000, x, 0, 2)).collect();
+        assert_eq!(row, [3, 2, 0, 0, 0, 0, 0, 1]);
+        assert_eq!(ppu.get_char_palette_offset(&mem, 0x0000, 0, 1, 2), 0);
+    }
+
+    #[test]
+    fn test_decode_4bpp_char() {
+        let ppu = Ppu::new();
+        let mut mem = PpuMemory::new();
+        // Row 2: plane 1 is set for every pixel, plane 2 only for the leftmost pixel
+        poke_word(&mut mem, 0x0102, 0xFF00);
+        poke_word(&mut mem, 0x010A, 0x0080);
+
+        let row: Vec<u16> = (0..8).map(|x| ppu.get_char_palette_offset(&mem, 0x0100, x, 2, 4)).collect();
+        assert_eq!(row, [6, 2, 2, 2, 2, 2, 2, 2]);
+        // Planes 2 and 3 don't belong to a 2bpp character
+        assert_eq!(ppu.get_char_palette_offset(&mem, 0x0100, 0, 2, 2), 2);
+    }
 }
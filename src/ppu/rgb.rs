@@ -25,15 +25,22 @@ impl Rgba {
     }
 
     /// Return Rgba struct from word in SNES CGRAM
+    /// 
+    /// CGRAM words are BGR555: `0bbbbbgg gggrrrrr`
     pub fn from_snes_palette(word: u16) -> Rgba {
         Rgba {
             a: nth_bit!(word, 15) as u8,
-            r: bit_slice!(word, 10, 14) as u8,
+            r: bit_slice!(word, 0, 4) as u8,
             g: bit_slice!(word, 5, 9) as u8,
-            b: bit_slice!(word, 0, 4) as u8,
+            b: bit_slice!(word, 10, 14) as u8,
         }
     }
 
+    /// Returns `self` as a BGR555 word like it is stored in CGRAM, alpha is not stored
+    pub fn to_snes_palette(self) -> u16 {
+        (self.b as u16 & 0x1F) << 10 | (self.g as u16 & 0x1F) << 5 | (self.r as u16 & 0x1F)
+    }
+
     pub fn set_r(&mut self, r: u8) {
         self.r = r;
     }
@@ -69,6 +76,16 @@ impl Rgba {
         )
     }
 
+    /// Applies master brightness from INIDISP, `brightness` is `0` (black) to `15` (full brightness)
+    pub fn apply_brightness(&self, brightness: u8) -> Rgba {
+        if brightness == 0 {
+            return Rgba::BLACK;
+        }
+
+        let scale = |c: u8| (c as u16 * (brightness as u16 + 1) / 16) as u8;
+        Rgba::new(scale(self.r), scale(self.g), scale(self.b), self.a)
+    }
+
     pub fn multiply(&self, f: u8) -> Rgba {
         let r = std::cmp::min(Self::MAX_RGB_VALUE, self.r * f);
         let g = std::cmp::min(Self::MAX_RGB_VALUE, self.g * f);
@@ -122,4 +139,25 @@ impl Sub for Rgba {
             a: self.a
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snes_palette_is_bgr() {
+        assert_eq!(Rgba::from_snes_palette(0x001F), Rgba::new(31, 0, 0, 0));
+        assert_eq!(Rgba::from_snes_palette(0x03E0), Rgba::new(0, 31, 0, 0));
+        assert_eq!(Rgba::from_snes_palette(0x7C00), Rgba::new(0, 0, 31, 0));
+        assert_eq!(Rgba::from_snes_palette(0x1234).to_snes_palette(), 0x1234);
+    }
+
+    #[test]
+    fn test_apply_brightness() {
+        let color = Rgba::new(31, 16, 2, 1);
+        assert_eq!(color.apply_brightness(0), Rgba::BLACK);
+        assert_eq!(color.apply_brightness(15), color);
+        assert_eq!(color.apply_brightness(7), Rgba::new(15, 8, 1, 1));
+    }
+}
@@ -1,9 +1,13 @@
-use super::{SCREEN_WIDTH, H_BLANK, NTSC_SCREEN_HEIGHT, V_BLANK};
+use crate::{region::Region, savestate::{Snapshot, StateWriter, StateReader, SaveStateError}};
+
+use super::{SCREEN_WIDTH, H_BLANK, NTSC_SCREEN_HEIGHT, PAL_SCREEN_HEIGHT, V_BLANK};
 
 
 pub const HOR_SCANLINES: usize = 338;
-pub const NTSC_VER_SCANLINES: usize = 261;
-pub const PAL_VER_SCANLINES: usize = 311;
+/// Number of scanlines in an NTSC frame, including V-blank
+pub const NTSC_VER_SCANLINES: usize = 262;
+/// Number of scanlines in a PAL frame, including V-blank
+pub const PAL_VER_SCANLINES: usize = 312;
 
 
 /// This struct emulates the electron beam in a CRT tv,
@@ -12,7 +16,10 @@ pub struct Scanline {
     pub x: usize,
     pub y: usize,
     pub scanline_sprites: usize,
-
+    /// Region decides the number of scanlines per frame
+    pub region: Region,
+    /// Overscan shows 239 instead of 224 lines, latched at the start of every frame
+    pub overscan: bool,
 }
 
 impl Scanline {
@@ -21,6 +28,8 @@ impl Scanline {
             x: 0,
             y: 0,
             scanline_sprites: 0,
+            region: Region::Ntsc,
+            overscan: false,
         }
     }
 
@@ -28,10 +37,7 @@ impl Scanline {
     pub fn goto_next(&mut self) {
         self.x += 1;
 
-        if self.x >= SCREEN_WIDTH {
-            // x > screen width means H_blank is on
-            *H_BLANK.write().unwrap() = true;
-        } else if self.x >= HOR_SCANLINES {
+        if self.x >= HOR_SCANLINES {
             // x > scanlines means h_blank turns off and x is at left of screen
             *H_BLANK.write().unwrap() = false;
             self.x = 0;
@@ -39,15 +45,57 @@ impl Scanline {
             // move to next scanline
             self.y += 1;
             self.scanline_sprites = 0;
+        } else if self.x >= SCREEN_WIDTH {
+            // x > screen width means H_blank is on
+            *H_BLANK.write().unwrap() = true;
         }
 
         // Same logic as x, but with Vblank instead
-        if self.y >= NTSC_SCREEN_HEIGHT {
-            *V_BLANK.write().unwrap() = true;
-        } else if self.y >= NTSC_VER_SCANLINES {
+        if self.y >= self.region.scanlines() {
             *V_BLANK.write().unwrap() = false;
             self.y = 0;
+        } else if self.y >= self.visible_lines() {
+            *V_BLANK.write().unwrap() = true;
+        }
+
+    }
+
+    /// Number of scanlines that are drawn, V-blank starts right after them
+    pub fn visible_lines(&self) -> usize {
+        if self.overscan {
+            PAL_SCREEN_HEIGHT
+        } else {
+            NTSC_SCREEN_HEIGHT
         }
+    }
+
+    /// Returns true if the beam is at the top left of the screen, i.e. a new frame starts
+    pub fn at_frame_start(&self) -> bool {
+        self.x == 0 && self.y == 0
+    }
+}
+impl Snapshot for Scanline {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_usize(self.x);
+        w.write_usize(self.y);
+        w.write_usize(self.scanline_sprites);
+        self.region.save_state(w);
+        w.write_bool(self.overscan);
+    }
+
+    /// Also restores the global H-blank and V-blank flags for the loaded beam position
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), SaveStateError> {
+        self.x = r.read_usize()?;
+        self.y = r.read_usize()?;
+        self.scanline_sprites = r.read_usize()?;
+        self.region.load_state(r)?;
+        self.overscan = r.read_bool()?;
 
+        if self.x >= HOR_SCANLINES || self.y >= self.region.scanlines() {
+            return Err(SaveStateError::InvalidValue("scanline position"));
+        }
+        *H_BLANK.write().unwrap() = self.x >= SCREEN_WIDTH;
+        *V_BLANK.write().unwrap() = self.y >= self.visible_lines();
+        Ok(())
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,91 @@
+use crate::ppu::rgb::Rgba;
+
+use super::filter::Pixel;
+
+/// Gamma of a CRT television, SNES games were made to look right on these
+const CRT_GAMMA: f32 = 2.5;
+/// Gamma of a modern monitor (sRGB)
+const DISPLAY_GAMMA: f32 = 2.2;
+/// Gamma of the LCD profile, LCDs wash out dark colors less than a CRT does
+const LCD_GAMMA: f32 = 2.0;
+
+/// Converts linear NTSC (SMPTE-C) phosphor colors to linear sRGB, CRT phosphors are slightly less saturated
+const CRT_MATRIX: [[f32; 3]; 3] = [
+    [0.9395, 0.0502, 0.0103],
+    [0.0178, 0.9658, 0.0164],
+    [-0.0016, -0.0044, 1.0060],
+];
+
+/// Mixes some of every channel into the others, like the limited color gamut of a handheld LCD
+const LCD_MATRIX: [[f32; 3]; 3] = [
+    [0.82, 0.14, 0.04],
+    [0.10, 0.80, 0.10],
+    [0.06, 0.14, 0.80],
+];
+/// Black level of the LCD profile, the backlight shines through even for black pixels
+const LCD_BLACK_LEVEL: f32 = 0.03;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// How 5 bit PPU colors are converted to the 8 bit colors of the final frame
+pub enum ColorProfile {
+    /// Plain linear scale of every channel from 5 to 8 bits
+    #[default]
+    Raw,
+    /// Corrects for the difference between the gamma of a CRT and the gamma of a modern display, darkening mid tones
+    Gamma,
+    /// Gamma correction together with the color gamut of CRT phosphors
+    Crt,
+    /// Less contrast and saturation, with raised blacks, like an LCD
+    Lcd,
+}
+
+impl ColorProfile {
+    pub const ALL: [ColorProfile; 4] = [ColorProfile::Raw, ColorProfile::Gamma, ColorProfile::Crt, ColorProfile::Lcd];
+
+    /// Parses a profile from its lowercase name, as used on the command line
+    pub fn from_name(name: &str) -> Option<ColorProfile> {
+        match name {
+            "raw" => Some(ColorProfile::Raw),
+            "gamma" => Some(ColorProfile::Gamma),
+            "crt" => Some(ColorProfile::Crt),
+            "lcd" => Some(ColorProfile::Lcd),
+            _ => None,
+        }
+    }
+
+    /// Returns the profile after this one in `ColorProfile::ALL`, wrapping around at the end
+    pub fn next(&self) -> ColorProfile {
+        let i = ColorProfile::ALL.iter().position(|p| p == self).unwrap_or(0);
+        ColorProfile::ALL[(i + 1) % ColorProfile::ALL.len()]
+    }
+
+    /// Converts a 5 bit PPU color to an opaque 8 bit pixel
+    pub fn convert(&self, color: &Rgba) -> Pixel {
+        let (r, g, b) = color.as_rgb_tuple();
+        let normalized = [r, g, b].map(|c| c as f32 / Rgba::MAX_RGB_VALUE as f32);
+
+        let [r, g, b] = match self {
+            ColorProfile::Raw => {
+                let (r, g, b, _) = color.as_highrange_rgba_tuple();
+                return [r, g, b, 0xFF];
+            },
+            ColorProfile::Gamma => normalized.map(|c| c.powf(CRT_GAMMA / DISPLAY_GAMMA)),
+            ColorProfile::Crt => {
+                let linear = normalized.map(|c| c.powf(CRT_GAMMA));
+                mul_matrix(&CRT_MATRIX, linear).map(|c| c.max(0.0).powf(1.0 / DISPLAY_GAMMA))
+            },
+            ColorProfile::Lcd => {
+                let linear = normalized.map(|c| c.powf(LCD_GAMMA));
+                mul_matrix(&LCD_MATRIX, linear)
+                    .map(|c| LCD_BLACK_LEVEL + (1.0 - LCD_BLACK_LEVEL) * c.max(0.0).powf(1.0 / DISPLAY_GAMMA))
+            },
+        };
+
+        let to_byte = |c: f32| (c * 255.0).round().clamp(0.0, 255.0) as u8;
+        [to_byte(r), to_byte(g), to_byte(b), 0xFF]
+    }
+}
+
+fn mul_matrix(m: &[[f32; 3]; 3], v: [f32; 3]) -> [f32; 3] {
+    m.map(|row| row[0] * v[0] + row[1] * v[1] + row[2] * v[2])
+}
@@ -0,0 +1,325 @@
+use crate::ppu::rgb::Rgba;
+
+use super::colorprofile::ColorProfile;
+
+/// 8 bit RGBA pixel
+pub type Pixel = [u8; 4];
+
+/// Frame of 8 bit RGBA pixels, the output of a video filter
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RgbaFrame {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<Pixel>,
+}
+
+impl RgbaFrame {
+    /// Converts PPU output to 8 bit RGBA using `profile`, every pixel is made opaque
+    pub fn from_pixels(width: usize, height: usize, pixels: &[Rgba], profile: ColorProfile) -> RgbaFrame {
+        let pixels = pixels.iter().map(|pix| profile.convert(pix)).collect();
+
+        RgbaFrame { width, height, pixels }
+    }
+
+    /// Returns the frame as RGBA bytes, row by row
+    pub fn bytes(&self) -> &[u8] {
+        self.pixels.as_flattened()
+    }
+
+    /// Returns the frame as RGB bytes, row by row
+    pub fn rgb_bytes(&self) -> Vec<u8> {
+        self.pixels.iter().flat_map(|p| [p[0], p[1], p[2]]).collect()
+    }
+
+    /// Returns pixel `x`, `y`, coordinates outside of the frame are clamped to the edge
+    fn get(&self, x: isize, y: isize) -> Pixel {
+        let x = x.clamp(0, self.width as isize - 1) as usize;
+        let y = y.clamp(0, self.height as isize - 1) as usize;
+        self.pixels[y * self.width + x]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// Post-processing applied to a completed frame before it is presented or saved, all filters run on the CPU
+pub enum VideoFilter {
+    #[default]
+    None,
+    /// Approximation of composite video: luma and chroma are separated and chroma is smeared horizontally
+    Ntsc,
+    /// Doubles the frame and darkens every other line like the gaps between the lines of a CRT
+    Scanlines,
+    /// Scale2x (EPX), doubles the frame and only copies neighbours to smooth out diagonal edges
+    Scale2x,
+    /// Simplified hq2x, doubles the frame and blends edges found by comparing pixels in YUV
+    Hq2x,
+    /// 2xBR, doubles the frame and blends along edges found with weighted YUV distances
+    Xbr,
+}
+
+impl VideoFilter {
+    pub const ALL: [VideoFilter; 6] = [
+        VideoFilter::None,
+        VideoFilter::Ntsc,
+        VideoFilter::Scanlines,
+        VideoFilter::Scale2x,
+        VideoFilter::Hq2x,
+        VideoFilter::Xbr,
+    ];
+
+    /// Parses a filter from its lowercase name, as used on the command line
+    pub fn from_name(name: &str) -> Option<VideoFilter> {
+        match name {
+            "none" => Some(VideoFilter::None),
+            "ntsc" => Some(VideoFilter::Ntsc),
+            "scanlines" => Some(VideoFilter::Scanlines),
+            "scale2x" => Some(VideoFilter::Scale2x),
+            "hq2x" => Some(VideoFilter::Hq2x),
+            "xbr" => Some(VideoFilter::Xbr),
+            _ => None,
+        }
+    }
+
+    /// Returns the filter after this one in `VideoFilter::ALL`, wrapping around at the end
+    pub fn next(&self) -> VideoFilter {
+        let i = VideoFilter::ALL.iter().position(|f| f == self).unwrap_or(0);
+        VideoFilter::ALL[(i + 1) % VideoFilter::ALL.len()]
+    }
+
+    /// Runs this filter on `frame`
+    pub fn apply(&self, frame: RgbaFrame) -> RgbaFrame {
+        match self {
+            VideoFilter::None => frame,
+            VideoFilter::Ntsc => ntsc(&frame),
+            VideoFilter::Scanlines => scanlines(&frame),
+            VideoFilter::Scale2x => scale2x(&frame, scale2x_corner),
+            VideoFilter::Hq2x => scale2x(&frame, hq2x_corner),
+            VideoFilter::Xbr => scale2x(&frame, xbr_corner),
+        }
+    }
+}
+
+/// Brightness of the darkened lines of the scanline filter, out of 256
+const SCANLINE_INTENSITY: u16 = 128;
+
+/// Horizontal kernel applied to luma, composite video has a limited bandwidth which softens the image a bit
+const NTSC_LUMA_KERNEL: [f32; 3] = [0.2, 0.6, 0.2];
+/// Horizontal kernel applied to chroma, chroma has a much lower bandwidth than luma and bleeds into neighbouring pixels
+const NTSC_CHROMA_KERNEL: [f32; 7] = [0.05, 0.1, 0.2, 0.3, 0.2, 0.1, 0.05];
+
+fn ntsc(frame: &RgbaFrame) -> RgbaFrame {
+    let yiq = frame.pixels.iter().map(|p| rgb_to_yiq(*p)).collect::<Vec<[f32; 3]>>();
+
+    let pixels = (0..frame.width * frame.height).map(|i| {
+        let (x, y) = ((i % frame.width) as isize, i / frame.width);
+        let sample = |dx: isize, channel: usize| {
+            let sx = (x + dx).clamp(0, frame.width as isize - 1) as usize;
+            yiq[y * frame.width + sx][channel]
+        };
+
+        let luma = NTSC_LUMA_KERNEL.iter().enumerate()
+            .map(|(k, w)| w * sample(k as isize - 1, 0))
+            .sum();
+        let [i_chroma, q_chroma] = [1, 2].map(|channel| NTSC_CHROMA_KERNEL.iter().enumerate()
+            .map(|(k, w)| w * sample(k as isize - 3, channel))
+            .sum());
+
+        yiq_to_rgb([luma, i_chroma, q_chroma])
+    }).collect();
+
+    RgbaFrame { width: frame.width, height: frame.height, pixels }
+}
+
+fn rgb_to_yiq(p: Pixel) -> [f32; 3] {
+    let [r, g, b] = [p[0], p[1], p[2]].map(|c| c as f32);
+    [
+        0.299 * r + 0.587 * g + 0.114 * b,
+        0.596 * r - 0.274 * g - 0.322 * b,
+        0.211 * r - 0.523 * g + 0.312 * b,
+    ]
+}
+
+fn yiq_to_rgb([y, i, q]: [f32; 3]) -> Pixel {
+    let clamp = |c: f32| c.round().clamp(0.0, 255.0) as u8;
+    [
+        clamp(y + 0.956 * i + 0.621 * q),
+        clamp(y - 0.272 * i - 0.647 * q),
+        clamp(y - 1.106 * i + 1.703 * q),
+        0xFF,
+    ]
+}
+
+fn scanlines(frame: &RgbaFrame) -> RgbaFrame {
+    let (width, height) = (frame.width * 2, frame.height * 2);
+
+    let pixels = (0..width * height).map(|i| {
+        let (x, y) = (i % width, i / width);
+        let p = frame.pixels[(y / 2) * frame.width + x / 2];
+        if y % 2 == 0 {
+            p
+        } else {
+            let dim = |c: u8| ((c as u16 * SCANLINE_INTENSITY) >> 8) as u8;
+            [dim(p[0]), dim(p[1]), dim(p[2]), p[3]]
+        }
+    }).collect();
+
+    RgbaFrame { width, height, pixels }
+}
+
+/// Doubles `frame`, every source pixel becomes 2x2 output pixels which are each computed by `corner`
+///
+/// `corner` gets a function that returns the source pixel at an offset from the current one, with the offsets
+/// mirrored so that positive `x` and `y` always point towards the corner being computed.
+/// This way every scaler only has to describe the bottom right corner
+fn scale2x(frame: &RgbaFrame, corner: fn(&dyn Fn(isize, isize) -> Pixel) -> Pixel) -> RgbaFrame {
+    let (width, height) = (frame.width * 2, frame.height * 2);
+    let mut pixels = vec![[0; 4]; width * height];
+
+    for y in 0..frame.height {
+        for x in 0..frame.width {
+            for (sx, sy) in [(-1, -1), (1, -1), (-1, 1), (1, 1)] {
+                let neighbour = |dx: isize, dy: isize| frame.get(x as isize + dx * sx, y as isize + dy * sy);
+                let out_x = 2 * x + (sx > 0) as usize;
+                let out_y = 2 * y + (sy > 0) as usize;
+                pixels[out_y * width + out_x] = corner(&neighbour);
+            }
+        }
+    }
+
+    RgbaFrame { width, height, pixels }
+}
+
+/// Scale2x: the corner takes the color of its two neighbours when they are equal and form an edge
+///
+/// Source: [Scale2x](https://www.scale2x.it/algorithm)
+fn scale2x_corner(p: &dyn Fn(isize, isize) -> Pixel) -> Pixel {
+    let (e, right, down, left, up) = (p(0, 0), p(1, 0), p(0, 1), p(-1, 0), p(0, -1));
+
+    if right == down && up != down && left != right {
+        right
+    } else {
+        e
+    }
+}
+
+/// Differences in YUV above which hq2x treats two pixels as different
+const HQ2X_THRESHOLD: [i32; 3] = [48, 7, 6];
+
+/// Simplified hq2x: instead of the full pattern table, only the two most common cases are handled.
+/// A corner between two similar neighbours that differ from the center is blended with them,
+/// a corner where only the diagonal neighbour differs is slightly blended with that neighbour
+///
+/// Source: [hq2x](https://en.wikipedia.org/wiki/Hqx)
+fn hq2x_corner(p: &dyn Fn(isize, isize) -> Pixel) -> Pixel {
+    let (e, right, down, diag) = (p(0, 0), p(1, 0), p(0, 1), p(1, 1));
+    let differs = |a: Pixel, b: Pixel| {
+        let (ya, yb) = (rgb_to_yuv(a), rgb_to_yuv(b));
+        (0..3).any(|c| (ya[c] - yb[c]).abs() > HQ2X_THRESHOLD[c])
+    };
+
+    if !differs(right, down) && differs(e, right) {
+        blend(&[(e, 2), (right, 1), (down, 1)])
+    } else if differs(e, diag) && !differs(e, right) && !differs(e, down) {
+        blend(&[(e, 3), (diag, 1)])
+    } else {
+        e
+    }
+}
+
+/// 2xBR: checks whether the edge through the corner runs along or across it by comparing weighted distances
+/// of the pixels around it, if it runs across the corner is blended with the closest neighbour
+///
+/// ```text
+///       A1 B1 C1
+///    A0 A  B  C  C4
+///    D0 D  E  F  F4
+///    G0 G  H  I  I4
+///       G5 H5 I5
+/// ```
+///
+/// Source: [xBR by Hyllian](https://forums.libretro.com/t/xbr-algorithm-tutorial/123)
+fn xbr_corner(p: &dyn Fn(isize, isize) -> Pixel) -> Pixel {
+    let (b, c, d, e, f, g, h, i) = (p(0, -1), p(1, -1), p(-1, 0), p(0, 0), p(1, 0), p(-1, 1), p(0, 1), p(1, 1));
+    let (f4, i4, h5, i5) = (p(2, 0), p(2, 1), p(0, 2), p(1, 2));
+
+    if e == f || e == h {
+        return e;
+    }
+
+    let across = yuv_distance(e, c) + yuv_distance(e, g) + yuv_distance(i, f4) + yuv_distance(i, h5) + 4 * yuv_distance(h, f);
+    let along = yuv_distance(h, d) + yuv_distance(h, i5) + yuv_distance(f, i4) + yuv_distance(f, b) + 4 * yuv_distance(e, i);
+
+    if across < along {
+        let closest = if yuv_distance(e, f) <= yuv_distance(e, h) { f } else { h };
+        blend(&[(e, 1), (closest, 1)])
+    } else {
+        e
+    }
+}
+
+fn rgb_to_yuv(p: Pixel) -> [i32; 3] {
+    let [r, g, b] = [p[0], p[1], p[2]].map(|c| c as i32);
+    [
+        (r + g + b) >> 2,
+        128 + ((r - b) >> 2),
+        128 + ((2 * g - r - b) >> 3),
+    ]
+}
+
+/// Weighted difference of two pixels in YUV, luma differences count the most
+fn yuv_distance(a: Pixel, b: Pixel) -> i32 {
+    let (ya, yb) = (rgb_to_yuv(a), rgb_to_yuv(b));
+    48 * (ya[0] - yb[0]).abs() + 7 * (ya[1] - yb[1]).abs() + 6 * (ya[2] - yb[2]).abs()
+}
+
+/// Weighted average of pixels, every pixel comes with its weight
+fn blend(weighted: &[(Pixel, u32)]) -> Pixel {
+    let total: u32 = weighted.iter().map(|(_, w)| w).sum();
+    let channel = |c: usize| (weighted.iter().map(|(p, w)| p[c] as u32 * w).sum::<u32>() / total) as u8;
+    [channel(0), channel(1), channel(2), channel(3)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const W: Pixel = [0xFF, 0xFF, 0xFF, 0xFF];
+    const K: Pixel = [0, 0, 0, 0xFF];
+
+    #[test]
+    fn test_scanlines_darkens_odd_rows() {
+        let frame = RgbaFrame { width: 1, height: 2, pixels: vec![[200, 100, 50, 0xFF], [10, 20, 30, 0xFF]] };
+        let out = scanlines(&frame);
+
+        assert_eq!((out.width, out.height), (2, 4));
+        assert_eq!(out.pixels, [
+            [200, 100, 50, 0xFF], [200, 100, 50, 0xFF],
+            [100, 50, 25, 0xFF], [100, 50, 25, 0xFF],
+            [10, 20, 30, 0xFF], [10, 20, 30, 0xFF],
+            [5, 10, 15, 0xFF], [5, 10, 15, 0xFF],
+        ]);
+    }
+
+    #[test]
+    fn test_scale2x_smooths_diagonal() {
+        let frame = RgbaFrame { width: 2, height: 2, pixels: vec![W, K, K, W] };
+        let out = scale2x(&frame, scale2x_corner);
+
+        assert_eq!((out.width, out.height), (4, 4));
+        assert_eq!(out.pixels, [
+            W, W, K, K,
+            W, K, W, K,
+            K, W, K, W,
+            K, K, W, W,
+        ]);
+    }
+
+    #[test]
+    fn test_scale2x_corner_keeps_center_without_edge() {
+        // Right and down match, but so does left, so there is no edge through the corner
+        let p = |dx: isize, dy: isize| if (dx, dy) == (0, 0) { W } else { K };
+        assert_eq!(scale2x_corner(&p), W);
+
+        let p = |dx: isize, dy: isize| if (dx, dy) == (1, 0) || (dx, dy) == (0, 1) { K } else { W };
+        assert_eq!(scale2x_corner(&p), K);
+    }
+}
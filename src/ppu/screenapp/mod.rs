@@ -1,18 +1,40 @@
-use pix_engine::{prelude::{Engine, PixResult, PixEngine, Color, Font}, state::PixState, shape::Point, random, line_};
-use pix_engine::color;
-use pix_engine::rgb;
+pub mod screenshot;
+pub mod presenter;
+pub mod filter;
+pub mod colorprofile;
 
-use super::{SCREEN_WIDTH, NTSC_SCREEN_HEIGHT, rgb::Rgba};
+use std::{path::Path, sync::{Arc, Mutex}};
+
+use pix_engine::prelude::PixResult;
+
+use crate::{arc_mut, savestate::{Snapshot, StateWriter, StateReader, SaveStateError, Thumbnail}};
+
+use self::{filter::{RgbaFrame, VideoFilter}, colorprofile::ColorProfile};
+
+use super::{SCREEN_WIDTH, NTSC_SCREEN_HEIGHT, PAL_SCREEN_HEIGHT, rgb::Rgba};
+
+/// Frame buffer of the PPU
+///
+/// The PPU draws into a back buffer pixel by pixel, once all visible scanlines are drawn
+/// the back buffer is copied to the completed frame, which is what gets presented and saved
 pub struct ScreenApp {
-    scanline: usize,
+    /// Back buffer, large enough for frames with overscan
     pixels: Vec<Rgba>,
+    /// Last completed frame, shared with the presenter. Always `SCREEN_WIDTH` wide, the height depends on overscan
+    frame: Arc<Mutex<Vec<Rgba>>>,
+    /// Filter applied to frames that are taken out of the frame buffer
+    filter: VideoFilter,
+    /// Conversion from PPU colors to the colors of frames that are taken out of the frame buffer
+    color_profile: ColorProfile,
 }
 
 impl ScreenApp {
     pub fn new() -> Self {
         ScreenApp {
-            scanline: 0,
-            pixels: vec![Rgba::default(); (NTSC_SCREEN_HEIGHT * SCREEN_WIDTH) as usize],
+            pixels: vec![Rgba::default(); PAL_SCREEN_HEIGHT * SCREEN_WIDTH],
+            frame: arc_mut!(vec![Rgba::default(); NTSC_SCREEN_HEIGHT * SCREEN_WIDTH]),
+            filter: VideoFilter::None,
+            color_profile: ColorProfile::Raw,
         }
     }
 
@@ -24,8 +46,8 @@ impl ScreenApp {
 
     /// Set pixels for a single scanline
     pub fn set_scanline(&mut self, scanline: usize, pix: &[Rgba]) {
-        let i = scanline * SCREEN_WIDTH as usize;
-        self.pixels[i..i+SCREEN_WIDTH as usize].clone_from_slice(pix);
+        let i = scanline * SCREEN_WIDTH;
+        self.pixels[i..i+SCREEN_WIDTH].clone_from_slice(pix);
     }
 
     /// Set all pixels at once
@@ -33,66 +55,107 @@ impl ScreenApp {
         self.pixels.clone_from_slice(pix);
     }
 
-    fn render_screen(&mut self, s: &mut PixState) {
-        self.pixels.iter().enumerate().for_each(|(i, rgb)| {
-            // Calculate x and y values and construct a Point
-            let (x, y) = (i % SCREEN_WIDTH, i / SCREEN_WIDTH);
-            let p = Point::new([x as i32, y as i32]);
+    /// Marks the first `lines` lines of the back buffer as a completed frame, called when the PPU enters V-blank
+    pub fn finish_frame(&mut self, lines: usize) {
+        let mut frame = self.frame.lock().unwrap();
+        frame.clear();
+        frame.extend_from_slice(&self.pixels[..lines * SCREEN_WIDTH]);
+    }
+
+    /// Returns a reference to the last completed frame, which gets updated by `finish_frame`
+    pub fn frame_ref(&self) -> Arc<Mutex<Vec<Rgba>>> {
+        self.frame.clone()
+    }
+
+    pub fn get_filter(&self) -> VideoFilter {
+        self.filter
+    }
+
+    /// Sets the filter that is applied to frames returned by `filtered_frame` and saved as screenshots
+    pub fn set_filter(&mut self, filter: VideoFilter) {
+        self.filter = filter;
+    }
+
+    pub fn get_color_profile(&self) -> ColorProfile {
+        self.color_profile
+    }
+
+    /// Sets the color profile that is used for frames returned by `filtered_frame` and saved as screenshots
+    pub fn set_color_profile(&mut self, profile: ColorProfile) {
+        self.color_profile = profile;
+    }
+
+    /// Returns the last completed frame converted with the current color profile and with the current filter applied
+    pub fn filtered_frame(&self) -> RgbaFrame {
+        let pixels = self.frame.lock().unwrap();
+        let frame = RgbaFrame::from_pixels(SCREEN_WIDTH, pixels.len() / SCREEN_WIDTH, &pixels, self.color_profile);
+        self.filter.apply(frame)
+    }
+
+    /// Returns the last completed frame as 8 bit RGBA bytes, row by row, with the current filter applied.
+    /// Every pixel is opaque
+    pub fn frame_rgba(&self) -> Vec<u8> {
+        self.filtered_frame().bytes().to_vec()
+    }
+
+    /// Returns the last completed frame as 8 bit RGB bytes, row by row, with the current filter applied
+    pub fn frame_rgb(&self) -> Vec<u8> {
+        self.filtered_frame().rgb_bytes()
+    }
+
+    /// Writes the last completed frame with the current filter applied to `path`,
+    /// the format is picked by the extension of `path`, see `ScreenshotFormat::from_path`
+    pub fn save_screenshot<P: AsRef<Path>>(&self, path: P) -> PixResult<()> {
+        screenshot::save_frame(&self.filtered_frame(), path)
+    }
+
+    /// Returns the last completed frame at half its size, as stored in the header of save states
+    pub fn thumbnail(&self) -> Thumbnail {
+        let frame = self.frame.lock().unwrap();
+        let (width, height) = (SCREEN_WIDTH / 2, frame.len() / SCREEN_WIDTH / 2);
+        let pixels = (0..width * height)
+            .map(|i| frame[(i / width) * 2 * SCREEN_WIDTH + (i % width) * 2].to_snes_palette())
+            .collect();
+
+        Thumbnail { width: width as u16, height: height as u16, pixels }
+    }
+}
+
+fn write_pixels(w: &mut StateWriter, pixels: &[Rgba]) {
+    for pix in pixels {
+        let (r, g, b, a) = pix.as_rgba_tuple();
+        w.write_bytes(&[r, g, b, a]);
+    }
+}
 
-            // Set color of pixel to be drawn
-            let (r, g, b, a) = rgb.as_highrange_rgba_tuple();
-            let c = color!(r, g, b, a);
-            s.stroke(c);
-            s.point(p);
-        });
+fn read_pixels(r: &mut StateReader, pixels: &mut [Rgba]) -> Result<(), SaveStateError> {
+    let mut rgba = [0; 4];
+    for pix in pixels.iter_mut() {
+        r.read_bytes(&mut rgba)?;
+        *pix = Rgba::new(rgba[0], rgba[1], rgba[2], rgba[3]);
     }
+    Ok(())
 }
 
-impl PixEngine for ScreenApp {
-    // Set up application state and initial settings. `PixState` contains
-    // engine specific state and utility methods for actions like getting mouse
-    // coordinates, drawing shapes, etc. (Optional)
-    fn on_start(&mut self, s: &mut PixState) -> PixResult<()> {
-        // Set the background to GRAY and clear the screen.
-        s.background(Color::BLACK);
-
-        // Returning `Err` instead of `Ok` would indicate initialization failed,
-        // and that the application should terminate immediately.
-        Ok(())
-    }
-
-    // Main update/render loop. Called as often as possible unless
-    // `target_frame_rate` was set with a value. (Required)
-    fn on_update(&mut self, s: &mut PixState) -> PixResult<()> {
-
-        self.render_screen(s);
-
-        let mut i: u16 = 0;
-        let mut j: u16 = 0;
-        let mut k: u16 = 0;
-        let mut l: u16 = 0;
-
-        let v = (0..SCREEN_WIDTH * NTSC_SCREEN_HEIGHT)
-        .map(|_| {
-            i = i.wrapping_add(1) % Rgba::MAX_RGB_VALUE as u16;
-            j = (random!(10) + i * 10) % Rgba::MAX_RGB_VALUE as u16;
-            k = (random!(1) + j * 6) % Rgba::MAX_RGB_VALUE as u16;
-            l = (random!(8) + k * 7) % Rgba::MAX_RGB_VALUE as u16;
-            Rgba::new(i as u8, j as u8, k as u8, l as u8)
-        })
-        .collect::<Vec<Rgba>>();
-
-        // self.set_scanline(self.scanline, &v);
-        self.set_pixels(&v);
-        self.scanline += 1;
-        self.scanline %= NTSC_SCREEN_HEIGHT as usize;
-        Ok(())
-    }
-
-    // Clean up any state or resources before exiting such as deleting temporary
-    // files or saving game state. (Optional)
-    fn on_stop(&mut self, s: &mut PixState) -> PixResult<()> {
-        // save sram data to file
-        Ok(())
-    }
-}
\ No newline at end of file
+/// Both the back buffer and the completed frame are saved, so a state loaded in the middle of a frame
+/// continues drawing where it was and the window shows the right frame right away.
+/// Filter and color profile are settings of the frontend and are not saved
+impl Snapshot for ScreenApp {
+    fn save_state(&self, w: &mut StateWriter) {
+        write_pixels(w, &self.pixels);
+        let frame = self.frame.lock().unwrap();
+        w.write_usize(frame.len() / SCREEN_WIDTH);
+        write_pixels(w, &frame);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), SaveStateError> {
+        read_pixels(r, &mut self.pixels)?;
+        let lines = r.read_usize()?;
+        if lines > PAL_SCREEN_HEIGHT {
+            return Err(SaveStateError::InvalidValue("frame height"));
+        }
+        let mut frame = self.frame.lock().unwrap();
+        frame.resize(lines * SCREEN_WIDTH, Rgba::default());
+        read_pixels(r, &mut frame)
+    }
+}
@@ -0,0 +1,198 @@
+use std::{sync::{Arc, Mutex, mpsc::Sender, atomic::{AtomicBool, Ordering}}, time::{SystemTime, UNIX_EPOCH}};
+
+use pix_engine::prelude::{Color, Key, KeyEvent, KeyMod, PixEngine, PixResult, PixState, PixelFormat, Rect, TextureId};
+
+use crate::{ppu::{SCREEN_WIDTH, rgb::Rgba}, savestate::slots::SlotRequest};
+
+use super::{screenshot, filter::{RgbaFrame, VideoFilter}, colorprofile::ColorProfile};
+
+/// SNES pixels are not square, on a TV they are 8/7 times as wide as they are high
+const PIXEL_ASPECT: f32 = 8.0 / 7.0;
+
+/// Window frontend that shows the completed frames of the PPU
+///
+/// Every update the last completed frame is uploaded to a single texture, which is drawn scaled to the window.
+/// * `A` toggles 8:7 pixel aspect ratio correction
+/// * `I` toggles integer scaling, when on the frame is only scaled by whole multiples vertically
+/// * `F` switches to the next video filter
+/// * `C` switches to the next color profile
+/// * `F12` saves a screenshot to the working directory
+/// * `F1` to `F4` quick save to slot 1 to 4, `Shift` + `F1` to `F4` quick load
+/// * Holding `Backspace` rewinds
+pub struct Presenter {
+    frame: Arc<Mutex<Vec<Rgba>>>,
+    /// Texture the frame is uploaded to, with its size. Filters can change the size of the frame
+    texture: Option<(TextureId, usize, usize)>,
+    pub filter: VideoFilter,
+    pub color_profile: ColorProfile,
+    pub aspect_correction: bool,
+    pub integer_scaling: bool,
+    /// Where quick save and quick load requests are sent, they are ignored if this is not set
+    slot_requests: Option<Sender<SlotRequest>>,
+    /// Set while the rewind key is held
+    rewinding: Option<Arc<AtomicBool>>,
+}
+
+impl Presenter {
+    /// Creates a presenter for a frame buffer, see `ScreenApp::frame_ref`
+    pub fn new(frame: Arc<Mutex<Vec<Rgba>>>, filter: VideoFilter, color_profile: ColorProfile) -> Presenter {
+        Presenter {
+            frame,
+            texture: None,
+            filter,
+            color_profile,
+            aspect_correction: true,
+            integer_scaling: true,
+            slot_requests: None,
+            rewinding: None,
+        }
+    }
+
+    /// Sends quick save and quick load requests to `sender`, the emulation loop should handle them between frames
+    pub fn set_slot_requests(&mut self, sender: Sender<SlotRequest>) {
+        self.slot_requests = Some(sender);
+    }
+
+    /// Sets `flag` to true while the rewind key is held and back to false when it is released
+    pub fn set_rewind_flag(&mut self, flag: Arc<AtomicBool>) {
+        self.rewinding = Some(flag);
+    }
+
+    /// Returns the last completed frame converted with the current color profile and with the current filter applied
+    fn filtered_frame(&self) -> RgbaFrame {
+        let pixels = self.frame.lock().unwrap();
+        let frame = RgbaFrame::from_pixels(SCREEN_WIDTH, pixels.len() / SCREEN_WIDTH, &pixels, self.color_profile);
+        self.filter.apply(frame)
+    }
+
+    /// Size of the window that shows a frame of `lines` scanlines at `scale` times its original height
+    pub fn window_size(&self, scale: u32, lines: usize) -> (u32, u32) {
+        let height = lines as u32 * scale;
+        (self.display_width(height as f32, lines) as u32, height)
+    }
+
+    /// Width a frame of `lines` scanlines is drawn at when it is `height` pixels high
+    fn display_width(&self, height: f32, lines: usize) -> f32 {
+        let aspect = if self.aspect_correction { PIXEL_ASPECT } else { 1.0 };
+        height * SCREEN_WIDTH as f32 * aspect / lines as f32
+    }
+
+    /// Returns the rectangle a frame of `lines` scanlines is drawn in, centered in a window of `window_width` by `window_height`
+    fn target_rect(&self, window_width: u32, window_height: u32, lines: usize) -> Rect<i32> {
+        let (window_width, window_height) = (window_width as f32, window_height as f32);
+
+        // Largest height at which the frame still fits in the window
+        let mut height = window_height.min(window_width * lines as f32 / self.display_width(lines as f32, lines));
+        if self.integer_scaling {
+            let scale = (height / lines as f32).floor().max(1.0);
+            height = scale * lines as f32;
+        }
+        let width = self.display_width(height, lines);
+
+        Rect::new(
+            ((window_width - width) / 2.0) as i32,
+            ((window_height - height) / 2.0) as i32,
+            width as i32,
+            height as i32,
+        )
+    }
+}
+
+impl PixEngine for Presenter {
+    fn on_start(&mut self, s: &mut PixState) -> PixResult<()> {
+        s.background(Color::BLACK);
+        Ok(())
+    }
+
+    fn on_update(&mut self, s: &mut PixState) -> PixResult<()> {
+        let frame = self.filtered_frame();
+
+        let texture = match self.texture {
+            Some((texture, width, height)) if width == frame.width && height == frame.height => texture,
+            old => {
+                if let Some((texture, _, _)) = old {
+                    s.delete_texture(texture)?;
+                }
+                let texture = s.create_texture(frame.width as u32, frame.height as u32, PixelFormat::Rgba)?;
+                self.texture = Some((texture, frame.width, frame.height));
+                texture
+            },
+        };
+
+        s.update_texture(texture, None, frame.bytes(), frame.width * 4)?;
+
+        let (window_width, window_height) = s.window_dimensions()?;
+        s.clear()?;
+        let lines = self.frame.lock().unwrap().len() / SCREEN_WIDTH;
+        s.texture(texture, None, self.target_rect(window_width, window_height, lines))
+    }
+
+    fn on_key_pressed(&mut self, s: &mut PixState, event: KeyEvent) -> PixResult<bool> {
+        match event.key {
+            Key::A => self.aspect_correction = !self.aspect_correction,
+            Key::I => self.integer_scaling = !self.integer_scaling,
+            Key::F => {
+                self.filter = self.filter.next();
+                show_status(s, &format!("Video filter: {:?}", self.filter))?;
+            },
+            Key::C => {
+                self.color_profile = self.color_profile.next();
+                show_status(s, &format!("Color profile: {:?}", self.color_profile))?;
+            },
+            Key::F12 => {
+                let secs = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+                let path = format!("screenshot_{secs}.png");
+                match screenshot::save_frame(&self.filtered_frame(), &path) {
+                    Ok(()) => show_status(s, &format!("Saved screenshot to {path}"))?,
+                    Err(e) => {
+                        eprintln!("Failed to save screenshot to {path}: {e}");
+                        show_status(s, "Failed to save screenshot")?;
+                    },
+                }
+            },
+            Key::Backspace => {
+                if let Some(flag) = &self.rewinding {
+                    flag.store(true, Ordering::Relaxed);
+                }
+            },
+            Key::F1 | Key::F2 | Key::F3 | Key::F4 => {
+                let slot = match event.key {
+                    Key::F1 => 1,
+                    Key::F2 => 2,
+                    Key::F3 => 3,
+                    _ => 4,
+                };
+                let request = if event.keymod.intersects(KeyMod::SHIFT) { SlotRequest::Load(slot) } else { SlotRequest::Save(slot) };
+                if let Some(sender) = &self.slot_requests {
+                    // The emulation thread only stops after the window is closed, so sending can't fail while keys are handled
+                    let _ = sender.send(request);
+                }
+            },
+            _ => return Ok(false),
+        }
+
+        Ok(true)
+    }
+
+    fn on_key_released(&mut self, _s: &mut PixState, event: KeyEvent) -> PixResult<bool> {
+        match (event.key, &self.rewinding) {
+            (Key::Backspace, Some(flag)) => {
+                flag.store(false, Ordering::Relaxed);
+                Ok(true)
+            },
+            _ => Ok(false),
+        }
+    }
+
+    fn on_stop(&mut self, s: &mut PixState) -> PixResult<()> {
+        if let Some((texture, _, _)) = self.texture.take() {
+            s.delete_texture(texture)?;
+        }
+        Ok(())
+    }
+}
+
+/// Shows `status` in the window title, after the name of the emulator
+fn show_status(s: &mut PixState, status: &str) -> PixResult<()> {
+    s.set_title(format!("snesemu - {status}"))
+}
@@ -0,0 +1,54 @@
+use std::{fs, io, path::Path};
+
+use pix_engine::prelude::{Image, PixResult, PixelFormat};
+
+use super::filter::RgbaFrame;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// File format of a screenshot
+pub enum ScreenshotFormat {
+    Png,
+    /// Binary PPM (`P6`), an RGB image with a tiny text header
+    Ppm,
+    /// Only the RGB bytes of all pixels, row by row, without header
+    RawRgb,
+}
+
+impl ScreenshotFormat {
+    /// Picks the format based on the extension of `path`: `.png`, `.ppm` or `.rgb`/`.raw`
+    pub fn from_path(path: &Path) -> Option<ScreenshotFormat> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "png" => Some(ScreenshotFormat::Png),
+            "ppm" => Some(ScreenshotFormat::Ppm),
+            "rgb" | "raw" => Some(ScreenshotFormat::RawRgb),
+            _ => None,
+        }
+    }
+}
+
+/// Writes `frame` to `path`, the format is picked by the extension of `path`, see `ScreenshotFormat::from_path`
+pub fn save_frame<P: AsRef<Path>>(frame: &RgbaFrame, path: P) -> PixResult<()> {
+    let path = path.as_ref();
+    let format = ScreenshotFormat::from_path(path).ok_or_else(|| io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("unknown screenshot format: {path:?}, use .png, .ppm, .rgb or .raw"),
+    ))?;
+
+    save_frame_as(frame, path, format)
+}
+
+/// Writes `frame` to `path` as `format`
+pub fn save_frame_as<P: AsRef<Path>>(frame: &RgbaFrame, path: P, format: ScreenshotFormat) -> PixResult<()> {
+    match format {
+        ScreenshotFormat::Png => {
+            Image::from_vec(frame.width as u32, frame.height as u32, frame.bytes().to_vec(), PixelFormat::Rgba).save(path)
+        },
+        ScreenshotFormat::Ppm => {
+            let mut bytes = format!("P6\n{} {}\n255\n", frame.width, frame.height).into_bytes();
+            bytes.extend(frame.rgb_bytes());
+            Ok(fs::write(path, bytes)?)
+        },
+        ScreenshotFormat::RawRgb => Ok(fs::write(path, frame.rgb_bytes())?),
+    }
+}
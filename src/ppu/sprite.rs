@@ -1,5 +1,9 @@
+use crate::savestate::{Snapshot, StateWriter, StateReader, SaveStateError};
+
 #[derive(Debug, Clone)]
 pub struct Sprite {
+    /// Index of this sprite in OAM
+    pub index: usize,
     /// X position of sprite
     pub x: usize,
     /// Y position of sprite
@@ -21,6 +25,7 @@ pub struct Sprite {
 impl Sprite {
     pub fn new() -> Sprite {
         Sprite {
+            index: 0,
             x: 0,
             y: 0,
             priority: 0,
@@ -31,4 +36,38 @@ impl Sprite {
             big_size: false,
         }
     }
+
+    /// X position as a signed number, the 9 bit x position wraps around from `255` to `-256`
+    pub fn signed_x(&self) -> isize {
+        if self.x >= 256 {
+            self.x as isize - 512
+        } else {
+            self.x as isize
+        }
+    }
+}
+
+impl Snapshot for Sprite {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_usize(self.index);
+        w.write_usize(self.x);
+        w.write_usize(self.y);
+        w.write_usize(self.priority);
+        w.write_usize(self.tile_index);
+        w.write_usize(self.palette);
+        w.write_bools(&[self.flip_v, self.flip_h, self.big_size]);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), SaveStateError> {
+        self.index = r.read_usize()? % 128;
+        self.x = r.read_usize()? & 0x1FF;
+        self.y = r.read_usize()? & 0xFF;
+        self.priority = r.read_usize()? & 0x3;
+        self.tile_index = r.read_usize()? & 0x1FF;
+        self.palette = r.read_usize()? & 0x7;
+        let mut flags = [false; 3];
+        r.read_bools(&mut flags)?;
+        [self.flip_v, self.flip_h, self.big_size] = flags;
+        Ok(())
+    }
 }
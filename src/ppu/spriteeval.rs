@@ -0,0 +1,175 @@
+use crate::{bit_set, savestate::{Snapshot, StateWriter, StateReader, SaveStateError}};
+
+use super::{Ppu, sprite::Sprite, SCREEN_WIDTH};
+
+/// Maximum number of sprites that can be in range of a single scanline
+pub const MAX_SPRITES_PER_LINE: usize = 32;
+/// Maximum number of 8 pixel wide sprite slivers that can be loaded for a single scanline
+pub const MAX_SLIVERS_PER_LINE: usize = 34;
+
+#[derive(Debug, Clone)]
+/// Sprite that is in range of the current scanline
+pub struct LineSprite {
+    pub sprite: Sprite,
+    /// Bit `n` is set if the `n`th 8 pixel wide sliver (counted from the left of the sprite) was loaded during time evaluation
+    pub loaded_slivers: u8,
+}
+
+impl Snapshot for LineSprite {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.sprite.save_state(w);
+        w.write_u8(self.loaded_slivers);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), SaveStateError> {
+        self.sprite.load_state(r)?;
+        self.loaded_slivers = r.read_u8()?;
+        Ok(())
+    }
+}
+
+impl Ppu {
+    /// Selects the sprites that are drawn on the current scanline, like the PPU does during H-blank of the previous line
+    /// 
+    /// * Range evaluation: the first 32 sprites (in priority order) that are on this scanline are selected,
+    ///   if there are more the range over flag is set
+    /// * Time evaluation: the selected sprites are processed in reverse order and their on screen slivers are loaded,
+    ///   if there are more than 34 slivers the time over flag is set and the remaining slivers (of the highest priority sprites) are dropped
+    /// 
+    /// Source: [snes wiki](https://snes.nesdev.org/wiki/Sprites)
+    pub fn evaluate_sprites(&mut self) {
+        let mut mem = self.memory.lock().unwrap();
+        let line = self.scanline.y;
+
+        // Range evaluation
+        let mut line_sprites: Vec<LineSprite> = Vec::with_capacity(MAX_SPRITES_PER_LINE);
+        for s in mem.oam.as_sprites() {
+            let (width, height) = mem.oam.get_sprite_size(s.big_size);
+            if !sprite_in_range(&s, width, height, line) {
+                continue;
+            }
+
+            if line_sprites.len() == MAX_SPRITES_PER_LINE {
+                mem.oam.range_over = true;
+                break;
+            }
+
+            line_sprites.push(LineSprite { sprite: s, loaded_slivers: 0 });
+        }
+
+        // The PPU leaves the internal OAM address at the last sprite it evaluated
+        let last_evaluated = match line_sprites.last() {
+            Some(line_sprite) => line_sprite.sprite.index,
+            None => mem.oam.highest_prio_obj + 127,
+        };
+        mem.oam.set_render_address(last_evaluated);
+
+        // Time evaluation
+        let mut sliver_count = 0;
+        'time: for line_sprite in line_sprites.iter_mut().rev() {
+            let (width, _) = mem.oam.get_sprite_size(line_sprite.sprite.big_size);
+            let onscreen = onscreen_slivers(&line_sprite.sprite, width);
+            for col in 0..width / 8 {
+                if !bit_set!(onscreen, col) {
+                    continue;
+                }
+
+                if sliver_count == MAX_SLIVERS_PER_LINE {
+                    mem.oam.time_over = true;
+                    break 'time;
+                }
+
+                line_sprite.loaded_slivers |= 1 << col;
+                sliver_count += 1;
+            }
+        }
+
+        self.line_sprites = line_sprites;
+    }
+}
+
+/// Returns true if sprite `s` is on scanline `line`
+/// 
+/// Sprites wrap around vertically at 256. Horizontally a sprite has to be at least partially on screen,
+/// except for sprites at x = -256 which are always considered in range
+pub(super) fn sprite_in_range(s: &Sprite, width: usize, height: usize, line: usize) -> bool {
+    let in_y_range = line.wrapping_sub(s.y) & 0xFF < height;
+
+    let x = s.signed_x();
+    let in_x_range = x == -256 || (x > -(width as isize) && x < SCREEN_WIDTH as isize);
+
+    in_x_range && in_y_range
+}
+
+/// Returns a mask where bit `n` is set if the `n`th 8 pixel wide sliver of sprite `s` is (partially) on screen,
+/// only these slivers are loaded during time evaluation
+pub(super) fn onscreen_slivers(s: &Sprite, width: usize) -> u8 {
+    (0..width / 8).fold(0, |mask, col| {
+        let sliver_x = s.signed_x() + 8 * col as isize;
+        if sliver_x <= -8 || sliver_x >= SCREEN_WIDTH as isize {
+            mask
+        } else {
+            mask | 1 << col
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sprite_at(x: usize, y: usize) -> Sprite {
+        let mut s = Sprite::new();
+        s.x = x;
+        s.y = y;
+        s
+    }
+
+    #[test]
+    fn test_range_limit() {
+        // OAM starts zeroed, so all 128 sprites are 8x8 at (0, 0)
+        let mut ppu = Ppu::new();
+        ppu.evaluate_sprites();
+
+        let indices: Vec<usize> = ppu.line_sprites.iter().map(|s| s.sprite.index).collect();
+        assert_eq!(indices, (0..MAX_SPRITES_PER_LINE).collect::<Vec<usize>>());
+        assert!(ppu.line_sprites.iter().all(|s| s.loaded_slivers == 0b1));
+        assert_eq!(ppu.memory.lock().unwrap().oam.read_stat77(), 0x41);
+    }
+
+    #[test]
+    fn test_time_limit_drops_highest_priority_slivers() {
+        // 32 sprites of 16x16 in range are 64 slivers
+        let mut ppu = Ppu::new();
+        ppu.memory.lock().unwrap().oam.smallobj_size = (16, 16);
+        ppu.evaluate_sprites();
+
+        // Slivers are loaded starting from the last sprite, so the first sprites miss out
+        let loaded: Vec<u8> = ppu.line_sprites.iter().map(|s| s.loaded_slivers).collect();
+        assert_eq!(loaded[..15], [0; 15]);
+        assert_eq!(loaded[15..], [0b11; 17]);
+        assert_eq!(ppu.memory.lock().unwrap().oam.read_stat77(), 0xC1);
+    }
+
+    #[test]
+    fn test_sprite_in_range() {
+        assert!(sprite_in_range(&sprite_at(0, 10), 8, 8, 17));
+        assert!(!sprite_in_range(&sprite_at(0, 10), 8, 8, 18));
+        // Sprites wrap around vertically
+        assert!(sprite_in_range(&sprite_at(0, 250), 16, 16, 4));
+        // Sprites left of the screen are out of range, except at x = -256
+        assert!(!sprite_in_range(&sprite_at(0x1F8, 0), 8, 8, 0));
+        assert!(sprite_in_range(&sprite_at(0x1F9, 0), 8, 8, 0));
+        assert!(sprite_in_range(&sprite_at(0x100, 0), 8, 8, 0));
+    }
+
+    #[test]
+    fn test_onscreen_slivers() {
+        assert_eq!(onscreen_slivers(&sprite_at(0, 0), 32), 0b1111);
+        // At x = -8 the left sliver is off screen, at x = 248 only the left sliver is on screen
+        assert_eq!(onscreen_slivers(&sprite_at(0x1F8, 0), 16), 0b10);
+        assert_eq!(onscreen_slivers(&sprite_at(248, 0), 16), 0b01);
+        // Sprites at x = -256 are in range but have no slivers on screen
+        assert_eq!(onscreen_slivers(&sprite_at(0x100, 0), 16), 0);
+    }
+}
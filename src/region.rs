@@ -0,0 +1,93 @@
+use std::time::Duration;
+
+use crate::{cpu::memory::cartridge::CartridgeRegion, savestate::{Snapshot, StateWriter, StateReader, SaveStateError}, ppu::scanline::{HOR_SCANLINES, NTSC_VER_SCANLINES, PAL_VER_SCANLINES}};
+
+/// Master clock cycles per PPU dot
+pub const MASTER_CLOCKS_PER_DOT: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// Video standard of the console, this decides the timing of the whole system
+pub enum Region {
+    /// 60 Hz, 262 scanlines per frame
+    #[default]
+    Ntsc,
+    /// 50 Hz, 312 scanlines per frame
+    Pal,
+}
+
+impl Region {
+    /// Number of scanlines in a frame, including V-blank
+    pub fn scanlines(&self) -> usize {
+        match self {
+            Region::Ntsc => NTSC_VER_SCANLINES,
+            Region::Pal => PAL_VER_SCANLINES,
+        }
+    }
+
+    /// Frequency of the master clock in Hz, all other clocks are derived from it
+    pub fn master_clock_hz(&self) -> u32 {
+        match self {
+            Region::Ntsc => 21_477_272,
+            Region::Pal => 21_281_370,
+        }
+    }
+
+    /// Master clock cycles in a frame, including V-blank
+    pub fn master_clocks_per_frame(&self) -> usize {
+        self.scanlines() * HOR_SCANLINES * MASTER_CLOCKS_PER_DOT
+    }
+
+    /// Duration of a single frame at the master clock frequency, about 1/60 s for NTSC and 1/50 s for PAL
+    pub fn frame_time(&self) -> Duration {
+        Duration::from_secs_f64(self.master_clocks_per_frame() as f64 / self.master_clock_hz() as f64)
+    }
+
+    /// PAL games usually use overscan to fill the taller picture, NTSC games usually don't
+    pub fn default_overscan(&self) -> bool {
+        matches!(self, Region::Pal)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// Region selected by the user, `Auto` picks the region that belongs to the inserted cartridge
+pub enum RegionSetting {
+    #[default]
+    Auto,
+    Ntsc,
+    Pal,
+}
+
+impl RegionSetting {
+    /// Parses a setting from its lowercase name, as used on the command line
+    pub fn from_name(name: &str) -> Option<RegionSetting> {
+        match name {
+            "auto" => Some(RegionSetting::Auto),
+            "ntsc" => Some(RegionSetting::Ntsc),
+            "pal" => Some(RegionSetting::Pal),
+            _ => None,
+        }
+    }
+
+    /// Returns the region to emulate for a cartridge made for `cartridge_region`
+    pub fn resolve(&self, cartridge_region: &CartridgeRegion) -> Region {
+        match self {
+            RegionSetting::Auto => cartridge_region.video_region(),
+            RegionSetting::Ntsc => Region::Ntsc,
+            RegionSetting::Pal => Region::Pal,
+        }
+    }
+}
+
+impl Snapshot for Region {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u8(*self as u8);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), SaveStateError> {
+        *self = match r.read_index(2, "region")? {
+            0 => Region::Ntsc,
+            _ => Region::Pal,
+        };
+        Ok(())
+    }
+}
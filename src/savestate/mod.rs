@@ -0,0 +1,342 @@
+pub mod slots;
+pub mod rewind;
+
+use std::{fmt, io, time::{SystemTime, UNIX_EPOCH}};
+
+/// First bytes of every save state file
+pub const SAVESTATE_MAGIC: [u8; 4] = *b"SNSS";
+/// Version of the save state format, increased whenever the layout of any snapshot changes
+pub const SAVESTATE_VERSION: u16 = 3;
+
+#[derive(Debug)]
+pub enum SaveStateError {
+    Io(io::Error),
+    /// File does not start with `SAVESTATE_MAGIC`
+    InvalidMagic,
+    /// File was made by a different version of the format
+    UnsupportedVersion(u16),
+    /// Save state was made with a different ROM
+    RomMismatch { expected: u16, found: u16 },
+    /// File ended before all state was read
+    UnexpectedEof,
+    /// A value in the file is out of range, contains the name of the value
+    InvalidValue(&'static str),
+}
+
+impl fmt::Display for SaveStateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveStateError::Io(e) => write!(f, "{e}"),
+            SaveStateError::InvalidMagic => write!(f, "not a save state"),
+            SaveStateError::UnsupportedVersion(v) => write!(f, "unsupported save state version {v}, expected {SAVESTATE_VERSION}"),
+            SaveStateError::RomMismatch { expected, found } => {
+                write!(f, "save state was made for ROM with checksum ${found:04X}, loaded ROM has checksum ${expected:04X}")
+            },
+            SaveStateError::UnexpectedEof => write!(f, "save state is truncated"),
+            SaveStateError::InvalidValue(name) => write!(f, "invalid value for {name} in save state"),
+        }
+    }
+}
+
+impl From<io::Error> for SaveStateError {
+    fn from(e: io::Error) -> Self {
+        SaveStateError::Io(e)
+    }
+}
+
+/// State that can be written to and restored from a save state
+///
+/// `load_state` has to read exactly what `save_state` wrote, in the same order
+pub trait Snapshot {
+    fn save_state(&self, w: &mut StateWriter);
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), SaveStateError>;
+}
+
+/// Serializes state as little endian binary
+pub struct StateWriter {
+    bytes: Vec<u8>,
+}
+
+impl StateWriter {
+    pub fn new() -> StateWriter {
+        StateWriter { bytes: Vec::new() }
+    }
+
+    /// Creates a writer with room for `capacity` bytes, snapshots taken every frame have the same size so this avoids reallocating
+    pub fn with_capacity(capacity: usize) -> StateWriter {
+        StateWriter { bytes: Vec::with_capacity(capacity) }
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    pub fn write_u8(&mut self, v: u8) {
+        self.bytes.push(v);
+    }
+
+    pub fn write_bool(&mut self, v: bool) {
+        self.write_u8(v as u8);
+    }
+
+    pub fn write_u16(&mut self, v: u16) {
+        self.bytes.extend_from_slice(&v.to_le_bytes());
+    }
+
+    pub fn write_u32(&mut self, v: u32) {
+        self.bytes.extend_from_slice(&v.to_le_bytes());
+    }
+
+    pub fn write_u64(&mut self, v: u64) {
+        self.bytes.extend_from_slice(&v.to_le_bytes());
+    }
+
+    /// `usize` is always stored as 64 bits, so save states work across platforms
+    pub fn write_usize(&mut self, v: usize) {
+        self.write_u64(v as u64);
+    }
+
+    /// Writes bytes of a fixed size buffer, the length is not stored
+    pub fn write_bytes(&mut self, v: &[u8]) {
+        self.bytes.extend_from_slice(v);
+    }
+
+    /// Writes words of a fixed size buffer, the length is not stored
+    pub fn write_words(&mut self, v: &[u16]) {
+        self.bytes.extend(v.iter().flat_map(|w| w.to_le_bytes()));
+    }
+
+    /// Writes flags of a fixed size array, one byte per flag
+    pub fn write_bools(&mut self, v: &[bool]) {
+        v.iter().for_each(|b| self.write_bool(*b));
+    }
+
+    /// Writes a buffer of variable size, prefixed by its length
+    pub fn write_vec(&mut self, v: &[u8]) {
+        self.write_usize(v.len());
+        self.write_bytes(v);
+    }
+}
+
+/// Reads state written by `StateWriter`
+pub struct StateReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> StateReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> StateReader<'a> {
+        StateReader { bytes, pos: 0 }
+    }
+
+    /// Returns all bytes that have not been read yet
+    pub fn remaining(&self) -> &'a [u8] {
+        &self.bytes[self.pos..]
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], SaveStateError> {
+        let end = self.pos.checked_add(n).filter(|end| *end <= self.bytes.len()).ok_or(SaveStateError::UnexpectedEof)?;
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, SaveStateError> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub fn read_bool(&mut self) -> Result<bool, SaveStateError> {
+        match self.read_u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(SaveStateError::InvalidValue("bool")),
+        }
+    }
+
+    pub fn read_u16(&mut self) -> Result<u16, SaveStateError> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    pub fn read_u32(&mut self) -> Result<u32, SaveStateError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    pub fn read_u64(&mut self) -> Result<u64, SaveStateError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    pub fn read_usize(&mut self) -> Result<usize, SaveStateError> {
+        usize::try_from(self.read_u64()?).map_err(|_| SaveStateError::InvalidValue("usize"))
+    }
+
+    /// Fills `buf` completely, counterpart of `StateWriter::write_bytes`
+    pub fn read_bytes(&mut self, buf: &mut [u8]) -> Result<(), SaveStateError> {
+        buf.copy_from_slice(self.take(buf.len())?);
+        Ok(())
+    }
+
+    /// Fills `buf` completely, counterpart of `StateWriter::write_words`
+    pub fn read_words(&mut self, buf: &mut [u16]) -> Result<(), SaveStateError> {
+        let bytes = self.take(buf.len() * 2)?;
+        for (w, b) in buf.iter_mut().zip(bytes.chunks_exact(2)) {
+            *w = u16::from_le_bytes([b[0], b[1]]);
+        }
+        Ok(())
+    }
+
+    /// Fills `buf` completely, counterpart of `StateWriter::write_bools`
+    pub fn read_bools(&mut self, buf: &mut [bool]) -> Result<(), SaveStateError> {
+        for b in buf.iter_mut() {
+            *b = self.read_bool()?;
+        }
+        Ok(())
+    }
+
+    /// Counterpart of `StateWriter::write_vec`
+    pub fn read_vec(&mut self) -> Result<Vec<u8>, SaveStateError> {
+        let len = self.read_usize()?;
+        Ok(self.take(len)?.to_vec())
+    }
+
+    /// Reads a value that has to be lower than `count`, used for enums. `name` is reported when the value is out of range
+    pub fn read_index(&mut self, count: u8, name: &'static str) -> Result<u8, SaveStateError> {
+        match self.read_u8()? {
+            i if i < count => Ok(i),
+            _ => Err(SaveStateError::InvalidValue(name)),
+        }
+    }
+}
+
+/// Downscaled copy of the frame at the time the state was saved, colors are BGR555 like in CGRAM
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Thumbnail {
+    pub width: u16,
+    pub height: u16,
+    pub pixels: Vec<u16>,
+}
+
+/// Information stored at the start of every save state
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SaveStateHeader {
+    pub version: u16,
+    /// Checksum from the header of the ROM the state was made with
+    pub rom_checksum: u16,
+    /// Seconds since the unix epoch at the time of saving
+    pub timestamp: u64,
+    pub thumbnail: Thumbnail,
+}
+
+impl SaveStateHeader {
+    pub fn new(rom_checksum: u16, thumbnail: Thumbnail) -> SaveStateHeader {
+        SaveStateHeader {
+            version: SAVESTATE_VERSION,
+            rom_checksum,
+            timestamp: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+            thumbnail,
+        }
+    }
+
+    pub fn write(&self, w: &mut StateWriter) {
+        w.write_bytes(&SAVESTATE_MAGIC);
+        w.write_u16(self.version);
+        w.write_u16(self.rom_checksum);
+        w.write_u64(self.timestamp);
+        w.write_u16(self.thumbnail.width);
+        w.write_u16(self.thumbnail.height);
+        w.write_words(&self.thumbnail.pixels);
+    }
+
+    /// Reads the header and checks magic and version, the rest of the state is not touched
+    pub fn read(r: &mut StateReader) -> Result<SaveStateHeader, SaveStateError> {
+        let mut magic = [0; 4];
+        r.read_bytes(&mut magic)?;
+        if magic != SAVESTATE_MAGIC {
+            return Err(SaveStateError::InvalidMagic);
+        }
+
+        let version = r.read_u16()?;
+        if version != SAVESTATE_VERSION {
+            return Err(SaveStateError::UnsupportedVersion(version));
+        }
+
+        let rom_checksum = r.read_u16()?;
+        let timestamp = r.read_u64()?;
+        let width = r.read_u16()?;
+        let height = r.read_u16()?;
+        // Checked before allocating, so a corrupt size can not allocate more than the file holds
+        if width as usize * height as usize * 2 > r.remaining().len() {
+            return Err(SaveStateError::InvalidValue("thumbnail size"));
+        }
+        let mut pixels = vec![0; width as usize * height as usize];
+        r.read_words(&mut pixels)?;
+
+        Ok(SaveStateHeader { version, rom_checksum, timestamp, thumbnail: Thumbnail { width, height, pixels } })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header() -> SaveStateHeader {
+        SaveStateHeader::new(0x1234, Thumbnail { width: 2, height: 1, pixels: vec![0x7FFF, 0x001F] })
+    }
+
+    #[test]
+    fn test_writer_reader_round_trip() {
+        let mut w = StateWriter::new();
+        w.write_u8(0x12);
+        w.write_bool(true);
+        w.write_u16(0x3456);
+        w.write_u32(0x789ABCDE);
+        w.write_usize(usize::MAX >> 1);
+        w.write_words(&[0x1111, 0x2222]);
+        w.write_bools(&[false, true]);
+        w.write_vec(&[1, 2, 3]);
+        let bytes = w.into_bytes();
+
+        let mut r = StateReader::new(&bytes);
+        assert_eq!(r.read_u8().unwrap(), 0x12);
+        assert!(r.read_bool().unwrap());
+        assert_eq!(r.read_u16().unwrap(), 0x3456);
+        assert_eq!(r.read_u32().unwrap(), 0x789ABCDE);
+        assert_eq!(r.read_usize().unwrap(), usize::MAX >> 1);
+        let mut words = [0; 2];
+        r.read_words(&mut words).unwrap();
+        assert_eq!(words, [0x1111, 0x2222]);
+        let mut bools = [true, false];
+        r.read_bools(&mut bools).unwrap();
+        assert_eq!(bools, [false, true]);
+        assert_eq!(r.read_vec().unwrap(), [1, 2, 3]);
+        assert!(r.remaining().is_empty());
+        assert!(matches!(r.read_u8(), Err(SaveStateError::UnexpectedEof)));
+    }
+
+    #[test]
+    fn test_header_round_trip() {
+        let mut w = StateWriter::new();
+        header().write(&mut w);
+        let bytes = w.into_bytes();
+        assert_eq!(SaveStateHeader::read(&mut StateReader::new(&bytes)).unwrap(), header());
+    }
+
+    #[test]
+    fn test_header_rejects_invalid_states() {
+        let mut w = StateWriter::new();
+        header().write(&mut w);
+        let bytes = w.into_bytes();
+
+        let mut magic = bytes.clone();
+        magic[0] = b'X';
+        assert!(matches!(SaveStateHeader::read(&mut StateReader::new(&magic)), Err(SaveStateError::InvalidMagic)));
+
+        let mut version = bytes.clone();
+        version[4] = version[4].wrapping_add(1);
+        assert!(matches!(SaveStateHeader::read(&mut StateReader::new(&version)), Err(SaveStateError::UnsupportedVersion(_))));
+
+        // Thumbnail size is at offset 16, a huge size must not be allocated
+        let mut size = bytes.clone();
+        size[16..20].copy_from_slice(&[0xFF; 4]);
+        assert!(matches!(SaveStateHeader::read(&mut StateReader::new(&size)), Err(SaveStateError::InvalidValue(_))));
+    }
+}
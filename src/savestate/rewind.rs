@@ -0,0 +1,240 @@
+use std::collections::VecDeque;
+
+/// A literal run in a delta ends once this many unchanged bytes follow it, shorter gaps are cheaper to keep in the literal
+const MIN_ZERO_RUN: usize = 8;
+
+/// Difference between two snapshots, turns one snapshot into the other with `Delta::apply`
+///
+/// The snapshots are XORed and the result is stored as alternating runs: a `u32` count of unchanged bytes,
+/// a `u32` count of changed bytes and then the XORed changed bytes. Most of the machine does not change
+/// from one frame to the next, so deltas are usually much smaller than full snapshots
+struct Delta {
+    /// Length of the snapshot this delta produces
+    len: usize,
+    runs: Vec<u8>,
+}
+
+impl Delta {
+    /// Returns the delta that turns `from` into `to`, the snapshots don't need to have the same length
+    fn encode(from: &[u8], to: &[u8]) -> Delta {
+        let xor = |i: usize| to[i] ^ from.get(i).copied().unwrap_or(0);
+        let mut runs = Vec::new();
+
+        let mut i = 0;
+        while i < to.len() {
+            let zero_start = i;
+            while i < to.len() && xor(i) == 0 {
+                i += 1;
+            }
+
+            let literal_start = i;
+            let mut zero_run = 0;
+            while i < to.len() && zero_run < MIN_ZERO_RUN {
+                zero_run = if xor(i) == 0 { zero_run + 1 } else { 0 };
+                i += 1;
+            }
+            // Unchanged bytes at the end of the literal belong to the next zero run
+            i -= zero_run;
+
+            runs.extend_from_slice(&((literal_start - zero_start) as u32).to_le_bytes());
+            runs.extend_from_slice(&((i - literal_start) as u32).to_le_bytes());
+            runs.extend((literal_start..i).map(xor));
+        }
+
+        Delta { len: to.len(), runs }
+    }
+
+    /// Applies this delta to the snapshot it was encoded from
+    fn apply(&self, from: &[u8]) -> Vec<u8> {
+        let mut out = from.to_vec();
+        out.resize(self.len, 0);
+
+        let read_u32 = |at: usize| u32::from_le_bytes(self.runs[at..at + 4].try_into().unwrap()) as usize;
+        let (mut pos, mut at) = (0, 0);
+        while at < self.runs.len() {
+            pos += read_u32(at);
+            let literal_len = read_u32(at + 4);
+            at += 8;
+            for (byte, x) in out[pos..pos + literal_len].iter_mut().zip(&self.runs[at..at + literal_len]) {
+                *byte ^= x;
+            }
+            pos += literal_len;
+            at += literal_len;
+        }
+
+        out
+    }
+
+    /// Bytes of memory used by this delta
+    fn size(&self) -> usize {
+        self.runs.len()
+    }
+}
+
+/// Ring buffer of machine snapshots for rewinding
+///
+/// Only the newest snapshot is kept in full. Every older snapshot is stored as a delta against the snapshot after it,
+/// so stepping back undoes one delta at a time. When the buffer grows beyond its memory budget the oldest deltas are dropped
+pub struct RewindBuffer {
+    /// A snapshot is captured every `interval` frames
+    interval: usize,
+    /// Maximum number of bytes used by the newest snapshot and all deltas together
+    budget: usize,
+    frames_since_capture: usize,
+    newest: Option<Vec<u8>>,
+    /// Deltas from oldest to newest, the last delta turns `newest` into the snapshot before it
+    deltas: VecDeque<Delta>,
+    /// Total size of `deltas`
+    delta_bytes: usize,
+}
+
+impl RewindBuffer {
+    /// Creates a buffer that captures a snapshot every `interval` frames and uses at most `budget` bytes
+    pub fn new(interval: usize, budget: usize) -> RewindBuffer {
+        RewindBuffer {
+            interval: interval.max(1),
+            budget,
+            frames_since_capture: 0,
+            newest: None,
+            deltas: VecDeque::new(),
+            delta_bytes: 0,
+        }
+    }
+
+    /// Counts a frame, returns true if a snapshot should be captured and given to `RewindBuffer::push`
+    pub fn advance_frame(&mut self) -> bool {
+        self.frames_since_capture += 1;
+        if self.frames_since_capture >= self.interval {
+            self.frames_since_capture = 0;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Adds a snapshot as the newest one, dropping the oldest snapshots if the budget is exceeded
+    pub fn push(&mut self, snapshot: Vec<u8>) {
+        if let Some(previous) = self.newest.take() {
+            let delta = Delta::encode(&snapshot, &previous);
+            self.delta_bytes += delta.size();
+            self.deltas.push_back(delta);
+        }
+        self.newest = Some(snapshot);
+
+        while self.memory_used() > self.budget {
+            match self.deltas.pop_front() {
+                Some(delta) => self.delta_bytes -= delta.size(),
+                None => break,
+            }
+        }
+    }
+
+    /// Removes and returns the newest snapshot, the snapshot before it becomes the newest
+    pub fn pop(&mut self) -> Option<Vec<u8>> {
+        let newest = self.newest.take()?;
+        if let Some(delta) = self.deltas.pop_back() {
+            self.delta_bytes -= delta.size();
+            self.newest = Some(delta.apply(&newest));
+        }
+        self.frames_since_capture = 0;
+        Some(newest)
+    }
+
+    /// Number of snapshots in the buffer
+    pub fn len(&self) -> usize {
+        self.newest.as_ref().map_or(0, |_| 1 + self.deltas.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.newest.is_none()
+    }
+
+    /// Bytes used by all snapshots in the buffer
+    pub fn memory_used(&self) -> usize {
+        self.newest.as_ref().map_or(0, |s| s.len()) + self.delta_bytes
+    }
+
+    /// Size of the newest snapshot, used to allocate the next snapshot up front
+    pub fn snapshot_size_hint(&self) -> usize {
+        self.newest.as_ref().map_or(0, |s| s.len())
+    }
+
+    pub fn clear(&mut self) {
+        self.newest = None;
+        self.deltas.clear();
+        self.delta_bytes = 0;
+        self.frames_since_capture = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Snapshot of `len` bytes where every byte depends on `seed`, only some of them change between seeds
+    fn snapshot(len: usize, seed: u8) -> Vec<u8> {
+        (0..len).map(|i| if i % 97 == 0 { seed.wrapping_add(i as u8) } else { i as u8 }).collect()
+    }
+
+    #[test]
+    fn test_delta_round_trip() {
+        let a = snapshot(4096, 1);
+        let pairs = [
+            (a.clone(), a.clone()),
+            (a.clone(), snapshot(4096, 2)),
+            (a.clone(), snapshot(5000, 3)),
+            (a.clone(), snapshot(100, 4)),
+            (Vec::new(), a.clone()),
+            (a.clone(), Vec::new()),
+        ];
+        for (from, to) in pairs {
+            assert_eq!(Delta::encode(&from, &to).apply(&from), to);
+        }
+    }
+
+    #[test]
+    fn test_delta_is_small_for_small_changes() {
+        let (a, b) = (snapshot(4096, 1), snapshot(4096, 2));
+        assert!(Delta::encode(&a, &b).size() < a.len() / 4);
+        // Identical snapshots need no runs at all
+        assert_eq!(Delta::encode(&a, &a).size(), 8);
+    }
+
+    #[test]
+    fn test_pop_returns_snapshots_newest_first() {
+        let mut rewind = RewindBuffer::new(1, usize::MAX);
+        for seed in 0..10 {
+            rewind.push(snapshot(1024, seed));
+        }
+        assert_eq!(rewind.len(), 10);
+        for seed in (0..10).rev() {
+            assert_eq!(rewind.pop(), Some(snapshot(1024, seed)));
+        }
+        assert!(rewind.is_empty());
+        assert_eq!(rewind.pop(), None);
+    }
+
+    #[test]
+    fn test_budget_drops_oldest_snapshots() {
+        let budget = 2048;
+        let mut rewind = RewindBuffer::new(1, budget);
+        for seed in 0..100 {
+            rewind.push(snapshot(1024, seed));
+            assert!(rewind.memory_used() <= budget);
+        }
+        let kept = rewind.len();
+        assert!(kept > 1 && kept < 100);
+
+        // The snapshots that are left are the newest ones
+        for seed in (100 - kept..100).rev() {
+            assert_eq!(rewind.pop(), Some(snapshot(1024, seed as u8)));
+        }
+    }
+
+    #[test]
+    fn test_capture_interval() {
+        let mut rewind = RewindBuffer::new(3, usize::MAX);
+        let captures: Vec<bool> = (0..6).map(|_| rewind.advance_frame()).collect();
+        assert_eq!(captures, [false, false, true, false, false, true]);
+    }
+}
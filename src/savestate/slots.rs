@@ -0,0 +1,51 @@
+use std::{fs, path::PathBuf};
+
+use super::{SaveStateError, SaveStateHeader, StateReader};
+
+/// Number of quick save slots, slots are numbered `1` to `SLOT_COUNT`
+pub const SLOT_COUNT: usize = 4;
+
+/// Directory all save slots are stored in, every ROM gets its own sub directory
+const SLOT_DIR: &str = "states";
+
+/// Quick save slots of a single ROM, stored as `states/<rom checksum>/slot<n>.state`
+pub struct SaveSlots {
+    dir: PathBuf,
+}
+
+impl SaveSlots {
+    pub fn new(rom_checksum: u16) -> SaveSlots {
+        SaveSlots { dir: PathBuf::from(SLOT_DIR).join(format!("{rom_checksum:04X}")) }
+    }
+
+    /// Returns the path of the file for `slot`, or `None` if the slot does not exist
+    pub fn path(&self, slot: usize) -> Option<PathBuf> {
+        (1..=SLOT_COUNT).contains(&slot).then(|| self.dir.join(format!("slot{slot}.state")))
+    }
+
+    /// Writes a complete save state to `slot`, overwriting what was in it
+    pub fn write(&self, slot: usize, state: &[u8]) -> Result<(), SaveStateError> {
+        let path = self.path(slot).ok_or(SaveStateError::InvalidValue("slot"))?;
+        fs::create_dir_all(&self.dir)?;
+        fs::write(path, state)?;
+        Ok(())
+    }
+
+    /// Reads the complete save state in `slot`
+    pub fn read(&self, slot: usize) -> Result<Vec<u8>, SaveStateError> {
+        let path = self.path(slot).ok_or(SaveStateError::InvalidValue("slot"))?;
+        Ok(fs::read(path)?)
+    }
+
+    /// Reads only the header of `slot`, to show what is in it without loading it
+    pub fn read_header(&self, slot: usize) -> Result<SaveStateHeader, SaveStateError> {
+        SaveStateHeader::read(&mut StateReader::new(&self.read(slot)?))
+    }
+}
+
+/// Request from the frontend to quick save or quick load a slot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlotRequest {
+    Save(usize),
+    Load(usize),
+}